@@ -5,7 +5,7 @@ cd /app
 
 uv pip install --group maturin --system
 
-maturin build --out dist --features mimalloc
+maturin build --out dist --features alloc-mimalloc
 
 uv pip install dist/*.whl --system
 
@@ -1,25 +1,111 @@
 __all__ = (
+    "ConfigStack",
+    "DumpTemplate",
+    "ErrorDetails",
+    "Located",
+    "RawSlice",
+    "Schema",
     "TOMLDecodeError",
     "TOMLDocument",
+    "TOMLDuplicateKeyError",
     "TOMLEncodeError",
+    "TOMLInvalidValueError",
+    "TOMLResourceLimitError",
+    "TOMLSyntaxError",
+    "Token",
+    "TomlWriter",
+    "Watcher",
     "__version__",
+    "add_dependency",
+    "allocator",
+    "array_append",
+    "array_insert",
+    "array_remove",
+    "deep_merge",
+    "digest",
     "dump",
+    "dump_path",
     "dumps",
+    "features",
+    "from_cbor",
+    "from_msgpack",
+    "get_comment",
+    "get_raw",
+    "infer_schema",
+    "iter_array_of_tables",
+    "iter_tables",
     "load",
+    "load_fd",
+    "load_path",
     "load_with_metadata",
     "loads",
+    "loads_chunks",
+    "loads_documents",
+    "loads_front_matter",
+    "position",
+    "remove_dependency",
+    "schema_to_typeddict",
+    "set_error_formatter",
+    "to_cbor",
+    "to_msgpack",
+    "tokenize",
+    "update_file",
+    "watch",
 )
 
 from ._lib import (
+    ErrorDetails,
+    RawSlice,
     TOMLDecodeError,
     TOMLDocument,
+    TOMLDuplicateKeyError,
     TOMLEncodeError,
+    TOMLInvalidValueError,
+    TOMLResourceLimitError,
+    TOMLSyntaxError,
+    Token,
+    Watcher,
+    add_dependency,
+    allocator,
+    array_append,
+    array_insert,
+    array_remove,
+    deep_merge,
+    digest,
     dump,
+    dump_path,
     dumps,
+    features,
+    from_cbor,
+    from_msgpack,
+    get_comment,
+    get_raw,
+    infer_schema,
+    iter_array_of_tables,
+    iter_tables,
     load,
+    load_fd,
+    load_path,
     load_with_metadata,
     loads,
+    loads_chunks,
+    loads_documents,
+    loads_front_matter,
+    position,
+    remove_dependency,
+    schema_to_typeddict,
+    set_error_formatter,
+    to_cbor,
+    to_msgpack,
+    tokenize,
+    update_file,
+    watch,
 )
 from ._toml_rs import (
     _VERSION as __version__,  # noqa: N811
+    ConfigStack,
+    DumpTemplate,
+    Located,
+    Schema,
+    TomlWriter,
 )
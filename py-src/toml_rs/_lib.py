@@ -1,50 +1,657 @@
-from collections.abc import Callable
+import atexit
+import hashlib
+import math
+import os
+import secrets
+import stat
+import sys
+import sysconfig
+from collections.abc import Callable, Iterable, Iterator, Mapping
+from datetime import date, datetime, time, timedelta
+from decimal import Decimal
 from pathlib import Path
-from typing import Any, BinaryIO, Literal, TextIO, TypeAlias
+from typing import (
+    Any,
+    BinaryIO,
+    Literal,
+    NamedTuple,
+    TextIO,
+    TypeAlias,
+    TypedDict,
+    overload,
+)
 
 from ._toml_rs import (
+    _ALLOCATOR,
+    _VERSION,
+    _ArrayTableSplitter,
+    Schema,
     TOMLDocument,
+    Watcher,
+    _add_dependency,
+    _array_insert,
+    _array_remove,
+    _canonicalize,
+    _compress,
+    _decode,
+    _decompress,
+    _deep_merge,
     _dumps,
+    _from_binary,
+    _get_comment,
+    _get_raw,
+    _infer_schema,
+    _iter_tables,
     _loads,
+    _loads_chunks,
+    _parse_comments,
     _parse_metadata_from_string,
+    _position,
+    _read_fd,
+    _remove_dependency,
+    _set_error_formatter,
+    _stop_watchers,
+    _to_binary,
+    _tokenize,
+    _typed_dicts,
+    _update_document,
+    _watch,
 )
 
 TomlVersion: TypeAlias = Literal["1.0.0", "1.1.0"]
+Allocator: TypeAlias = Literal["mimalloc", "snmalloc", "system"]
 ParseFloat: TypeAlias = Callable[[str], Any]
+IntFactory: TypeAlias = Callable[[int], Any]
+ValueHook: TypeAlias = Callable[[tuple[str | int, ...], Any], Any]
+TableHook: TypeAlias = Callable[[str, dict[str, Any]], Any]
+Fallback: TypeAlias = Literal["vars"]
+Compression: TypeAlias = Literal["gzip", "zstd"]
+KeyNormalization: TypeAlias = Literal["nfc", "none"]
+LeapSeconds: TypeAlias = Literal["error", "clamp"]
+Datetimes: TypeAlias = Literal["objects", "iso_strings"]
+SurrogatePolicy: TypeAlias = Literal["error", "replace", "backslashreplace"]
+EscapeForm: TypeAlias = Literal["u", "U", "x"]
+ArrayMerge: TypeAlias = Literal["replace", "append", "unique"]
+SortArrays: TypeAlias = bool | Literal["strings"] | Iterable[str]
+IndentChar: TypeAlias = int | Literal["\t"]
+DatetimeFormat: TypeAlias = Literal["date", "local", "utc"]
+ArrayStyle: TypeAlias = Literal["auto", "one_per_line"]
+NanosecondPolicy: TypeAlias = Literal["keep", "truncate"]
+TokenKind: TypeAlias = Literal[
+    "key",
+    "string",
+    "number",
+    "boolean",
+    "datetime",
+    "comment",
+    "punctuation",
+    "whitespace",
+    "newline",
+    "error",
+]
 
 DEFAULT_TOML_VERSION: TomlVersion = "1.0.0"
+DEFAULT_LINE_WIDTH = 88
+COMPRESSION_SUFFIXES: dict[str, Compression] = {".gz": "gzip", ".zst": "zstd"}
+
+
+class CommentInfo(TypedDict):
+    leading: str | None
+    trailing: str | None
+
+
+class FloatFormat(TypedDict, total=False):
+    precision: int
+    fixed: bool
+
+
+class Token(NamedTuple):
+    kind: TokenKind
+    text: str
+    start: int
+    end: int
+
+
+class RawSlice(NamedTuple):
+    text: str
+    start: int
+    end: int
 
 
-def load(
+class ErrorDetails(NamedTuple):
+    message: str
+    span: tuple[int, int]
+    expected: list[str]
+    path: list[str]
+
+
+class Features(TypedDict):
+    version: str
+    toml_versions: tuple[TomlVersion, ...]
+    allocator: Allocator
+    free_threading: bool
+
+
+Mapping.register(TOMLDocument)
+
+
+def load(  # noqa: PLR0913
     fp: BinaryIO,
     /,
     *,
     parse_float: ParseFloat = float,
     toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    preserve_format: bool = False,
+    strict: bool = False,
+    allow_nan: bool = True,
+    only: Iterable[str] | None = None,
+    intern_strings: bool = False,
+    normalize_keys: KeyNormalization = "none",
+    leap_seconds: LeapSeconds = "error",
+    datetimes: Datetimes = "objects",
+    value_hook: ValueHook | None = None,
+    max_string_length: int | None = None,
+    max_items: int | None = None,
+    max_depth: int | None = None,
+    int_factory: IntFactory | None = None,
+    key_by: Mapping[str, str] | None = None,
+    locations: bool = False,
+    table_hook: TableHook | None = None,
+    encoding: str | None = None,
 ) -> dict[str, Any]:
     toml_bytes = fp.read()
-    try:
-        toml_str = toml_bytes.decode()
-    except AttributeError:
+    if isinstance(toml_bytes, str):
         msg = "File must be opened in binary mode, e.g. use `open('foo.toml', 'rb')`"
-        raise TypeError(msg) from None
-    return loads(toml_str, parse_float=parse_float, toml_version=toml_version)
+        raise TypeError(msg)
+    name = getattr(fp, "name", None)
+    return _loads_source(
+        toml_bytes,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        preserve_format=preserve_format,
+        strict=strict,
+        allow_nan=allow_nan,
+        only=only,
+        intern_strings=intern_strings,
+        normalize_keys=normalize_keys,
+        leap_seconds=leap_seconds,
+        datetimes=datetimes,
+        value_hook=value_hook,
+        max_string_length=max_string_length,
+        max_items=max_items,
+        max_depth=max_depth,
+        int_factory=int_factory,
+        key_by=key_by,
+        locations=locations,
+        table_hook=table_hook,
+        filename=name if isinstance(name, str) else None,
+        encoding=encoding,
+    )
 
 
+def load_fd(
+    fd: int,
+    /,
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    strict: bool = False,
+    allow_nan: bool = True,
+) -> dict[str, Any]:
+    if sys.platform == "win32":
+        with os.fdopen(fd, "rb", closefd=False) as file:
+            toml_str = _decode(file.read(), None)
+    else:
+        toml_str = _read_fd(fd)
+    return loads(
+        toml_str,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        strict=strict,
+        allow_nan=allow_nan,
+    )
+
+
+def load_path(  # noqa: PLR0913
+    path: str | os.PathLike[str],
+    /,
+    *,
+    compression: Compression | None = None,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    strict: bool = False,
+    allow_nan: bool = True,
+    locations: bool = False,
+    encoding: str | None = None,
+) -> dict[str, Any]:
+    path = Path(path)
+    toml_bytes = path.read_bytes()
+    compression = compression or _detect_compression(path)
+    source: str | bytes = toml_bytes
+    if compression is not None:
+        source = _decompress(toml_bytes, compression, encoding)
+    return _loads_source(
+        source,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        strict=strict,
+        allow_nan=allow_nan,
+        locations=locations,
+        filename=str(path),
+        encoding=encoding,
+    )
+
+
+def _detect_compression(path: Path) -> Compression | None:
+    return COMPRESSION_SUFFIXES.get(path.suffix)
+
+
+@overload
 def loads(
     s: str,
     /,
     *,
+    parse_float: ParseFloat = ...,
+    toml_version: TomlVersion = ...,
+    preserve_format: bool = ...,
+    strict: bool = ...,
+    allow_nan: bool = ...,
+    only: Iterable[str] | None = ...,
+    intern_strings: bool = ...,
+    normalize_keys: KeyNormalization = ...,
+    leap_seconds: LeapSeconds = ...,
+    datetimes: Datetimes = ...,
+    value_hook: ValueHook | None = ...,
+    max_string_length: int | None = ...,
+    max_items: int | None = ...,
+    max_depth: int | None = ...,
+    int_factory: IntFactory | None = ...,
+    key_by: Mapping[str, str] | None = ...,
+    locations: bool = ...,
+    table_hook: TableHook | None = ...,
+    filename: str | None = ...,
+    schema: Schema | None = ...,
+    with_comments: Literal[False] = False,
+) -> dict[str, Any]: ...
+
+
+@overload
+def loads(
+    s: str,
+    /,
+    *,
+    parse_float: ParseFloat = ...,
+    toml_version: TomlVersion = ...,
+    preserve_format: bool = ...,
+    strict: bool = ...,
+    allow_nan: bool = ...,
+    only: Iterable[str] | None = ...,
+    intern_strings: bool = ...,
+    normalize_keys: KeyNormalization = ...,
+    leap_seconds: LeapSeconds = ...,
+    datetimes: Datetimes = ...,
+    value_hook: ValueHook | None = ...,
+    max_string_length: int | None = ...,
+    max_items: int | None = ...,
+    max_depth: int | None = ...,
+    int_factory: IntFactory | None = ...,
+    key_by: Mapping[str, str] | None = ...,
+    locations: bool = ...,
+    table_hook: TableHook | None = ...,
+    filename: str | None = ...,
+    schema: Schema | None = ...,
+    with_comments: Literal[True],
+) -> tuple[dict[str, Any], dict[str, CommentInfo]]: ...
+
+
+def loads(  # noqa: PLR0913
+    s: str,
+    /,
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    preserve_format: bool = False,
+    strict: bool = False,
+    allow_nan: bool = True,
+    only: Iterable[str] | None = None,
+    intern_strings: bool = False,
+    normalize_keys: KeyNormalization = "none",
+    leap_seconds: LeapSeconds = "error",
+    datetimes: Datetimes = "objects",
+    value_hook: ValueHook | None = None,
+    max_string_length: int | None = None,
+    max_items: int | None = None,
+    max_depth: int | None = None,
+    int_factory: IntFactory | None = None,
+    key_by: Mapping[str, str] | None = None,
+    locations: bool = False,
+    table_hook: TableHook | None = None,
+    filename: str | None = None,
+    schema: Schema | None = None,
+    with_comments: bool = False,
+) -> dict[str, Any] | tuple[dict[str, Any], dict[str, CommentInfo]]:
+    if not isinstance(s, str):
+        msg = f"Expected str object, not '{type(s).__qualname__}'"
+        raise TypeError(msg)
+    data = _loads_source(
+        s,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        preserve_format=preserve_format,
+        strict=strict,
+        allow_nan=allow_nan,
+        only=only,
+        intern_strings=intern_strings,
+        normalize_keys=normalize_keys,
+        leap_seconds=leap_seconds,
+        datetimes=datetimes,
+        value_hook=value_hook,
+        max_string_length=max_string_length,
+        max_items=max_items,
+        max_depth=max_depth,
+        int_factory=int_factory,
+        key_by=key_by,
+        locations=locations,
+        filename=filename,
+        table_hook=table_hook,
+    )
+    if schema is not None:
+        schema.validate(data)
+    if with_comments:
+        return data, _parse_comments(s, toml_version=toml_version)
+    return data
+
+
+# Parses text, or bytes as read from a file, which are decoded without a copy
+# when they are UTF-8.
+def _loads_source(  # noqa: PLR0913
+    source: str | bytes,
+    /,
+    *,
     parse_float: ParseFloat = float,
     toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    preserve_format: bool = False,
+    strict: bool = False,
+    allow_nan: bool = True,
+    only: Iterable[str] | None = None,
+    intern_strings: bool = False,
+    normalize_keys: KeyNormalization = "none",
+    leap_seconds: LeapSeconds = "error",
+    datetimes: Datetimes = "objects",
+    value_hook: ValueHook | None = None,
+    max_string_length: int | None = None,
+    max_items: int | None = None,
+    max_depth: int | None = None,
+    int_factory: IntFactory | None = None,
+    key_by: Mapping[str, str] | None = None,
+    locations: bool = False,
+    table_hook: TableHook | None = None,
+    filename: str | None = None,
+    encoding: str | None = None,
 ) -> dict[str, Any]:
+    return _loads(
+        source,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        preserve_format=preserve_format,
+        strict=strict,
+        allow_nan=allow_nan,
+        only=None if only is None else list(only),
+        intern_strings=intern_strings,
+        normalize_keys=normalize_keys,
+        leap_seconds=leap_seconds,
+        datetimes=datetimes,
+        value_hook=value_hook,
+        max_string_length=max_string_length,
+        max_items=max_items,
+        max_depth=max_depth,
+        int_factory=int_factory,
+        key_by=None if key_by is None else list(key_by.items()),
+        locations=locations,
+        filename=filename,
+        table_hook=table_hook,
+        encoding=encoding,
+    )
+
+
+def iter_tables(
+    s: str,
+    /,
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> Iterator[tuple[str, Any]]:
     if not isinstance(s, str):
         msg = f"Expected str object, not '{type(s).__qualname__}'"
         raise TypeError(msg)
-    return _loads(s, parse_float=parse_float, toml_version=toml_version)
+    return _iter_tables(s, parse_float=parse_float, toml_version=toml_version)
+
+
+def loads_chunks(
+    chunks: Iterable[str],
+    /,
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    strict: bool = False,
+    allow_nan: bool = True,
+) -> dict[str, Any]:
+    return _loads_chunks(
+        chunks,
+        parse_float=parse_float,
+        toml_version=toml_version,
+        strict=strict,
+        allow_nan=allow_nan,
+    )
+
+
+def loads_front_matter(
+    text: str,
+    /,
+    delimiter: str = "+++",
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> tuple[dict[str, Any], str]:
+    lines = text.splitlines(keepends=True)
+    if not lines or lines[0].rstrip("\r\n") != delimiter:
+        return {}, text
+
+    start = end = len(lines[0])
+    for line in lines[1:]:
+        if line.rstrip("\r\n") == delimiter:
+            break
+        end += len(line)
+    else:
+        msg = f"Front matter is not closed by a {delimiter!r} line"
+        raise TOMLSyntaxError(msg, text, len(text))
+
+    # The opening delimiter is replaced by an empty line, so line numbers in
+    # error messages match the original text.
+    try:
+        data = loads(
+            "\n" + text[start:end],
+            parse_float=parse_float,
+            toml_version=toml_version,
+        )
+    except TOMLDecodeError as exc:
+        raise type(exc)(exc.msg, text, exc.pos + start - 1) from None
+
+    closing = text.find("\n", end)
+    return data, "" if closing == -1 else text[closing + 1 :]
+
+
+def loads_documents(
+    text: str,
+    /,
+    separator: str = "+++",
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> list[dict[str, Any]]:
+    bounds = []
+    start = offset = 0
+    for line in text.splitlines(keepends=True):
+        if line.rstrip("\r\n") == separator:
+            bounds.append((start, offset))
+            start = offset + len(line)
+        offset += len(line)
+    bounds.append((start, len(text)))
+
+    documents = []
+    for start, end in bounds:
+        document = text[start:end]
+        try:
+            documents.append(
+                loads(document, parse_float=parse_float, toml_version=toml_version),
+            )
+        except TOMLDecodeError:
+            # Parsed again after as many empty lines as precede the document,
+            # so line numbers in the error message match the whole text.
+            padding = "\n" * text.count("\n", 0, start)
+            try:
+                loads(
+                    padding + document,
+                    parse_float=parse_float,
+                    toml_version=toml_version,
+                )
+            except TOMLDecodeError as exc:
+                raise type(exc)(exc.msg, text, exc.pos - len(padding) + start) from None
+            raise
+    return documents
+
+
+def get_comment(
+    text: str,
+    path: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> CommentInfo | None:
+    comments = _get_comment(text, path, toml_version=toml_version)
+    if comments is None:
+        return None
+    leading, trailing = comments
+    return CommentInfo(leading=leading, trailing=trailing)
+
+
+# Where byte `offset` of `text` is, such as the `pos` of a TOMLDecodeError,
+# as a 1-based line and column counted like the parser's own messages count
+# them.
+def position(text: str, offset: int, /) -> tuple[int, int]:
+    return _position(text, offset)
+
+
+def get_raw(
+    text: str,
+    path: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> RawSlice | None:
+    span = _get_raw(text, path, toml_version=toml_version)
+    if span is None:
+        return None
+    start, end = span
+    return RawSlice(text[start:end], start, end)
+
+
+def tokenize(
+    text: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> list[Token]:
+    return [
+        Token(kind, text[start:end], start, end)
+        for kind, start, end in _tokenize(text, toml_version=toml_version)
+    ]
+
+
+def iter_array_of_tables(
+    source: str | Path | TextIO,
+    name: str,
+    /,
+    *,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> Iterator[dict[str, Any]]:
+    splitter = _ArrayTableSplitter(name, toml_version=toml_version)
+
+    if isinstance(source, str):
+        lines: Iterable[str] = source.splitlines(keepends=True)
+        yield from _iter_elements(
+            lines, splitter, lambda: source, parse_float, toml_version
+        )
+    elif isinstance(source, Path):
+        with source.open(encoding="utf-8") as file:
+            yield from _iter_elements(
+                file,
+                splitter,
+                lambda: source.read_text(encoding="utf-8"),
+                parse_float,
+                toml_version,
+            )
+    else:
+        yield from _iter_elements(source, splitter, None, parse_float, toml_version)
+
+
+def _iter_elements(
+    lines: Iterable[str],
+    splitter: _ArrayTableSplitter,
+    document: Callable[[], str] | None,
+    parse_float: ParseFloat,
+    toml_version: TomlVersion,
+) -> Iterator[dict[str, Any]]:
+    path = splitter.path
+    for element, lineno, offset in _split_elements(lines, splitter):
+        try:
+            table: Any = loads(
+                element, parse_float=parse_float, toml_version=toml_version
+            )
+        except TOMLDecodeError:
+            _raise_in_document(
+                element, lineno, offset, document, parse_float, toml_version
+            )
+            raise
+        for key in path:
+            table = table[key]
+        yield table[0]
+
+
+def _split_elements(
+    lines: Iterable[str],
+    splitter: _ArrayTableSplitter,
+) -> Iterator[tuple[str, int, int]]:
+    for line in lines:
+        yield from splitter.feed(line)
+    yield from splitter.finish()
 
 
-def dump(
+def _raise_in_document(  # noqa: PLR0913
+    element: str,
+    lineno: int,
+    offset: int,
+    document: Callable[[], str] | None,
+    parse_float: ParseFloat,
+    toml_version: TomlVersion,
+) -> None:
+    # Parsed again behind as many lines as come before the element, so the
+    # message gives the line and column in the document.
+    try:
+        loads("\n" * lineno + element, parse_float=parse_float, toml_version=toml_version)
+    except TOMLDecodeError as err:
+        # A stream is not kept, so its text before the element is blanked
+        # out; `doc`, `pos`, `lineno` and `colno` still agree.
+        doc = (
+            " " * (offset - lineno) + "\n" * lineno + element
+            if document is None
+            else document()
+        )
+        raise type(err)(err.msg, doc, offset + err.pos - lineno) from None
+
+
+def dump(  # noqa: PLR0913
     obj: Any,
     /,
     file: str | Path | TextIO,
@@ -52,12 +659,46 @@ def dump(
     *,
     pretty: bool = False,
     toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    fallback: Fallback | None = None,
+    strict: bool = False,
+    quote_keys: bool = False,
+    surrogates: SurrogatePolicy = "error",
+    escape_form: EscapeForm = "u",
+    sort_arrays: SortArrays = False,
+    key_order: Mapping[str, Iterable[str]] | None = None,
+    encode_hook: ValueHook | None = None,
+    max_depth: int | None = None,
+    indent_char: IndentChar = 4,
+    preserve_order: bool = False,
+    comments: Mapping[str, str] | None = None,
+    float_formats: Mapping[str, FloatFormat] | None = None,
+    datetime_formats: Mapping[str, DatetimeFormat] | None = None,
+    parse_datetime_strings: bool = False,
+    array_style: ArrayStyle = "auto",
+    nanoseconds: NanosecondPolicy = "keep",
 ) -> int:
     toml_str = _dumps(
         obj,
         inline_tables=inline_tables,
         pretty=pretty,
         toml_version=toml_version,
+        fallback=fallback,
+        strict=strict,
+        quote_keys=quote_keys,
+        surrogates=surrogates,
+        escape_form=escape_form,
+        sort_arrays=_sort_arrays(sort_arrays),
+        key_order=_key_order(key_order),
+        encode_hook=encode_hook,
+        max_depth=max_depth,
+        indent_char=indent_char,
+        preserve_order=preserve_order,
+        comments=None if comments is None else dict(comments),
+        float_formats=None if float_formats is None else dict(float_formats),
+        datetime_formats=None if datetime_formats is None else dict(datetime_formats),
+        parse_datetime_strings=parse_datetime_strings,
+        array_style=array_style,
+        nanoseconds=nanoseconds,
     )
     if isinstance(file, str):
         file = Path(file)
@@ -67,22 +708,446 @@ def dump(
     return file.write(toml_str)
 
 
-def dumps(
+def dump_path(  # noqa: PLR0913
     obj: Any,
+    path: str | os.PathLike[str],
     /,
     inline_tables: set[str] | None = None,
     *,
+    atomic: bool = True,
+    compression: Compression | None = None,
     pretty: bool = False,
     toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    fallback: Fallback | None = None,
+    strict: bool = False,
+    quote_keys: bool = False,
+    surrogates: SurrogatePolicy = "error",
+    escape_form: EscapeForm = "u",
+    sort_arrays: SortArrays = False,
+    key_order: Mapping[str, Iterable[str]] | None = None,
+    encode_hook: ValueHook | None = None,
+    max_depth: int | None = None,
+    indent_char: IndentChar = 4,
+    preserve_order: bool = False,
+    comments: Mapping[str, str] | None = None,
+    float_formats: Mapping[str, FloatFormat] | None = None,
+    datetime_formats: Mapping[str, DatetimeFormat] | None = None,
+    parse_datetime_strings: bool = False,
+    array_style: ArrayStyle = "auto",
+    nanoseconds: NanosecondPolicy = "keep",
+) -> int:
+    path = Path(path)
+    toml_str = dumps(
+        obj,
+        inline_tables,
+        pretty=pretty,
+        toml_version=toml_version,
+        fallback=fallback,
+        strict=strict,
+        quote_keys=quote_keys,
+        surrogates=surrogates,
+        escape_form=escape_form,
+        sort_arrays=sort_arrays,
+        key_order=key_order,
+        encode_hook=encode_hook,
+        max_depth=max_depth,
+        indent_char=indent_char,
+        preserve_order=preserve_order,
+        comments=comments,
+        float_formats=float_formats,
+        datetime_formats=datetime_formats,
+        parse_datetime_strings=parse_datetime_strings,
+        array_style=array_style,
+        nanoseconds=nanoseconds,
+    )
+    compression = compression or _detect_compression(path)
+    if compression is None:
+        toml_bytes = toml_str.encode()
+    else:
+        toml_bytes = _compress(toml_str, compression)
+
+    if not atomic:
+        return path.write_bytes(toml_bytes)
+
+    _write_atomic(path, toml_bytes)
+    return len(toml_bytes)
+
+
+def _write_atomic(path: Path, data: bytes) -> None:
+    # A symlinked target keeps its link; the file it points to is replaced.
+    path = path.resolve()
+    # Written next to the target, so the final rename never crosses filesystems
+    # and readers only ever see the old or the new file.
+    fd, tmp_path = _create_temp(path)
+    try:
+        with os.fdopen(fd, "wb") as file:
+            file.write(data)
+            file.flush()
+            os.fsync(file.fileno())
+        if path.exists():
+            tmp_path.chmod(stat.S_IMODE(path.stat().st_mode))
+        tmp_path.replace(path)
+    except BaseException:
+        tmp_path.unlink(missing_ok=True)
+        raise
+
+    # The rename is only durable once the directory entry is on disk too.
+    if sys.platform != "win32":
+        dir_fd = os.open(path.parent, os.O_RDONLY)
+        try:
+            os.fsync(dir_fd)
+        finally:
+            os.close(dir_fd)
+
+
+def _create_temp(path: Path) -> tuple[int, Path]:
+    # Created with mode 0o666 like open() does, so the kernel applies the
+    # umask to a file that is new.
+    flags = os.O_CREAT | os.O_EXCL | os.O_WRONLY | getattr(os, "O_BINARY", 0)
+    while True:
+        tmp_path = path.with_name(f".{path.name}.{secrets.token_hex(8)}.tmp")
+        try:
+            return os.open(tmp_path, flags, 0o666), tmp_path
+        except FileExistsError:
+            continue
+
+
+def update_file(
+    path: str | os.PathLike[str],
+    changes: Mapping[str, Any],
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> bool:
+    path = Path(path)
+    # Read as bytes so line endings survive untouched.
+    toml_string = path.read_bytes().decode()
+    updated = _update_document(toml_string, dict(changes), toml_version=toml_version)
+
+    if updated == toml_string:
+        return False
+
+    _write_atomic(path, updated.encode())
+    return True
+
+
+def array_append(
+    text: str,
+    path: str,
+    value: Any,
+    /,
+    *,
+    line_width: int = DEFAULT_LINE_WIDTH,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _array_insert(text, path, None, value, line_width, toml_version=toml_version)
+
+
+def array_insert(  # noqa: PLR0913
+    text: str,
+    path: str,
+    index: int,
+    value: Any,
+    /,
+    *,
+    line_width: int = DEFAULT_LINE_WIDTH,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _array_insert(text, path, index, value, line_width, toml_version=toml_version)
+
+
+def array_remove(
+    text: str,
+    path: str,
+    value: Any,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _array_remove(text, path, value, toml_version=toml_version)
+
+
+def add_dependency(
+    text: str,
+    requirement: str,
+    /,
+    *,
+    group: str | None = None,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _add_dependency(text, requirement, group, toml_version=toml_version)
+
+
+def remove_dependency(
+    text: str,
+    requirement: str,
+    /,
+    *,
+    group: str | None = None,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _remove_dependency(text, requirement, group, toml_version=toml_version)
+
+
+def dumps(  # noqa: PLR0913
+    obj: Any,
+    /,
+    inline_tables: set[str] | None = None,
+    *,
+    pretty: bool = False,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+    fallback: Fallback | None = None,
+    strict: bool = False,
+    quote_keys: bool = False,
+    surrogates: SurrogatePolicy = "error",
+    escape_form: EscapeForm = "u",
+    sort_arrays: SortArrays = False,
+    key_order: Mapping[str, Iterable[str]] | None = None,
+    encode_hook: ValueHook | None = None,
+    max_depth: int | None = None,
+    indent_char: IndentChar = 4,
+    preserve_order: bool = False,
+    comments: Mapping[str, str] | None = None,
+    float_formats: Mapping[str, FloatFormat] | None = None,
+    datetime_formats: Mapping[str, DatetimeFormat] | None = None,
+    parse_datetime_strings: bool = False,
+    array_style: ArrayStyle = "auto",
+    nanoseconds: NanosecondPolicy = "keep",
+    verify: bool = False,
 ) -> str:
-    return _dumps(
+    text = _dumps(
         obj,
         inline_tables=inline_tables,
         pretty=pretty,
         toml_version=toml_version,
+        fallback=fallback,
+        strict=strict,
+        quote_keys=quote_keys,
+        surrogates=surrogates,
+        escape_form=escape_form,
+        sort_arrays=_sort_arrays(sort_arrays),
+        key_order=_key_order(key_order),
+        encode_hook=encode_hook,
+        max_depth=max_depth,
+        indent_char=indent_char,
+        preserve_order=preserve_order,
+        comments=None if comments is None else dict(comments),
+        float_formats=None if float_formats is None else dict(float_formats),
+        datetime_formats=None if datetime_formats is None else dict(datetime_formats),
+        parse_datetime_strings=parse_datetime_strings,
+        array_style=array_style,
+        nanoseconds=nanoseconds,
+    )
+
+    if verify:
+        reparsed = loads(text, toml_version=toml_version)
+        mismatch = _round_trip_mismatch(obj, reparsed, ())
+        if mismatch is not None:
+            path, reason = mismatch
+            where = ".".join(path) or "document root"
+            msg = f"dumps output does not round-trip at {where}: {reason}"
+            raise TOMLEncodeError(msg)
+
+    return text
+
+
+def _sort_arrays(sort_arrays: SortArrays) -> str | set[str] | None:
+    if sort_arrays is True:
+        return "all"
+    if sort_arrays is False:
+        return None
+    if isinstance(sort_arrays, str):
+        return sort_arrays
+    return set(sort_arrays)
+
+
+def _key_order(
+    key_order: Mapping[str, Iterable[str]] | None,
+) -> dict[str, list[str]] | None:
+    if key_order is None:
+        return None
+    return {path: list(keys) for path, keys in key_order.items()}
+
+
+# Values are compared by what they mean after a trip through TOML: tuples
+# come back as lists, decimals as floats, offsets are kept to the minute,
+# NaN equals NaN and zeros keep their sign. Objects only dumps knows how to
+# convert are not checked.
+def _round_trip_mismatch(  # noqa: C901, PLR0911, PLR0912
+    expected: Any,
+    actual: Any,
+    path: tuple[str, ...],
+) -> tuple[tuple[str, ...], str] | None:
+    if isinstance(expected, Mapping):
+        if not isinstance(actual, dict):
+            return path, f"expected a table, got {actual!r}"
+        if set(expected) != set(actual):
+            return path, f"keys {sorted(expected)} became {sorted(actual)}"
+        for key, value in expected.items():
+            mismatch = _round_trip_mismatch(value, actual[key], (*path, key))
+            if mismatch is not None:
+                return mismatch
+        return None
+
+    if isinstance(expected, (list, tuple)):
+        if not isinstance(actual, list) or len(actual) != len(expected):
+            return path, f"expected {len(expected)} items, got {actual!r}"
+        for index, (item, got) in enumerate(zip(expected, actual, strict=True)):
+            mismatch = _round_trip_mismatch(item, got, (*path, str(index)))
+            if mismatch is not None:
+                return mismatch
+        return None
+
+    if isinstance(expected, bool):
+        same = isinstance(actual, bool) and actual == expected
+    elif isinstance(expected, str):
+        same = isinstance(actual, str) and actual == expected
+    elif isinstance(expected, int):
+        same = (
+            isinstance(actual, int)
+            and not isinstance(actual, bool)
+            and actual == expected
+        )
+    elif isinstance(expected, (float, Decimal)):
+        number = float(expected)
+        # `-0.0 == 0.0`, so the sign is compared on its own.
+        same = isinstance(actual, float) and (
+            (actual == number and math.copysign(1, actual) == math.copysign(1, number))
+            or (math.isnan(actual) and math.isnan(number))
+        )
+    elif isinstance(expected, datetime):
+        same = isinstance(actual, datetime) and _same_datetime(expected, actual)
+    elif isinstance(expected, date):
+        same = type(actual) is date and actual == expected
+    elif isinstance(expected, time):
+        same = isinstance(actual, time) and actual == expected.replace(tzinfo=None)
+    else:
+        return None
+
+    return None if same else (path, f"{expected!r} became {actual!r}")
+
+
+def _same_datetime(expected: datetime, actual: datetime) -> bool:
+    if actual.replace(tzinfo=None) != expected.replace(tzinfo=None):
+        return False
+    expected_offset = expected.utcoffset()
+    actual_offset = actual.utcoffset()
+    if expected_offset is None or actual_offset is None:
+        return expected_offset is actual_offset
+    return abs(expected_offset - actual_offset) < timedelta(minutes=1)
+
+
+def to_msgpack(
+    text: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> bytes:
+    return _to_binary(text, "msgpack", toml_version=toml_version)
+
+
+def from_msgpack(
+    data: bytes,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _from_binary(data, "msgpack", toml_version=toml_version)
+
+
+def to_cbor(
+    text: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> bytes:
+    return _to_binary(text, "cbor", toml_version=toml_version)
+
+
+def from_cbor(
+    data: bytes,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    return _from_binary(data, "cbor", toml_version=toml_version)
+
+
+def digest(
+    s: str,
+    /,
+    algorithm: str = "sha256",
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    canonical = _canonicalize(loads(s, toml_version=toml_version))
+    return hashlib.new(algorithm, canonical.encode()).hexdigest()
+
+
+def infer_schema(
+    texts: str | Iterable[str],
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> dict[str, Any]:
+    if isinstance(texts, str):
+        texts = [texts]
+    return _infer_schema([loads(text, toml_version=toml_version) for text in texts])
+
+
+def schema_to_typeddict(schema: Mapping[str, Any], /, name: str = "Config") -> str:
+    return _typed_dicts(dict(schema), name=name)
+
+
+def deep_merge(
+    base: Mapping[str, Any],
+    overlay: Mapping[str, Any],
+    /,
+    *,
+    arrays: ArrayMerge = "replace",
+) -> dict[str, Any]:
+    return _deep_merge(dict(base), dict(overlay), arrays=arrays)
+
+
+def allocator() -> Allocator:
+    return _ALLOCATOR
+
+
+def features() -> Features:
+    return {
+        "version": _VERSION,
+        "toml_versions": ("1.0.0", "1.1.0"),
+        "allocator": _ALLOCATOR,
+        "free_threading": bool(sysconfig.get_config_var("Py_GIL_DISABLED")),
+    }
+
+
+# A watcher thread must not outlive the interpreter it calls back into.
+atexit.register(_stop_watchers)
+
+
+def watch(
+    path: str | os.PathLike[str],
+    callback: Callable[[dict[str, Any] | None, Exception | None], object],
+    /,
+    *,
+    debounce: float = 0.1,
+    parse_float: ParseFloat = float,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> Watcher:
+    return _watch(
+        path,
+        callback,
+        parse_float=parse_float,
+        debounce=debounce,
+        toml_version=toml_version,
     )
 
 
+def set_error_formatter(formatter: Callable[[ErrorDetails], str] | None, /) -> None:
+    _set_error_formatter(formatter)
+
+
 def load_with_metadata(
     toml: str | BinaryIO,
     /,
@@ -127,6 +1192,22 @@ class TOMLDecodeError(ValueError):
         )  # fmt: off
 
 
+class TOMLSyntaxError(TOMLDecodeError):
+    pass
+
+
+class TOMLInvalidValueError(TOMLDecodeError):
+    pass
+
+
+class TOMLDuplicateKeyError(TOMLDecodeError):
+    pass
+
+
+class TOMLResourceLimitError(TOMLDecodeError):
+    pass
+
+
 class TOMLEncodeError(TypeError):
     def __init__(self, msg: str, *args: Any) -> None:
         msg = msg.rstrip()
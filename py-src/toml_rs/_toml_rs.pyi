@@ -1,10 +1,36 @@
-from collections.abc import Callable
-from typing import Any, Literal, Protocol, TypeAlias, TypedDict
+from collections.abc import (
+    Callable,
+    ItemsView,
+    Iterable,
+    Iterator,
+    KeysView,
+    Mapping,
+    ValuesView,
+)
+from os import PathLike
+from typing import Any, Literal, Protocol, TextIO, TypeAlias, TypedDict
 
 _VERSION: str
+_ALLOCATOR: Literal["mimalloc", "snmalloc", "system"]
 
 TomlVersion: TypeAlias = Literal["1.0.0", "1.1.0"]
 ParseFloat: TypeAlias = Callable[[str], Any]
+IntFactory: TypeAlias = Callable[[int], Any]
+ValueHook: TypeAlias = Callable[[tuple[str | int, ...], Any], Any]
+TableHook: TypeAlias = Callable[[str, dict[str, Any]], Any]
+Fallback: TypeAlias = Literal["vars"]
+Compression: TypeAlias = Literal["gzip", "zstd"]
+BinaryFormat: TypeAlias = Literal["msgpack", "cbor"]
+LeapSeconds: TypeAlias = Literal["error", "clamp"]
+Datetimes: TypeAlias = Literal["objects", "iso_strings"]
+KeyNormalization: TypeAlias = Literal["nfc", "none"]
+SurrogatePolicy: TypeAlias = Literal["error", "replace", "backslashreplace"]
+EscapeForm: TypeAlias = Literal["u", "U", "x"]
+ArrayMerge: TypeAlias = Literal["replace", "append", "unique"]
+IndentChar: TypeAlias = int | Literal["\t"]
+DatetimeFormat: TypeAlias = Literal["date", "local", "utc"]
+ArrayStyle: TypeAlias = Literal["auto", "one_per_line"]
+NanosecondPolicy: TypeAlias = Literal["keep", "truncate"]
 
 class KeyMeta(TypedDict, total=False):
     key: str
@@ -16,25 +42,286 @@ class KeyMeta(TypedDict, total=False):
     value_line: int | tuple[int, int]
     value_col: int | tuple[int, int]
 
+class FloatFormat(TypedDict, total=False):
+    precision: int
+    fixed: bool
+
+class CommentInfo(TypedDict):
+    leading: str | None
+    trailing: str | None
+
 class DocumentMeta(TypedDict):
     nodes: dict[str, KeyMeta]
 
 class TOMLDocument(Protocol):
     value: dict[str, Any]
     meta: DocumentMeta
+    toml_version: TomlVersion
 
+    @staticmethod
+    def from_dict(
+        data: dict[str, Any],
+        /,
+        toml_version: TomlVersion = ...,
+    ) -> TOMLDocument: ...
+    def unwrap(self) -> dict[str, Any]: ...
+    def visit(
+        self,
+        callback: Callable[
+            [tuple[str | int, ...], tuple[int, int] | None, str],
+            bool | None,
+        ],
+        /,
+    ) -> None: ...
     def __getitem__(self, key: str, /) -> Any: ...
     def __setitem__(self, key: str, value: Any, /) -> None: ...
     def __delitem__(self, key: str, /) -> None: ...
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[str]: ...
+    def __contains__(self, key: object, /) -> bool: ...
+    def keys(self) -> KeysView[str]: ...
+    def values(self) -> ValuesView[Any]: ...
+    def items(self) -> ItemsView[str, Any]: ...
+    def get(self, key: str, default: Any = None, /) -> Any: ...
+    def get_path(self, path: str, default: Any = None, /) -> Any: ...
+
+class Located:
+    @property
+    def value(self) -> Any: ...
+    @property
+    def line(self) -> int: ...
+    @property
+    def col(self) -> int: ...
+    @property
+    def file(self) -> str | None: ...
+
+class Schema:
+    @staticmethod
+    def compile(schema: Mapping[str, Any], /) -> Schema: ...
+    def validate(self, data: Any, /) -> None: ...
+
+class DumpTemplate:
+    @staticmethod
+    def from_example(
+        example: dict[str, Any],
+        /,
+        *,
+        toml_version: TomlVersion = ...,
+    ) -> DumpTemplate: ...
+    def dumps(self, values: dict[str, Any], /) -> str: ...
+
+class ConfigStack:
+    def __init__(
+        self,
+        defaults: dict[str, Any] | None = None,
+        *,
+        toml_version: TomlVersion = ...,
+        resolver: Callable[[str], str | None] | None = None,
+    ) -> None: ...
+    @property
+    def data(self) -> dict[str, Any]: ...
+    def add_file(
+        self,
+        path: str | PathLike[str],
+        /,
+        *,
+        required: bool = ...,
+    ) -> None: ...
+    def add_env(
+        self,
+        prefix: str,
+        /,
+        environ: Mapping[str, str] | None = None,
+    ) -> None: ...
+    def source(self, path: str) -> str | None: ...
+
+class TomlWriter:
+    def __init__(
+        self,
+        file: str | PathLike[str] | TextIO,
+        /,
+        *,
+        toml_version: TomlVersion = ...,
+    ) -> None: ...
+    def key(self, name: str, value: Any) -> None: ...
+    def table(self, path: str) -> None: ...
+    def array_of_tables(self, path: str) -> None: ...
+    def close(self) -> None: ...
+    @property
+    def closed(self) -> bool: ...
+    def __enter__(self) -> TomlWriter: ...
+    def __exit__(self, *args: object) -> None: ...
+
+class _ArrayTableSplitter:
+    def __init__(self, name: str, /, *, toml_version: TomlVersion = ...) -> None: ...
+    def feed(self, text: str, /) -> list[tuple[str, int, int]]: ...
+    def finish(self) -> list[tuple[str, int, int]]: ...
+    @property
+    def path(self) -> list[str]: ...
 
 def _loads(
-    s: str,
+    s: str | bytes,
+    /,
+    *,
+    parse_float: ParseFloat = ...,
+    toml_version: TomlVersion = ...,
+    preserve_format: bool = ...,
+    strict: bool = ...,
+    allow_nan: bool = ...,
+    only: list[str] | None = ...,
+    intern_strings: bool = ...,
+    normalize_keys: KeyNormalization = ...,
+    leap_seconds: LeapSeconds = ...,
+    datetimes: Datetimes = ...,
+    value_hook: ValueHook | None = ...,
+    max_string_length: int | None = ...,
+    max_items: int | None = ...,
+    max_depth: int | None = ...,
+    int_factory: IntFactory | None = ...,
+    key_by: list[tuple[str, str]] | None = ...,
+    locations: bool = ...,
+    filename: str | None = ...,
+    table_hook: TableHook | None = ...,
+    encoding: str | None = ...,
+) -> dict[str, Any]: ...
+
+def _read_fd(fd: int, /) -> str: ...
+def _position(text: str, offset: int, /) -> tuple[int, int]: ...
+def _get_comment(
+    toml_string: str,
+    path: str,
+    toml_version: TomlVersion,
+) -> tuple[str | None, str | None] | None: ...
+def _get_raw(
+    toml_string: str,
+    path: str,
+    toml_version: TomlVersion,
+) -> tuple[int, int] | None: ...
+def _set_error_formatter(formatter: Callable[[Any], str] | None) -> None: ...
+def _tokenize(
+    toml_string: str,
+    toml_version: TomlVersion,
+) -> list[tuple[str, int, int]]: ...
+def _array_insert(
+    toml_string: str,
+    path: str,
+    index: int | None,
+    value: Any,
+    line_width: int,
+    toml_version: TomlVersion,
+) -> str: ...
+def _array_remove(
+    toml_string: str,
+    path: str,
+    value: Any,
+    toml_version: TomlVersion,
+) -> str: ...
+def _decorate(
+    toml_string: str,
+    path: str,
+    comment: str | None,
+    before: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _graft(
+    toml_string: str,
+    path: str,
+    source: str,
+    source_key: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _move_table(
+    toml_string: str,
+    path: str,
+    before: str | None,
+    after: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _trivia(
+    toml_string: str,
+    path: str,
+    toml_version: TomlVersion,
+) -> dict[str, str]: ...
+def _set_trivia(
+    toml_string: str,
+    path: str,
+    before: str | None,
+    before_eq: str | None,
+    after_eq: str | None,
+    after: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _add_dependency(
+    toml_string: str,
+    requirement: str,
+    group: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _remove_dependency(
+    toml_string: str,
+    requirement: str,
+    group: str | None,
+    toml_version: TomlVersion,
+) -> str: ...
+def _decompress(
+    data: bytes,
+    compression: Compression,
+    encoding: str | None,
+) -> str: ...
+def _decode(data: bytes, encoding: str | None) -> str: ...
+def _compress(toml_string: str, compression: Compression) -> bytes: ...
+def _to_binary(
+    toml_string: str,
+    binary_format: BinaryFormat,
+    toml_version: TomlVersion,
+) -> bytes: ...
+def _from_binary(
+    data: bytes,
+    binary_format: BinaryFormat,
+    toml_version: TomlVersion,
+) -> str: ...
+
+class Watcher:
+    @property
+    def running(self) -> bool: ...
+    def stop(self) -> None: ...
+    def __enter__(self) -> Watcher: ...
+    def __exit__(self, *args: object) -> None: ...
+
+def _watch(
+    path: str | PathLike[str],
+    callback: Callable[[dict[str, Any] | None, Exception | None], object],
+    parse_float: ParseFloat,
+    debounce: float,
+    toml_version: TomlVersion,
+) -> Watcher: ...
+
+def _stop_watchers() -> None: ...
+
+def _update_document(
+    toml_string: str,
+    changes: dict[str, Any],
+    toml_version: TomlVersion,
+) -> str: ...
+
+def _loads_chunks(
+    chunks: Iterable[str],
     /,
     *,
     parse_float: ParseFloat = ...,
     toml_version: TomlVersion = ...,
+    strict: bool = ...,
+    allow_nan: bool = ...,
 ) -> dict[str, Any]: ...
 
+def _iter_tables(
+    s: str,
+    /,
+    *,
+    parse_float: ParseFloat = ...,
+    toml_version: TomlVersion = ...,
+) -> Iterator[tuple[str, Any]]: ...
+
 def _dumps(
     obj: Any,
     /,
@@ -42,8 +329,39 @@ def _dumps(
     *,
     pretty: bool = False,
     toml_version: TomlVersion = ...,
+    fallback: Fallback | None = None,
+    strict: bool = False,
+    quote_keys: bool = False,
+    surrogates: SurrogatePolicy = "error",
+    escape_form: EscapeForm = "u",
+    sort_arrays: str | set[str] | None = None,
+    key_order: dict[str, list[str]] | None = None,
+    encode_hook: ValueHook | None = None,
+    max_depth: int | None = None,
+    indent_char: IndentChar = 4,
+    preserve_order: bool = False,
+    comments: dict[str, str] | None = None,
+    float_formats: dict[str, FloatFormat] | None = None,
+    datetime_formats: dict[str, DatetimeFormat] | None = None,
+    parse_datetime_strings: bool = False,
+    array_style: ArrayStyle = "auto",
+    nanoseconds: NanosecondPolicy = "keep",
 ) -> str: ...
 
+def _parse_comments(
+    toml_string: str,
+    toml_version: TomlVersion = ...,
+) -> dict[str, CommentInfo]: ...
+
+def _canonicalize(obj: dict[str, Any], /) -> str: ...
+def _deep_merge(
+    base: dict[str, Any],
+    overlay: dict[str, Any],
+    arrays: ArrayMerge,
+) -> dict[str, Any]: ...
+def _infer_schema(docs: list[dict[str, Any]], /) -> dict[str, Any]: ...
+def _typed_dicts(schema: dict[str, Any], name: str) -> str: ...
+
 def _parse_metadata_from_string(
     toml_string: str,
     toml_version: TomlVersion = ...,
@@ -0,0 +1,80 @@
+__all__ = (
+    "Date",
+    "DateTime",
+    "Float",
+    "Formatted",
+    "Integer",
+    "String",
+    "Time",
+)
+
+from datetime import date, datetime, time
+from typing import Any
+
+
+class Formatted:
+    raw: str | None = None
+
+
+class Integer(int, Formatted):
+    pass
+
+
+class Float(float, Formatted):
+    pass
+
+
+class String(str, Formatted):
+    pass
+
+
+class DateTime(datetime, Formatted):
+    pass
+
+
+class Date(date, Formatted):
+    pass
+
+
+class Time(time, Formatted):
+    pass
+
+
+def _wrap(value: Any, raw: str) -> Any:
+    obj: Formatted
+    value_type = type(value)
+
+    if value_type is int:
+        obj = Integer(value)
+    elif value_type is float:
+        obj = Float(value)
+    elif value_type is str:
+        obj = String(value)
+    elif value_type is datetime:
+        obj = DateTime(
+            value.year,
+            value.month,
+            value.day,
+            value.hour,
+            value.minute,
+            value.second,
+            value.microsecond,
+            value.tzinfo,
+            fold=value.fold,
+        )
+    elif value_type is date:
+        obj = Date(value.year, value.month, value.day)
+    elif value_type is time:
+        obj = Time(
+            value.hour,
+            value.minute,
+            value.second,
+            value.microsecond,
+            value.tzinfo,
+            fold=value.fold,
+        )
+    else:
+        return value
+
+    obj.raw = raw
+    return obj
@@ -0,0 +1,498 @@
+__all__ = (
+    "Array",
+    "Comment",
+    "Container",
+    "InlineTable",
+    "TOMLDocument",
+    "Table",
+    "Trivia",
+    "Whitespace",
+    "array",
+    "comment",
+    "document",
+    "dumps",
+    "inline_table",
+    "item",
+    "loads",
+    "nl",
+    "parse",
+    "table",
+    "ws",
+)
+
+import copy
+import json
+import re
+from collections.abc import Iterable, Iterator, MutableMapping, MutableSequence
+from itertools import pairwise
+from typing import Any, TypedDict, TypeVar
+
+from ._lib import (
+    DEFAULT_LINE_WIDTH,
+    DEFAULT_TOML_VERSION,
+    TomlVersion,
+    dumps as _dumps,
+    loads as _loads,
+)
+from ._toml_rs import (
+    _array_insert,
+    _array_remove,
+    _decorate,
+    _graft,
+    _move_table,
+    _set_trivia,
+    _trivia,
+    _update_document,
+)
+
+_BARE_KEY = re.compile(r"[A-Za-z0-9_-]+")
+
+_ContainerT = TypeVar("_ContainerT", bound="Container")
+_ArrayT = TypeVar("_ArrayT", bound="Array")
+
+
+def _key_path(keys: tuple[str, ...]) -> str:
+    return ".".join(
+        key
+        if _BARE_KEY.fullmatch(key)
+        else json.dumps(key, ensure_ascii=False).replace("\x7f", "\\u007f")
+        for key in keys
+    )
+
+
+class _Source:
+    def __init__(self, text: str, toml_version: TomlVersion) -> None:
+        self.text = text
+        self.toml_version = toml_version
+        self._data: dict[str, Any] | None = None
+
+    @property
+    def data(self) -> dict[str, Any]:
+        if self._data is None:
+            self._data = _loads(self.text, toml_version=self.toml_version)
+        return self._data
+
+    def lookup(self, path: tuple[str, ...]) -> Any:
+        value: Any = self.data
+        for key in path:
+            value = value[key]
+        return value
+
+    def update(self, text: str) -> None:
+        self.text = text
+        self._data = None
+
+
+# The text around a key and its value, or around a table header: `before`
+# holds the indentation and any comment lines above it, `before_eq` and
+# `after_eq` the spaces around the `=`, and `after` whatever follows on the
+# same line, like a trailing comment.
+class Trivia(TypedDict, total=False):
+    before: str
+    before_eq: str
+    after_eq: str
+    after: str
+
+
+class Comment:
+    def __init__(self, text: str) -> None:
+        self.text = text
+
+    def as_string(self) -> str:
+        return f"# {self.text}\n"
+
+
+class Whitespace:
+    def __init__(self, text: str) -> None:
+        self.text = text
+
+    def as_string(self) -> str:
+        return self.text
+
+
+def _view(source: _Source, path: tuple[str, ...]) -> Any:
+    value = source.lookup(path)
+    if isinstance(value, dict):
+        return Table(source, path)
+    if isinstance(value, list):
+        return Array(source, path)
+    return value
+
+
+class Container(MutableMapping[str, Any]):
+    def __init__(self, source: _Source, path: tuple[str, ...] = ()) -> None:
+        self._source = source
+        self._path = path
+        # Comments and blank lines added with `add`, written before the next key.
+        self._trivia = ""
+        # A comment for a detached table's header, set once it is attached.
+        self._comment: str | None = None
+
+    def _value(self) -> dict[str, Any]:
+        return self._source.lookup(self._path)
+
+    def __getitem__(self, key: str) -> Any:
+        return _view(self._source, (*self._path, key))
+
+    def __setitem__(self, key: str, value: Any) -> None:
+        source = self._source
+        path = _key_path((*self._path, key))
+
+        if (
+            isinstance(value, (Container, Array))
+            and value._source is not source  # noqa: SLF001
+            and len(value._path) <= 1  # noqa: SLF001
+        ):
+            # Copy the item with its formatting and comments.
+            source_key = value._path[0] if value._path else None  # noqa: SLF001
+            source.update(
+                _graft(
+                    source.text,
+                    path,
+                    value._source.text,  # noqa: SLF001
+                    source_key,
+                    toml_version=source.toml_version,
+                )
+            )
+            if value._comment is not None:  # noqa: SLF001
+                self._decorate(key, comment=value._comment)  # noqa: SLF001
+            return
+
+        if isinstance(value, (Container, Array)):
+            value = value.unwrap()
+        source.update(
+            _update_document(
+                source.text,
+                {path: value},
+                toml_version=source.toml_version,
+            )
+        )
+
+    def __delitem__(self, key: str) -> None:
+        if key not in self._value():
+            raise KeyError(key)
+        self._source.update(
+            _update_document(
+                self._source.text,
+                {_key_path((*self._path, key)): None},
+                toml_version=self._source.toml_version,
+            )
+        )
+
+    def __iter__(self) -> Iterator[str]:
+        return iter(self._value())
+
+    def __len__(self) -> int:
+        return len(self._value())
+
+    def __repr__(self) -> str:
+        return f"{type(self).__name__}({self._value()!r})"
+
+    def _decorate(
+        self,
+        key: str | None,
+        *,
+        comment: str | None = None,
+        before: str | None = None,
+    ) -> None:
+        path = self._path if key is None else (*self._path, key)
+        self._source.update(
+            _decorate(
+                self._source.text,
+                _key_path(path),
+                comment,
+                before,
+                toml_version=self._source.toml_version,
+            )
+        )
+
+    def add(
+        self: _ContainerT,
+        key: str | Comment | Whitespace,
+        value: Any = None,
+    ) -> _ContainerT:
+        if isinstance(key, (Comment, Whitespace)):
+            self._trivia += key.as_string()
+            return self
+
+        if key in self._value():
+            msg = f"Key {key!r} already exists"
+            raise KeyError(msg)
+
+        self[key] = value
+        if self._trivia:
+            before = self._trivia
+            # Keep the blank line that separates a table from what precedes it.
+            if isinstance(self._value()[key], dict) and not isinstance(
+                value, InlineTable
+            ):
+                before = f"\n{before}"
+            self._decorate(key, before=before)
+            self._trivia = ""
+        return self
+
+    append = add
+
+    # Moves the table at the dotted key path `path`, its sub-tables and the
+    # comments above them, next to another table. Paths are relative to
+    # this container.
+    def move(
+        self: _ContainerT,
+        path: str,
+        /,
+        *,
+        before: str | None = None,
+        after: str | None = None,
+    ) -> _ContainerT:
+        self._source.update(
+            _move_table(
+                self._source.text,
+                self._subpath(path),
+                None if before is None else self._subpath(before),
+                None if after is None else self._subpath(after),
+                toml_version=self._source.toml_version,
+            )
+        )
+        return self
+
+    # Moves the tables at `paths` so they are written in that order, starting
+    # where the first of them is.
+    def reorder(self: _ContainerT, paths: Iterable[str], /) -> _ContainerT:
+        for previous, path in pairwise(paths):
+            self.move(path, after=previous)
+        return self
+
+    def trivia(self, key: str, /) -> Trivia:
+        return _trivia(  # type: ignore[return-value]
+            self._source.text,
+            _key_path((*self._path, key)),
+            toml_version=self._source.toml_version,
+        )
+
+    # Replaces the given parts of the text around `key`, named as `trivia`
+    # names them. Tables have no `before_eq` or `after_eq`.
+    def set_trivia(
+        self: _ContainerT,
+        key: str,
+        /,
+        *,
+        before: str | None = None,
+        before_eq: str | None = None,
+        after_eq: str | None = None,
+        after: str | None = None,
+    ) -> _ContainerT:
+        self._source.update(
+            _set_trivia(
+                self._source.text,
+                _key_path((*self._path, key)),
+                before,
+                before_eq,
+                after_eq,
+                after,
+                toml_version=self._source.toml_version,
+            )
+        )
+        return self
+
+    def _subpath(self, path: str) -> str:
+        return f"{_key_path(self._path)}.{path}" if self._path else path
+
+    def unwrap(self) -> dict[str, Any]:
+        return copy.deepcopy(self._value())
+
+    def as_string(self) -> str:
+        if not self._path:
+            return self._source.text + self._trivia
+        return _dumps(self.unwrap(), toml_version=self._source.toml_version)
+
+
+class TOMLDocument(Container):
+    def __str__(self) -> str:
+        return self.as_string()
+
+
+class Table(Container):
+    def comment(self: _ContainerT, text: str) -> _ContainerT:
+        if self._path:
+            self._decorate(None, comment=text)
+        else:
+            self._comment = text
+        return self
+
+
+class InlineTable(Table):
+    def as_string(self) -> str:
+        return self._source.text.partition(" = ")[2].rstrip("\n")
+
+
+class Array(MutableSequence[Any]):
+    def __init__(self, source: _Source, path: tuple[str, ...]) -> None:
+        self._source = source
+        self._path = path
+        self._comment: str | None = None
+
+    def _value(self) -> list[Any]:
+        return self._source.lookup(self._path)
+
+    def _update(self, text: str) -> None:
+        self._source.update(text)
+
+    def __getitem__(self, index: Any) -> Any:
+        return self._value()[index]
+
+    def __setitem__(self, index: Any, value: Any) -> None:
+        items = self.unwrap()
+        items[index] = value
+        self._update(
+            _update_document(
+                self._source.text,
+                {_key_path(self._path): items},
+                toml_version=self._source.toml_version,
+            )
+        )
+
+    def __delitem__(self, index: Any) -> None:
+        items = self._value()
+        if isinstance(index, int) and items.index(items[index]) == index % len(items):
+            # The first equal element is the one to remove, so the layout
+            # of the rest of the array is kept.
+            self._update(
+                _array_remove(
+                    self._source.text,
+                    _key_path(self._path),
+                    items[index],
+                    toml_version=self._source.toml_version,
+                )
+            )
+            return
+
+        items = self.unwrap()
+        del items[index]
+        self._update(
+            _update_document(
+                self._source.text,
+                {_key_path(self._path): items},
+                toml_version=self._source.toml_version,
+            )
+        )
+
+    def __len__(self) -> int:
+        return len(self._value())
+
+    def __eq__(self, other: object) -> bool:
+        if isinstance(other, Array):
+            return self._value() == other._value()
+        return self._value() == other
+
+    __hash__ = None  # type: ignore[assignment]
+
+    def __repr__(self) -> str:
+        return f"{type(self).__name__}({self._value()!r})"
+
+    def insert(self, index: int, value: Any) -> None:
+        if isinstance(value, (Container, Array)):
+            value = value.unwrap()
+        self._update(
+            _array_insert(
+                self._source.text,
+                _key_path(self._path),
+                index,
+                value,
+                DEFAULT_LINE_WIDTH,
+                toml_version=self._source.toml_version,
+            )
+        )
+
+    def comment(self: _ArrayT, text: str) -> _ArrayT:
+        self._update(
+            _decorate(
+                self._source.text,
+                _key_path(self._path),
+                text,
+                None,
+                toml_version=self._source.toml_version,
+            )
+        )
+        return self
+
+    def unwrap(self) -> list[Any]:
+        return copy.deepcopy(self._value())
+
+    def as_string(self) -> str:
+        return _dumps(
+            {"v": self._value()},
+            toml_version=self._source.toml_version,
+        ).partition(" = ")[2].rstrip("\n")
+
+
+def parse(
+    text: str,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> TOMLDocument:
+    source = _Source(text, toml_version)
+    # Report invalid documents right away rather than on first access.
+    _ = source.data
+    return TOMLDocument(source)
+
+
+loads = parse
+
+
+def dumps(
+    data: Any,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> str:
+    if isinstance(data, Container):
+        return data.as_string()
+    return _dumps(data, toml_version=toml_version)
+
+
+def document(*, toml_version: TomlVersion = DEFAULT_TOML_VERSION) -> TOMLDocument:
+    return TOMLDocument(_Source("", toml_version))
+
+
+def table(*, toml_version: TomlVersion = DEFAULT_TOML_VERSION) -> Table:
+    return Table(_Source("", toml_version))
+
+
+def inline_table(*, toml_version: TomlVersion = DEFAULT_TOML_VERSION) -> InlineTable:
+    return InlineTable(_Source("v = {}\n", toml_version), ("v",))
+
+
+def array(
+    raw: str = "[]",
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> Array:
+    return Array(_Source(f"v = {raw}\n", toml_version), ("v",))
+
+
+def item(
+    value: Any,
+    /,
+    *,
+    toml_version: TomlVersion = DEFAULT_TOML_VERSION,
+) -> Any:
+    if isinstance(value, dict):
+        return Table(_Source(_dumps(value, toml_version=toml_version), toml_version))
+    if isinstance(value, list):
+        text = _dumps({"v": value}, toml_version=toml_version)
+        return Array(_Source(text, toml_version), ("v",))
+    return value
+
+
+def comment(text: str, /) -> Comment:
+    return Comment(text)
+
+
+def ws(text: str, /) -> Whitespace:
+    return Whitespace(text)
+
+
+def nl() -> Whitespace:
+    return Whitespace("\n")
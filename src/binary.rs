@@ -0,0 +1,249 @@
+use std::{convert::Infallible, fmt::Display};
+
+use minicbor::{
+    Decoder, Encoder,
+    data::{Tag, Type},
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rmpv::Value;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    MessagePack,
+    Cbor,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "msgpack" => Ok(Self::MessagePack),
+            "cbor" => Ok(Self::Cbor),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported format: {other}"
+            ))),
+        }
+    }
+}
+
+// A TOML value stripped of its formatting, shared by both TOML versions and
+// both binary formats.
+pub enum Node {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Datetime { text: String, offset: bool },
+    Array(Vec<Self>),
+    Table(Vec<(String, Self)>),
+}
+
+fn invalid(err: &impl Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+// MessagePack has no type for datetimes, so they are written as strings.
+// CBOR tags offset datetimes as RFC 3339 text (tag 0); local datetimes, dates
+// and times have no tag and are written as strings too.
+pub fn encode(node: Node, format: Format) -> PyResult<Vec<u8>> {
+    match format {
+        Format::MessagePack => {
+            let mut out = Vec::new();
+            rmpv::encode::write_value(&mut out, &to_msgpack(node)).map_err(|err| invalid(&err))?;
+            Ok(out)
+        }
+        Format::Cbor => {
+            let mut encoder = Encoder::new(Vec::new());
+            write_cbor(&mut encoder, &node).map_err(|err| invalid(&err))?;
+            Ok(encoder.into_writer())
+        }
+    }
+}
+
+pub fn decode(data: &[u8], format: Format) -> PyResult<Node> {
+    let (node, rest) = match format {
+        Format::MessagePack => {
+            let mut reader = data;
+            let value = rmpv::decode::read_value(&mut reader).map_err(|err| invalid(&err))?;
+            (from_msgpack(value)?, reader.len())
+        }
+        Format::Cbor => {
+            let mut decoder = Decoder::new(data);
+            let node = read_cbor(&mut decoder)?;
+            (node, data.len() - decoder.position())
+        }
+    };
+
+    if rest != 0 {
+        return Err(PyValueError::new_err(format!(
+            "{rest} trailing bytes after the encoded value"
+        )));
+    }
+    Ok(node)
+}
+
+fn to_msgpack(node: Node) -> Value {
+    match node {
+        Node::String(text) | Node::Datetime { text, .. } => Value::from(text),
+        Node::Integer(int) => Value::from(int),
+        Node::Float(float) => Value::from(float),
+        Node::Boolean(bool) => Value::from(bool),
+        Node::Array(items) => Value::Array(items.into_iter().map(to_msgpack).collect()),
+        Node::Table(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, node)| (Value::from(key), to_msgpack(node)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_msgpack(value: Value) -> PyResult<Node> {
+    let node = match value {
+        Value::Boolean(bool) => Node::Boolean(bool),
+        Value::Integer(int) => Node::Integer(int.as_i64().ok_or_else(|| {
+            PyValueError::new_err(format!("integer {int} does not fit in a TOML integer"))
+        })?),
+        Value::F32(float) => Node::Float(f64::from(float)),
+        Value::F64(float) => Node::Float(float),
+        Value::String(text) => Node::String(
+            text.into_str()
+                .ok_or_else(|| PyValueError::new_err("invalid UTF-8 in MessagePack string"))?,
+        ),
+        Value::Array(items) => Node::Array(
+            items
+                .into_iter()
+                .map(from_msgpack)
+                .collect::<PyResult<_>>()?,
+        ),
+        Value::Map(entries) => Node::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    Value::String(key) => Ok((
+                        key.into_str().ok_or_else(|| {
+                            PyValueError::new_err("invalid UTF-8 in MessagePack string")
+                        })?,
+                        from_msgpack(value)?,
+                    )),
+                    other => Err(PyValueError::new_err(format!(
+                        "MessagePack map keys must be strings, not {other}"
+                    ))),
+                })
+                .collect::<PyResult<_>>()?,
+        ),
+        Value::Nil => return Err(PyValueError::new_err("TOML has no null value")),
+        Value::Binary(_) | Value::Ext(..) => {
+            return Err(PyValueError::new_err(
+                "MessagePack binary and extension values have no TOML equivalent",
+            ));
+        }
+    };
+    Ok(node)
+}
+
+fn write_cbor(
+    encoder: &mut Encoder<Vec<u8>>,
+    node: &Node,
+) -> Result<(), minicbor::encode::Error<Infallible>> {
+    match node {
+        Node::String(text) => {
+            encoder.str(text)?;
+        }
+        Node::Integer(int) => {
+            encoder.i64(*int)?;
+        }
+        Node::Float(float) => {
+            encoder.f64(*float)?;
+        }
+        Node::Boolean(bool) => {
+            encoder.bool(*bool)?;
+        }
+        Node::Datetime { text, offset } => {
+            if *offset {
+                encoder.tag(Tag::DateTime)?;
+            }
+            encoder.str(text)?;
+        }
+        Node::Array(items) => {
+            encoder.array(items.len() as u64)?;
+            for item in items {
+                write_cbor(encoder, item)?;
+            }
+        }
+        Node::Table(entries) => {
+            encoder.map(entries.len() as u64)?;
+            for (key, node) in entries {
+                encoder.str(key)?;
+                write_cbor(encoder, node)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_cbor(decoder: &mut Decoder<'_>) -> PyResult<Node> {
+    let datatype = decoder.datatype().map_err(|err| invalid(&err))?;
+    let node = match datatype {
+        Type::Bool => Node::Boolean(decoder.bool().map_err(|err| invalid(&err))?),
+        Type::U8
+        | Type::U16
+        | Type::U32
+        | Type::U64
+        | Type::I8
+        | Type::I16
+        | Type::I32
+        | Type::I64
+        | Type::Int => Node::Integer(decoder.i64().map_err(|err| invalid(&err))?),
+        Type::F32 | Type::F64 => Node::Float(decoder.f64().map_err(|err| invalid(&err))?),
+        Type::String => Node::String(read_str(decoder)?),
+        Type::Tag => match decoder.tag().map_err(|err| invalid(&err))? {
+            Tag::DateTime => Node::Datetime {
+                text: read_str(decoder)?,
+                offset: true,
+            },
+            tag => {
+                return Err(PyValueError::new_err(format!(
+                    "unsupported CBOR tag {tag:?}"
+                )));
+            }
+        },
+        Type::Array => {
+            let len = decoder
+                .array()
+                .map_err(|err| invalid(&err))?
+                .unwrap_or_default();
+            Node::Array(
+                (0..len)
+                    .map(|_| read_cbor(decoder))
+                    .collect::<PyResult<_>>()?,
+            )
+        }
+        Type::Map => {
+            let len = decoder
+                .map()
+                .map_err(|err| invalid(&err))?
+                .unwrap_or_default();
+            Node::Table(
+                (0..len)
+                    .map(|_| Ok((read_str(decoder)?, read_cbor(decoder)?)))
+                    .collect::<PyResult<_>>()?,
+            )
+        }
+        Type::Null | Type::Undefined => {
+            return Err(PyValueError::new_err("TOML has no null value"));
+        }
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "CBOR {other} has no TOML equivalent"
+            )));
+        }
+    };
+    Ok(node)
+}
+
+fn read_str(decoder: &mut Decoder<'_>) -> PyResult<String> {
+    decoder
+        .str()
+        .map(str::to_owned)
+        .map_err(|err| invalid(&err))
+}
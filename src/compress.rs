@@ -0,0 +1,54 @@
+use std::io::{Read, Write};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+#[derive(Clone, Copy)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported compression: {other}"
+            ))),
+        }
+    }
+}
+
+// Concatenated gzip members are read as one stream, the same as `gzip.open`.
+pub fn decompress(
+    py: Python<'_>,
+    data: &[u8],
+    compression: Compression,
+    encoding: Option<&str>,
+) -> PyResult<String> {
+    let bytes = py.detach(|| match compression {
+        Compression::Gzip => {
+            let mut bytes = Vec::new();
+            flate2::read::MultiGzDecoder::new(data)
+                .read_to_end(&mut bytes)
+                .map(|_| bytes)
+        }
+        Compression::Zstd => zstd::stream::decode_all(data),
+    })?;
+
+    crate::encoding::decode(py, bytes, encoding)
+}
+
+pub fn compress(py: Python<'_>, text: &str, compression: Compression) -> PyResult<Vec<u8>> {
+    py.detach(|| match compression {
+        Compression::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(text.as_bytes())?;
+            encoder.finish()
+        }
+        Compression::Zstd => zstd::stream::encode_all(text.as_bytes(), 0),
+    })
+    .map_err(PyErr::from)
+}
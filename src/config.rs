@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use pyo3::{
+    exceptions::{PyFileNotFoundError, PyValueError},
+    intern,
+    prelude::*,
+    types::{PyDict, PyFloat, PyString},
+};
+use rustc_hash::FxHashMap;
+
+use crate::{core::options::LoadOptions, document::parse_key_path};
+
+// Layers are merged as they are added: tables are merged key by key, any
+// other value replaces what an earlier layer set. Every key remembers the
+// layer that set it last. With a `resolver`, files are read by calling
+// `resolver(name)` instead, which returns the text or `None` when there is no
+// such file, so layers can come from anywhere.
+#[pyclass]
+pub struct ConfigStack {
+    data: Py<PyDict>,
+    sources: FxHashMap<Vec<String>, String>,
+    toml_version: &'static str,
+    resolver: Option<Py<PyAny>>,
+}
+
+#[pymethods]
+impl ConfigStack {
+    #[new]
+    #[pyo3(signature = (defaults = None, *, toml_version = "1.0.0", resolver = None))]
+    fn new(
+        py: Python<'_>,
+        defaults: Option<&Bound<'_, PyDict>>,
+        toml_version: &str,
+        resolver: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        let toml_version = match toml_version {
+            "1.0.0" => "1.0.0",
+            "1.1.0" => "1.1.0",
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+
+        let mut stack = Self {
+            data: PyDict::new(py).unbind(),
+            sources: FxHashMap::default(),
+            toml_version,
+            resolver,
+        };
+        if let Some(defaults) = defaults {
+            stack.merge(py, defaults, "defaults")?;
+        }
+        Ok(stack)
+    }
+
+    #[getter]
+    fn data(&self, py: Python<'_>) -> Py<PyDict> {
+        self.data.clone_ref(py)
+    }
+
+    #[pyo3(signature = (path, /, *, required = true))]
+    fn add_file(&mut self, py: Python<'_>, path: PathBuf, required: bool) -> PyResult<()> {
+        let text = if let Some(resolver) = &self.resolver {
+            let name = path.to_string_lossy();
+            let text = resolver.bind(py).call1((name.as_ref(),))?;
+            if text.is_none() {
+                if !required {
+                    return Ok(());
+                }
+                return Err(PyFileNotFoundError::new_err(format!(
+                    "Resolver found no file named {name:?}"
+                )));
+            }
+            text.extract::<String>()?
+        } else {
+            match py.detach(|| std::fs::read_to_string(&path)) {
+                Ok(text) => text,
+                Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(());
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        let layer = self.loads(py, &text)?;
+        self.merge(py, layer.bind(py).cast()?, &path.display().to_string())
+    }
+
+    // `PREFIX_DB__HOST=x` sets `db.host`. Values are read as TOML when they
+    // are a single valid value, e.g. `8080` or `[1, 2]`, and kept as strings
+    // otherwise.
+    #[pyo3(signature = (prefix, /, environ = None))]
+    fn add_env(
+        &mut self,
+        py: Python<'_>,
+        prefix: &str,
+        environ: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let environ = match environ {
+            Some(environ) => environ.clone(),
+            None => py
+                .import(intern!(py, "os"))?
+                .getattr(intern!(py, "environ"))?,
+        };
+
+        let mut vars = Vec::new();
+        for item in environ.call_method0(intern!(py, "items"))?.try_iter()? {
+            let (name, value) = item?.extract::<(String, String)>()?;
+            if let Some(rest) = name.strip_prefix(prefix) {
+                let keys = rest.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+                if !keys.iter().any(String::is_empty) {
+                    vars.push((keys, name, value));
+                }
+            }
+        }
+        vars.sort_unstable();
+
+        for (keys, name, value) in vars {
+            let Some((last, parents)) = keys.split_last() else {
+                continue;
+            };
+
+            let layer = PyDict::new(py);
+            let mut table = layer.clone();
+            for key in parents {
+                let child = PyDict::new(py);
+                table.set_item(key, &child)?;
+                table = child;
+            }
+            table.set_item(last, self.env_value(py, &value)?)?;
+
+            self.merge(py, &layer, &format!("env:{name}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn source(&self, path: &str) -> PyResult<Option<String>> {
+        let keys = parse_key_path(path)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid key path: {path:?}")))?;
+        Ok(self.sources.get(&keys).cloned())
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("ConfigStack({})", self.data.bind(py).repr()?))
+    }
+}
+
+impl ConfigStack {
+    fn loads(&self, py: Python<'_>, text: &str) -> PyResult<Py<PyAny>> {
+        let parse_float = py.get_type::<PyFloat>().into_any();
+        let options = LoadOptions::plain(&parse_float);
+        crate::toml_rs::loads_with_options(py, text, self.toml_version, &options)
+    }
+
+    fn env_value<'py>(&self, py: Python<'py>, value: &str) -> PyResult<Bound<'py, PyAny>> {
+        if !value.contains(['\n', '\r']) {
+            let parsed = self.loads(py, &format!("value = {value}"));
+            if let Ok(parsed) = parsed
+                && let Ok(parsed) = parsed.bind(py).cast::<PyDict>()
+                && parsed.len() == 1
+                && let Some(value) = parsed.get_item("value")?
+            {
+                return Ok(value);
+            }
+        }
+        Ok(PyString::new(py, value).into_any())
+    }
+
+    fn merge(&mut self, py: Python<'_>, layer: &Bound<'_, PyDict>, source: &str) -> PyResult<()> {
+        let data = self.data.bind(py).clone();
+        merge_into(&data, layer, &mut Vec::new(), source, &mut self.sources)
+    }
+}
+
+fn merge_into(
+    target: &Bound<'_, PyDict>,
+    layer: &Bound<'_, PyDict>,
+    path: &mut Vec<String>,
+    source: &str,
+    sources: &mut FxHashMap<Vec<String>, String>,
+) -> PyResult<()> {
+    for (key, value) in layer.iter() {
+        let key = key.extract::<String>()?;
+        path.push(key.clone());
+        sources.insert(path.clone(), source.to_owned());
+
+        if let Ok(table) = value.cast::<PyDict>() {
+            let existing = target
+                .get_item(&key)?
+                .and_then(|existing| existing.cast_into::<PyDict>().ok());
+            let child = match existing {
+                Some(existing) => existing,
+                None => {
+                    // Earlier values under this key are gone, so are their sources.
+                    sources.retain(|keys, _| !(keys.len() > path.len() && keys.starts_with(path)));
+                    let child = PyDict::new(target.py());
+                    target.set_item(&key, &child)?;
+                    child
+                }
+            };
+            merge_into(&child, table, path, source, sources)?;
+        } else {
+            sources.retain(|keys, _| !(keys.len() > path.len() && keys.starts_with(path)));
+            target.set_item(&key, value)?;
+        }
+
+        path.pop();
+    }
+
+    Ok(())
+}
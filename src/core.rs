@@ -1,2 +1,13 @@
+pub mod buffer;
+pub mod escape;
+pub mod formatted;
+pub mod hook;
+pub mod intern;
+pub mod located;
+pub mod lossy;
 pub mod macros;
 pub mod metadata;
+pub mod normalize;
+pub mod options;
+pub mod pyproject;
+pub mod surrogates;
@@ -0,0 +1,70 @@
+use std::fmt::{self, Display, Write};
+
+use pyo3::{
+    exceptions::{PyMemoryError, PyRuntimeError, PyUnicodeDecodeError},
+    prelude::*,
+};
+
+// A `String` that reserves before every write, so running out of memory
+// surfaces as `MemoryError` instead of aborting the interpreter.
+#[derive(Default)]
+pub struct FallibleString {
+    buf: String,
+    out_of_memory: bool,
+}
+
+impl Write for FallibleString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.buf.try_reserve(s.len()).is_err() {
+            self.out_of_memory = true;
+            return Err(fmt::Error);
+        }
+        self.buf.push_str(s);
+        Ok(())
+    }
+}
+
+impl FallibleString {
+    pub fn push(&mut self, value: &impl Display) -> PyResult<()> {
+        if write!(self, "{value}").is_ok() {
+            return Ok(());
+        }
+        // Anything but a failed allocation is the formatter giving up part
+        // way, and what has been written so far is not the whole document.
+        if self.out_of_memory {
+            Err(PyMemoryError::new_err("out of memory while rendering TOML"))
+        } else {
+            Err(PyRuntimeError::new_err(
+                "formatting failed while rendering TOML",
+            ))
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+}
+
+pub fn render(value: &impl Display) -> PyResult<String> {
+    let mut out = FallibleString::default();
+    out.push(value)?;
+    Ok(out.into_string())
+}
+
+pub fn try_to_owned(s: &str) -> PyResult<String> {
+    let mut owned = String::new();
+    owned
+        .try_reserve_exact(s.len())
+        .map_err(|_| PyMemoryError::new_err("out of memory while copying TOML document"))?;
+    owned.push_str(s);
+    Ok(owned)
+}
+
+pub fn decode_utf8(py: Python<'_>, bytes: Vec<u8>) -> PyResult<String> {
+    String::from_utf8(bytes).map_err(|err| {
+        match PyUnicodeDecodeError::new_utf8(py, err.as_bytes(), err.utf8_error()) {
+            Ok(exc) => PyErr::from_value(exc.into_any()),
+            Err(err) => err,
+        }
+    })
+}
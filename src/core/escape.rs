@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use crate::core::options::EscapeForm;
+
+// Whether `text` holds characters a basic string can only hold as a numeric
+// escape, the control characters without a short form like `\n`.
+pub fn needs_escape(text: &str) -> bool {
+    text.chars().any(|ch| {
+        matches!(
+            ch,
+            '\0'..='\u{7}' | '\u{b}' | '\u{e}'..='\u{1f}' | '\u{7f}'
+        )
+    })
+}
+
+// Writes `text` as a basic string, using `form` for numeric escapes.
+pub fn basic_string(text: &str, form: EscapeForm) -> String {
+    let mut repr = String::with_capacity(text.len() + 2);
+    repr.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => repr.push_str("\\\""),
+            '\\' => repr.push_str("\\\\"),
+            '\u{8}' => repr.push_str("\\b"),
+            '\t' => repr.push_str("\\t"),
+            '\n' => repr.push_str("\\n"),
+            '\u{c}' => repr.push_str("\\f"),
+            '\r' => repr.push_str("\\r"),
+            '\0'..='\u{1f}' | '\u{7f}' => {
+                let code = u32::from(ch);
+                let _ = match form {
+                    EscapeForm::Unicode => write!(repr, "\\u{code:04X}"),
+                    EscapeForm::LongUnicode => write!(repr, "\\U{code:08X}"),
+                    EscapeForm::Hex => write!(repr, "\\x{code:02X}"),
+                };
+            }
+            ch => repr.push(ch),
+        }
+    }
+    repr.push('"');
+    repr
+}
@@ -0,0 +1,23 @@
+use pyo3::{prelude::*, sync::PyOnceLock};
+
+fn get_wrap_func(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    static WRAP_FUNC: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+    WRAP_FUNC
+        .get_or_try_init(py, || {
+            py.import("toml_rs.items")?
+                .getattr("_wrap")
+                .map(Bound::unbind)
+        })
+        .map(|func| func.bind(py))
+}
+
+// Wraps a loaded scalar into its `toml_rs.items` counterpart, which remembers
+// the source text so `dumps` can emit it unchanged.
+pub fn wrap<'py>(
+    py: Python<'py>,
+    value: &Bound<'py, PyAny>,
+    raw: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    get_wrap_func(py)?.call1((value, raw))
+}
@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+
+use pyo3::{
+    IntoPyObjectExt,
+    prelude::*,
+    types::{PyDict, PyTuple},
+};
+
+// The `value_hook` of `loads`, called as `hook(path, value)` for every scalar
+// once it is converted, and the `encode_hook` of `dumps`, called the same way
+// for every value before it is written. `path` is a tuple of the keys and
+// array indices that lead to the value, kept up to date while the tree is
+// walked.
+pub struct ValueHook<'py> {
+    hook: Bound<'py, PyAny>,
+    path: RefCell<Vec<Bound<'py, PyAny>>>,
+}
+
+impl<'py> ValueHook<'py> {
+    pub const fn new(hook: Bound<'py, PyAny>) -> Self {
+        Self {
+            hook,
+            path: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn call(&self, value: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let path = PyTuple::new(self.hook.py(), self.path.borrow().iter())?;
+        self.hook.call1((path, value))
+    }
+}
+
+// Runs `f` with `part` appended to the hook's path, or just runs it when
+// there is no hook.
+pub fn enter<'py, T>(
+    hook: Option<&ValueHook<'py>>,
+    part: impl IntoPyObject<'py>,
+    f: impl FnOnce() -> PyResult<T>,
+) -> PyResult<T> {
+    enter_all(hook, [part], f)
+}
+
+// Like `enter`, for several path parts at once.
+pub fn enter_all<'py, T, P: IntoPyObject<'py>>(
+    hook: Option<&ValueHook<'py>>,
+    parts: impl IntoIterator<Item = P>,
+    f: impl FnOnce() -> PyResult<T>,
+) -> PyResult<T> {
+    let Some(hook) = hook else {
+        return f();
+    };
+
+    let depth = hook.path.borrow().len();
+    for part in parts {
+        let part = part.into_bound_py_any(hook.hook.py())?;
+        hook.path.borrow_mut().push(part);
+    }
+    let result = f();
+    hook.path.borrow_mut().truncate(depth);
+    result
+}
+
+// Appends `part` to the hook's path until the matching `leave`, for walks
+// that keep their own stack instead of recursing.
+pub fn push<'py>(hook: Option<&ValueHook<'py>>, part: impl IntoPyObject<'py>) -> PyResult<()> {
+    if let Some(hook) = hook {
+        let part = part.into_bound_py_any(hook.hook.py())?;
+        hook.path.borrow_mut().push(part);
+    }
+    Ok(())
+}
+
+pub fn leave(hook: Option<&ValueHook<'_>>) {
+    if let Some(hook) = hook {
+        hook.path.borrow_mut().pop();
+    }
+}
+
+// The `table_hook` of `loads`, called as `hook(path, table)` for every table
+// below the top level once all of its keys are converted, with what it
+// returns in place of the dict. `path` is the dotted key path of the table;
+// tables in an array of tables share the path of the array.
+pub struct TableHook<'py> {
+    hook: Bound<'py, PyAny>,
+    path: RefCell<Vec<String>>,
+}
+
+impl<'py> TableHook<'py> {
+    pub const fn new(hook: Bound<'py, PyAny>) -> Self {
+        Self {
+            hook,
+            path: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+// Like `enter_all`, for the path of a `TableHook`.
+pub fn enter_table<T, K: AsRef<str>>(
+    hook: Option<&TableHook<'_>>,
+    keys: impl IntoIterator<Item = K>,
+    f: impl FnOnce() -> PyResult<T>,
+) -> PyResult<T> {
+    let Some(hook) = hook else {
+        return f();
+    };
+
+    let depth = hook.path.borrow().len();
+    hook.path
+        .borrow_mut()
+        .extend(keys.into_iter().map(|key| key.as_ref().to_owned()));
+    let result = f();
+    hook.path.borrow_mut().truncate(depth);
+    result
+}
+
+// Like `push` and `leave`, for the path of a `TableHook`.
+pub fn push_table(hook: Option<&TableHook<'_>>, key: &str) {
+    if let Some(hook) = hook {
+        hook.path.borrow_mut().push(key.to_owned());
+    }
+}
+
+pub fn leave_table(hook: Option<&TableHook<'_>>) {
+    if let Some(hook) = hook {
+        hook.path.borrow_mut().pop();
+    }
+}
+
+// Hands a converted table to the hook, if there is one and the table is not
+// the document itself.
+pub fn finish_table<'py>(
+    hook: Option<&TableHook<'py>>,
+    table: Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyAny>> {
+    match hook {
+        Some(hook) if !hook.path.borrow().is_empty() => {
+            let path = hook.path.borrow().join(".");
+            hook.hook.call1((path, table))
+        }
+        _ => Ok(table.into_any()),
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+
+use pyo3::{
+    prelude::*,
+    types::{PyDelta, PyString, PyTzInfo},
+};
+use rustc_hash::FxHashMap;
+
+// Hands out one `str` object per distinct string value for the duration of a
+// load, so documents that repeat the same values keep a single copy of each.
+#[derive(Default)]
+pub struct StringCache(RefCell<FxHashMap<Box<str>, Py<PyString>>>);
+
+impl StringCache {
+    pub fn get<'py>(&self, py: Python<'py>, value: &str) -> Bound<'py, PyString> {
+        let mut strings = self.0.borrow_mut();
+        if let Some(cached) = strings.get(value) {
+            return cached.bind(py).clone();
+        }
+
+        let py_string = PyString::new(py, value);
+        strings.insert(value.into(), py_string.clone().unbind());
+        py_string
+    }
+}
+
+// Datetimes of one document tend to share a handful of offsets, so each
+// offset gets one `timezone` object per load. UTC needs no entry: Python
+// already keeps a single `timezone.utc`.
+#[derive(Default)]
+pub struct TimezoneCache(RefCell<FxHashMap<i16, Py<PyTzInfo>>>);
+
+impl TimezoneCache {
+    pub fn get<'py>(&self, py: Python<'py>, minutes: i16) -> PyResult<Bound<'py, PyTzInfo>> {
+        let mut timezones = self.0.borrow_mut();
+        if let Some(cached) = timezones.get(&minutes) {
+            return Ok(cached.bind(py).clone());
+        }
+
+        let tzinfo = fixed_offset(py, minutes)?;
+        timezones.insert(minutes, tzinfo.clone().unbind());
+        Ok(tzinfo)
+    }
+}
+
+pub fn fixed_offset(py: Python<'_>, minutes: i16) -> PyResult<Bound<'_, PyTzInfo>> {
+    const SECS_IN_DAY: i32 = 86_400;
+
+    let seconds = i32::from(minutes) * 60;
+    let days = seconds.div_euclid(SECS_IN_DAY);
+    let seconds = seconds.rem_euclid(SECS_IN_DAY);
+    let py_delta = PyDelta::new(py, days, seconds, 0, false)?;
+    PyTzInfo::fixed_offset(py, py_delta)
+}
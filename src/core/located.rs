@@ -0,0 +1,60 @@
+use pyo3::prelude::*;
+
+use crate::core::metadata::DocIndex;
+
+// A scalar loaded with `locations=True`, with the place it was written.
+// `line` and `col` count from 1, like the document metadata does.
+#[pyclass(frozen, module = "toml_rs")]
+pub struct Located {
+    #[pyo3(get)]
+    value: Py<PyAny>,
+    #[pyo3(get)]
+    line: usize,
+    #[pyo3(get)]
+    col: usize,
+    #[pyo3(get)]
+    file: Option<Py<PyAny>>,
+}
+
+#[pymethods]
+impl Located {
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let file = match &self.file {
+            Some(file) => file.bind(py).repr()?.to_string(),
+            None => String::from("None"),
+        };
+        Ok(format!(
+            "Located({}, file={file}, line={}, col={})",
+            self.value.bind(py).repr()?,
+            self.line,
+            self.col
+        ))
+    }
+}
+
+pub struct Locations<'a, 'py> {
+    index: DocIndex<'a>,
+    file: Option<Bound<'py, PyAny>>,
+}
+
+impl<'a, 'py> Locations<'a, 'py> {
+    pub fn new(doc: &'a str, file: Option<Bound<'py, PyAny>>) -> Self {
+        Self {
+            index: DocIndex::new(doc),
+            file,
+        }
+    }
+
+    // Wraps `value`, which starts at byte `pos` of the document.
+    pub fn wrap(&self, value: Bound<'py, PyAny>, pos: usize) -> PyResult<Bound<'py, PyAny>> {
+        let py = value.py();
+        let (line, col) = self.index.line_col(pos);
+        let located = Located {
+            value: value.unbind(),
+            line,
+            col,
+            file: self.file.as_ref().map(|file| file.clone().unbind()),
+        };
+        Ok(Bound::new(py, located)?.into_any())
+    }
+}
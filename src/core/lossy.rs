@@ -0,0 +1,68 @@
+use std::ffi::CString;
+
+use pyo3::{exceptions::PyUserWarning, prelude::*};
+
+// Reports a conversion that silently drops information: a `UserWarning` by
+// default, or the error built by `error` under `strict=True`.
+pub fn report(
+    py: Python<'_>,
+    strict: bool,
+    message: String,
+    error: impl FnOnce(String) -> PyErr,
+) -> PyResult<()> {
+    if strict {
+        return Err(error(message));
+    }
+
+    let message = CString::new(message)?;
+    PyErr::warn(py, py.get_type::<PyUserWarning>().as_any(), &message, 2)
+}
+
+// Splits a decimal float into its significant digits and the exponent `e`
+// such that the value is `0.<digits> * 10^e`, so `"1.50e2"` and `"150.0"`
+// both become `("15", 3)`.
+fn significand(lexeme: &str) -> (String, i32) {
+    let (mantissa, exponent) = lexeme
+        .split_once(['e', 'E'])
+        .map_or((lexeme, 0), |(mantissa, exponent)| {
+            (mantissa, exponent.replace('_', "").parse().unwrap_or(0))
+        });
+    let mantissa = mantissa.trim_start_matches(['+', '-']);
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = int_part
+        .chars()
+        .chain(frac_part.chars())
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    let int_len = int_part.chars().filter(char::is_ascii_digit).count();
+
+    let trimmed = digits.trim_start_matches('0');
+    let leading_zeros = digits.len() - trimmed.len();
+    let trimmed = trimmed.trim_end_matches('0');
+
+    let exponent = exponent + i32::try_from(int_len).unwrap_or(i32::MAX)
+        - i32::try_from(leading_zeros).unwrap_or(i32::MAX);
+
+    if trimmed.is_empty() {
+        (String::new(), 0)
+    } else {
+        (trimmed.to_owned(), exponent)
+    }
+}
+
+// Whether the float written as `lexeme` survives the round trip to `repr`.
+// Up to 15 significant digits always do, which skips the `repr` call for
+// nearly every float found in real documents.
+pub fn float_is_exact(lexeme: &str, repr: impl FnOnce() -> PyResult<String>) -> PyResult<bool> {
+    if lexeme.contains(['n', 'i']) {
+        return Ok(true);
+    }
+
+    let (digits, exponent) = significand(lexeme);
+    if digits.len() <= 15 {
+        return Ok(true);
+    }
+
+    Ok(significand(&repr()?) == (digits, exponent))
+}
@@ -1,2 +1,11 @@
+pub mod binary;
+pub mod comments;
 pub mod dumps;
+pub mod edit;
+pub mod escape;
+pub mod iter;
 pub mod loads;
+pub mod pyproject;
+pub mod quote;
+pub mod raw;
+pub mod tokens;
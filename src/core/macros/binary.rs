@@ -0,0 +1,152 @@
+#[macro_export]
+macro_rules! impl_binary {
+    () => {
+        pub fn to_node(py: pyo3::Python<'_>, doc: &str) -> pyo3::PyResult<Node> {
+            let parsed = py
+                .detach(|| DeTable::parse(doc))
+                .map_err(|err| $crate::error::parse_error(&err, doc))?;
+
+            parsed
+                .get_ref()
+                .iter()
+                .map(|(key, value)| {
+                    Ok((key.get_ref().clone().into_owned(), value_node(value, doc)?))
+                })
+                .collect::<pyo3::PyResult<_>>()
+                .map(Node::Table)
+        }
+
+        fn value_node(value: &Spanned<DeValue<'_>>, doc: &str) -> pyo3::PyResult<Node> {
+            let span = value.span();
+            let node = match value.as_ref() {
+                DeValue::String(str) => Node::String(str.clone().into_owned()),
+                DeValue::Integer(int) => {
+                    let bytes = int.as_str().as_bytes();
+                    let parse_options = lexical_core::ParseIntegerOptions::new();
+
+                    let int_64 = $crate::parse_int!(i64, bytes, &parse_options, int.radix())
+                        .map_err(|_| {
+                            $crate::error::invalid_value(
+                                format!(
+                                    "integer '{}' does not fit in 64 bits",
+                                    $crate::core::metadata::raw_slice(doc, &span)
+                                ),
+                                doc,
+                                span,
+                            )
+                        })?;
+                    Node::Integer(int_64)
+                }
+                DeValue::Float(float) => {
+                    let parsed: f64 =
+                        lexical_core::parse(float.as_str().as_bytes()).map_err(|err| {
+                            $crate::toml_rs::TOMLInvalidValueError::new_err((
+                                format!("invalid float '{}': {err}", float.as_str()),
+                                doc.to_string(),
+                                span.start,
+                            ))
+                        })?;
+                    Node::Float(parsed)
+                }
+                DeValue::Boolean(bool) => Node::Boolean(*bool),
+                DeValue::Datetime(datetime) => Node::Datetime {
+                    text: datetime.to_string(),
+                    offset: datetime.offset.is_some(),
+                },
+                DeValue::Array(array) => Node::Array(
+                    array
+                        .iter()
+                        .map(|item| value_node(item, doc))
+                        .collect::<pyo3::PyResult<_>>()?,
+                ),
+                DeValue::Table(table) => Node::Table(
+                    table
+                        .iter()
+                        .map(|(key, value)| {
+                            Ok((key.get_ref().clone().into_owned(), value_node(value, doc)?))
+                        })
+                        .collect::<pyo3::PyResult<_>>()?,
+                ),
+            };
+            Ok(node)
+        }
+
+        pub fn from_node(node: Node) -> pyo3::PyResult<String> {
+            let Node::Table(entries) = node else {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "a TOML document must be a map at the top level",
+                ));
+            };
+
+            let mut doc = DocumentMut::new();
+            for (key, node) in entries {
+                doc.insert(&key, node_item(node)?);
+            }
+            $crate::core::buffer::render(&doc)
+        }
+
+        fn node_table(entries: Vec<(String, Node)>) -> pyo3::PyResult<Table> {
+            let mut table = Table::new();
+            for (key, node) in entries {
+                table.insert(&key, node_item(node)?);
+            }
+            // A table holding only other tables gets no header of its own.
+            table.set_implicit(
+                !table.is_empty()
+                    && table
+                        .iter()
+                        .all(|(_, item)| item.is_table() || item.is_array_of_tables()),
+            );
+            Ok(table)
+        }
+
+        fn node_item(node: Node) -> pyo3::PyResult<Item> {
+            match node {
+                Node::Table(entries) => Ok(Item::Table(node_table(entries)?)),
+                Node::Array(items)
+                    if !items.is_empty()
+                        && items.iter().all(|item| matches!(item, Node::Table(_))) =>
+                {
+                    let mut tables = ArrayOfTables::new();
+                    for item in items {
+                        if let Node::Table(entries) = item {
+                            tables.push(node_table(entries)?);
+                        }
+                    }
+                    Ok(Item::ArrayOfTables(tables))
+                }
+                node => node_value(node).map(Item::Value),
+            }
+        }
+
+        fn node_value(node: Node) -> pyo3::PyResult<Value> {
+            let value = match node {
+                Node::String(text) => Value::from(text),
+                Node::Integer(int) => Value::from(int),
+                Node::Float(float) => Value::from(float),
+                Node::Boolean(bool) => Value::from(bool),
+                Node::Datetime { text, .. } => {
+                    Value::from(text.parse::<Datetime>().map_err(|err| {
+                        pyo3::exceptions::PyValueError::new_err(format!(
+                            "invalid datetime {text:?}: {err}"
+                        ))
+                    })?)
+                }
+                Node::Array(items) => Value::Array(
+                    items
+                        .into_iter()
+                        .map(node_value)
+                        .collect::<pyo3::PyResult<Array>>()?,
+                ),
+                Node::Table(entries) => {
+                    let mut table = InlineTable::new();
+                    for (key, node) in entries {
+                        table.insert(key, node_value(node)?);
+                    }
+                    Value::InlineTable(table)
+                }
+            };
+            Ok(value)
+        }
+    };
+}
@@ -0,0 +1,340 @@
+#[macro_export]
+macro_rules! impl_comments {
+    ($extract_fn:ident, $comment_at_fn:ident, $add_fn:ident) => {
+        pub fn $extract_fn<'py>(
+            py: pyo3::Python<'py>,
+            doc: &str,
+        ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+            let parsed = doc
+                .parse::<DocumentMut>()
+                .map_err(|err| $crate::error::parse_error(&err, doc))?;
+
+            let comments = pyo3::types::PyDict::new(py);
+            collect_table(&comments, parsed.as_table(), "")?;
+            Ok(comments)
+        }
+
+        // The leading and trailing comment of the item at `path`, as
+        // `$extract_fn` reports them, or `None` when there is no such item.
+        pub fn $comment_at_fn(
+            doc: &str,
+            path: &str,
+        ) -> pyo3::PyResult<Option<(Option<String>, Option<String>)>> {
+            let parsed = doc
+                .parse::<DocumentMut>()
+                .map_err(|err| $crate::error::parse_error(&err, doc))?;
+            let keys = $crate::document::parse_key_path(path).unwrap_or_default();
+            let Some((last, parents)) = keys.split_last() else {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid key path: {path:?}"
+                )));
+            };
+
+            let mut parent: &dyn TableLike = parsed.as_table();
+            for key in parents {
+                match parent.get(key).and_then(Item::as_table_like) {
+                    Some(table) => parent = table,
+                    None => return Ok(None),
+                }
+            }
+            let Some((key, item)) = parent.get_key_value(last) else {
+                return Ok(None);
+            };
+
+            Ok(match item {
+                Item::Value(value) => Some((
+                    decor_comments(key.leaf_decor()).0,
+                    decor_comments(value.decor()).1,
+                )),
+                Item::Table(table) => {
+                    let (leading, trailing) = decor_comments(table.decor());
+                    Some((
+                        leading.or_else(|| decor_comments(key.leaf_decor()).0),
+                        trailing,
+                    ))
+                }
+                Item::ArrayOfTables(_) | Item::None => None,
+            })
+        }
+
+        fn comment_text(raw: Option<&str>) -> Option<String> {
+            let lines = raw?
+                .lines()
+                .filter_map(|line| line.trim_start().strip_prefix('#'))
+                .map(str::trim)
+                .collect::<Vec<_>>();
+
+            if lines.is_empty() {
+                None
+            } else {
+                Some(lines.join("\n"))
+            }
+        }
+
+        fn decor_comments(decor: &Decor) -> (Option<String>, Option<String>) {
+            (
+                comment_text(decor.prefix().and_then(RawString::as_str)),
+                comment_text(decor.suffix().and_then(RawString::as_str)),
+            )
+        }
+
+        fn join_path(parent: &str, key: &Key) -> String {
+            let key = Key::new(key.get());
+            if parent.is_empty() {
+                key.display_repr().into_owned()
+            } else {
+                format!("{parent}.{}", key.display_repr())
+            }
+        }
+
+        fn push_comments(
+            out: &pyo3::Bound<'_, pyo3::types::PyDict>,
+            path: &str,
+            leading: Option<String>,
+            trailing: Option<String>,
+        ) -> pyo3::PyResult<()> {
+            if leading.is_none() && trailing.is_none() {
+                return Ok(());
+            }
+
+            let entry = pyo3::types::PyDict::new(out.py());
+            entry.set_item("leading", leading)?;
+            entry.set_item("trailing", trailing)?;
+            out.set_item(path, entry)
+        }
+
+        fn collect_table(
+            out: &pyo3::Bound<'_, pyo3::types::PyDict>,
+            table: &Table,
+            path: &str,
+        ) -> pyo3::PyResult<()> {
+            for (key, item) in table.iter() {
+                let Some(key) = table.key(key) else {
+                    continue;
+                };
+                let item_path = join_path(path, key);
+
+                match item {
+                    Item::Value(value) => {
+                        let (leading, _) = decor_comments(key.leaf_decor());
+                        let (_, trailing) = decor_comments(value.decor());
+                        push_comments(out, &item_path, leading, trailing)?;
+
+                        if let Value::InlineTable(inline_table) = value {
+                            collect_inline_table(out, inline_table, &item_path)?;
+                        }
+                    }
+                    Item::Table(table) => {
+                        let (leading, trailing) = decor_comments(table.decor());
+                        let leading = leading.or_else(|| decor_comments(key.leaf_decor()).0);
+                        push_comments(out, &item_path, leading, trailing)?;
+                        collect_table(out, table, &item_path)?;
+                    }
+                    Item::ArrayOfTables(array) => {
+                        for (i, table) in array.iter().enumerate() {
+                            let table_path = format!("{item_path}[{i}]");
+                            let (leading, trailing) = decor_comments(table.decor());
+                            push_comments(out, &table_path, leading, trailing)?;
+                            collect_table(out, table, &table_path)?;
+                        }
+                    }
+                    Item::None => {}
+                }
+            }
+
+            Ok(())
+        }
+
+        fn collect_inline_table(
+            out: &pyo3::Bound<'_, pyo3::types::PyDict>,
+            table: &InlineTable,
+            path: &str,
+        ) -> pyo3::PyResult<()> {
+            for (key, value) in table.iter() {
+                let Some(key) = table.key(key) else {
+                    continue;
+                };
+                let item_path = join_path(path, key);
+
+                let (leading, _) = decor_comments(key.leaf_decor());
+                let (_, trailing) = decor_comments(value.decor());
+                push_comments(out, &item_path, leading, trailing)?;
+
+                if let Value::InlineTable(inline_table) = value {
+                    collect_inline_table(out, inline_table, &item_path)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        // Writes the `comments` of `dumps` above the items they name. A path
+        // ending in `[index]` or `[key=value]` picks entries of an array of
+        // tables, the latter every entry whose `key` is `value`; an array of
+        // inline tables is turned into one so its entries get headers.
+        pub fn $add_fn(
+            doc: &mut DocumentMut,
+            comments: &rustc_hash::FxHashMap<String, String>,
+        ) -> pyo3::PyResult<()> {
+            let mut paths = comments.keys().collect::<Vec<_>>();
+            paths.sort_unstable();
+
+            for path in paths {
+                let lines = comment_lines(&comments[path]);
+                let (keys, entry) = comment_target(path).ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!("Invalid key path: {path:?}"))
+                })?;
+                let not_found = || {
+                    pyo3::exceptions::PyKeyError::new_err(format!("Key path not found: {path:?}"))
+                };
+                let cannot = |reason: &str| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "Cannot comment {path:?}: {reason}"
+                    ))
+                };
+                let Some((last, parents)) = keys.split_last() else {
+                    return Err(not_found());
+                };
+
+                let mut parent = doc.as_table_mut();
+                for key in parents {
+                    parent = match parent.get_mut(key) {
+                        Some(Item::Table(table)) => table,
+                        Some(Item::Value(_)) => return Err(cannot("inside an inline value")),
+                        _ => return Err(not_found()),
+                    };
+                }
+                let (mut key, item) = parent.get_key_value_mut(last).ok_or_else(not_found)?;
+
+                if let Some(entry) = entry {
+                    if item.is_value() {
+                        *item = std::mem::take(item)
+                            .into_array_of_tables()
+                            .map_or_else(|item| item, Item::ArrayOfTables);
+                    }
+                    let Item::ArrayOfTables(tables) = item else {
+                        return Err(cannot("not an array of tables"));
+                    };
+
+                    let mut found = false;
+                    for (i, table) in tables.iter_mut().enumerate() {
+                        if entry.matches(i, table) {
+                            table.decor_mut().set_prefix(format!("\n{lines}"));
+                            found = true;
+                        }
+                    }
+                    if !found {
+                        return Err(not_found());
+                    }
+                    continue;
+                }
+
+                match item {
+                    Item::Value(_) => key.leaf_decor_mut().set_prefix(lines),
+                    Item::Table(table) => {
+                        // An implicit table has no header to put the comment on.
+                        table.set_implicit(false);
+                        table.decor_mut().set_prefix(format!("\n{lines}"));
+                    }
+                    Item::ArrayOfTables(tables) => {
+                        if let Some(table) = tables.get_mut(0) {
+                            table.decor_mut().set_prefix(format!("\n{lines}"));
+                        }
+                    }
+                    Item::None => return Err(not_found()),
+                }
+            }
+
+            // Headers are written after a blank line, except the first one
+            // when nothing comes before it.
+            if !doc.as_table().iter().any(|(_, item)| item.is_value())
+                && let Some(decor) = first_header(doc.as_table_mut())
+                && let Some(prefix) = decor.prefix().and_then(RawString::as_str)
+                && let Some(prefix) = prefix.strip_prefix('\n')
+            {
+                let prefix = prefix.to_owned();
+                decor.set_prefix(prefix);
+            }
+
+            Ok(())
+        }
+
+        enum Entry {
+            Index(usize),
+            Field(String, String),
+        }
+
+        impl Entry {
+            fn matches(&self, index: usize, table: &Table) -> bool {
+                match self {
+                    Self::Index(i) => *i == index,
+                    Self::Field(key, value) => match table.get(key) {
+                        Some(Item::Value(Value::String(string))) => string.value() == value,
+                        Some(Item::Value(other)) => other.to_string().trim() == value,
+                        _ => false,
+                    },
+                }
+            }
+        }
+
+        // The keys of `path`, and the entry its `[...]` suffix picks.
+        fn comment_target(path: &str) -> Option<(Vec<String>, Option<Entry>)> {
+            let Some((path, selector)) = path
+                .strip_suffix(']')
+                .and_then(|path| path.rsplit_once('['))
+            else {
+                return Some(($crate::document::parse_key_path(path)?, None));
+            };
+
+            let entry = match selector.trim().parse::<usize>() {
+                Ok(index) => Entry::Index(index),
+                Err(_) => {
+                    let (key, value) = selector.split_once('=')?;
+                    let value = value.trim();
+                    let value = value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .unwrap_or(value);
+                    Entry::Field(key.trim().to_owned(), value.to_owned())
+                }
+            };
+            Some(($crate::document::parse_key_path(path)?, Some(entry)))
+        }
+
+        fn comment_lines(text: &str) -> String {
+            text.lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        "#\n".to_owned()
+                    } else {
+                        format!("# {line}\n")
+                    }
+                })
+                .collect()
+        }
+
+        // The decor of the header written first, in the order tables are
+        // rendered.
+        fn first_header(table: &mut Table) -> Option<&mut Decor> {
+            for (_, item) in table.iter_mut() {
+                match item {
+                    Item::Table(table) => {
+                        if !table.is_implicit() || table.iter().any(|(_, item)| item.is_value()) {
+                            return Some(table.decor_mut());
+                        }
+                        if let Some(decor) = first_header(table) {
+                            return Some(decor);
+                        }
+                    }
+                    Item::ArrayOfTables(tables) => {
+                        if let Some(table) = tables.get_mut(0) {
+                            return Some(table.decor_mut());
+                        }
+                    }
+                    Item::Value(_) | Item::None => {}
+                }
+            }
+            None
+        }
+    };
+}
@@ -3,6 +3,7 @@ macro_rules! impl_dumps {
     (
         $validate_fn:ident,
         $python_to_toml_fn:ident,
+        $dumps_plain_fn:ident,
         $to_toml_macro:ident,
         $toml_dt_macro:ident
     ) => {
@@ -35,24 +36,448 @@ macro_rules! impl_dumps {
         pub fn $python_to_toml_fn<'py>(
             py: pyo3::Python<'py>,
             obj: &pyo3::Bound<'py, pyo3::PyAny>,
-            inline_tables: Option<&rustc_hash::FxHashSet<String>>,
+            options: &$crate::core::options::DumpOptions<'_, 'py>,
         ) -> pyo3::PyResult<Item> {
             to_toml_impl(
                 py,
-                obj,
-                inline_tables,
+                &encode(obj, options)?,
+                options,
                 &mut smallvec::SmallVec::<String, 32>::with_capacity(
-                    inline_tables.map_or(0, rustc_hash::FxHashSet::len),
+                    options.inline_tables.map_or(0, rustc_hash::FxHashSet::len),
                 ),
             )
         }
 
+        // Documents made only of builtin types are written straight to a
+        // string, matching what rendering a `DocumentMut` would produce
+        // without building one. Returns `None` for anything else.
+        pub fn $dumps_plain_fn<'py>(
+            py: pyo3::Python<'py>,
+            obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            options: &$crate::core::options::DumpOptions<'_, 'py>,
+        ) -> pyo3::PyResult<Option<String>> {
+            fn get_timezone_type(
+                py: pyo3::Python<'_>,
+            ) -> pyo3::PyResult<&pyo3::Bound<'_, pyo3::types::PyType>> {
+                static TIMEZONE_TYPE: pyo3::sync::PyOnceLock<pyo3::Py<pyo3::types::PyType>> =
+                    pyo3::sync::PyOnceLock::new();
+
+                TIMEZONE_TYPE.import(py, "datetime", "timezone")
+            }
+
+            // Both walks here recurse, so deeper documents, and values that
+            // contain themselves, are left to `to_toml_impl`.
+            const PLAIN_MAX_DEPTH: usize = 32;
+
+            // Converting these has no side effects, so the check can run
+            // before anything is written and the caller can still fall back.
+            fn is_plain(
+                py: pyo3::Python<'_>,
+                obj: &pyo3::Bound<'_, pyo3::PyAny>,
+                depth: usize,
+            ) -> pyo3::PyResult<bool> {
+                // Strings with lone surrogates are reported with their path
+                // by `to_toml_impl`.
+                if let Ok(s) = obj.cast_exact::<pyo3::types::PyString>() {
+                    return Ok(s.to_str().is_ok());
+                }
+                if obj.is_exact_instance_of::<pyo3::types::PyBool>()
+                    || obj.is_exact_instance_of::<pyo3::types::PyInt>()
+                    || obj.is_exact_instance_of::<pyo3::types::PyFloat>()
+                    || obj.is_exact_instance_of::<pyo3::types::PyDate>()
+                    || obj.is_exact_instance_of::<pyo3::types::PyTime>()
+                {
+                    return Ok(true);
+                }
+                if obj.is_exact_instance_of::<pyo3::types::PyDateTime>() {
+                    let tzinfo = obj.getattr(pyo3::intern!(py, "tzinfo"))?;
+                    return Ok(tzinfo.is_none() || tzinfo.get_type().is(get_timezone_type(py)?));
+                }
+                if depth > PLAIN_MAX_DEPTH {
+                    return Ok(false);
+                }
+                if obj.is_exact_instance_of::<pyo3::types::PyDict>() {
+                    for (key, value) in obj.cast::<pyo3::types::PyDict>()?.iter() {
+                        if !key
+                            .cast_exact::<pyo3::types::PyString>()
+                            .is_ok_and(|key| key.to_str().is_ok())
+                            || !is_plain(py, &value, depth + 1)?
+                        {
+                            return Ok(false);
+                        }
+                    }
+                    return Ok(true);
+                }
+                if obj.is_exact_instance_of::<pyo3::types::PyList>()
+                    || obj.is_exact_instance_of::<pyo3::types::PyTuple>()
+                {
+                    for item in obj.try_iter()? {
+                        if !is_plain(py, &item?, depth + 1)? {
+                            return Ok(false);
+                        }
+                    }
+                    return Ok(true);
+                }
+                Ok(false)
+            }
+
+            // `toml_path` is the key path of `dict`, which values are
+            // reported at like everywhere else.
+            fn write_table<'py>(
+                py: pyo3::Python<'py>,
+                dict: &pyo3::Bound<'py, pyo3::types::PyDict>,
+                options: &$crate::core::options::DumpOptions<'_, 'py>,
+                toml_path: &mut smallvec::SmallVec<String, 32>,
+                first_table: &mut bool,
+                out: &mut $crate::core::buffer::FallibleString,
+            ) -> pyo3::PyResult<()> {
+                if !toml_path.is_empty() {
+                    if !*first_table {
+                        out.push(&'\n')?;
+                    }
+                    *first_table = false;
+
+                    out.push(&'[')?;
+                    for (i, key) in toml_path.iter().enumerate() {
+                        if i != 0 {
+                            out.push(&'.')?;
+                        }
+                        out.push(&Key::new(key.as_str()))?;
+                    }
+                    out.push(&"]\n")?;
+                }
+
+                for (key, value) in dict.iter() {
+                    if value.is_exact_instance_of::<pyo3::types::PyDict>() {
+                        continue;
+                    }
+                    *first_table = false;
+
+                    let key = key.cast::<pyo3::types::PyString>()?.to_str()?;
+                    toml_path.push(key.to_owned());
+                    let item = to_toml_impl(py, &value, options, toml_path);
+                    toml_path.pop();
+                    if let Item::Value(value) = item? {
+                        out.push(&Key::new(key))?;
+                        out.push(&" = ")?;
+                        out.push(&value)?;
+                        out.push(&'\n')?;
+                    }
+                }
+
+                for (key, value) in dict.iter() {
+                    if let Ok(table) = value.cast_exact::<pyo3::types::PyDict>() {
+                        toml_path.push(key.cast::<pyo3::types::PyString>()?.to_str()?.to_owned());
+                        write_table(py, table, options, toml_path, first_table, out)?;
+                        toml_path.pop();
+                    }
+                }
+
+                Ok(())
+            }
+
+            let Ok(dict) = obj.cast_exact::<pyo3::types::PyDict>() else {
+                return Ok(None);
+            };
+            if !is_plain(py, obj, 0)? {
+                return Ok(None);
+            }
+
+            let mut out = $crate::core::buffer::FallibleString::default();
+            write_table(
+                py,
+                dict,
+                options,
+                &mut smallvec::SmallVec::new(),
+                &mut true,
+                &mut out,
+            )?;
+            Ok(Some(out.into_string()))
+        }
+
+        // The value `encode_hook` gives for `obj`, or `obj` when there is no
+        // hook. Called once for every value, before its type is looked at.
+        fn encode<'py>(
+            obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            options: &$crate::core::options::DumpOptions<'_, 'py>,
+        ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::PyAny>> {
+            match options.encode_hook {
+                Some(hook) => hook.call(obj.clone()),
+                None => Ok(obj.clone()),
+            }
+        }
+
+        // Converts `obj` with an explicit stack of the tables and arrays being
+        // filled, so deeply nested values cannot overflow the native stack.
         fn to_toml_impl<'py>(
             py: pyo3::Python<'py>,
             obj: &pyo3::Bound<'py, pyo3::PyAny>,
-            inline_tables: Option<&rustc_hash::FxHashSet<String>>,
+            options: &$crate::core::options::DumpOptions<'_, 'py>,
             toml_path: &mut smallvec::SmallVec<String, 32>,
         ) -> pyo3::PyResult<Item> {
+            // The values whose frames are on `stack`, by address.
+            let mut open_values = rustc_hash::FxHashSet::default();
+            let mut stack = Vec::new();
+            let mut step = open(py, obj.clone(), options, toml_path, 0, &mut open_values)?;
+            loop {
+                let mut frame = match step {
+                    Step::Open(frame) => frame,
+                    Step::Done(item) => {
+                        let Some(mut frame) = stack.pop() else {
+                            return Ok(item);
+                        };
+                        frame.add(item, options, toml_path)?;
+                        frame
+                    }
+                };
+                step = match frame.next_child(options, toml_path)? {
+                    Some(child) => {
+                        let step = open(
+                            py,
+                            encode(&child, options)?,
+                            options,
+                            toml_path,
+                            stack.len() + 1,
+                            &mut open_values,
+                        )?;
+                        stack.push(frame);
+                        step
+                    }
+                    None => {
+                        open_values.remove(&frame.value.as_ptr());
+                        Step::Done(frame.finish(options, toml_path)?)
+                    }
+                };
+            }
+        }
+
+        // Converts `value`, or opens a frame for it when it is a table or an
+        // array with values to convert. `depth` is the number of frames it is
+        // inside of.
+        fn open<'py>(
+            py: pyo3::Python<'py>,
+            value: pyo3::Bound<'py, pyo3::PyAny>,
+            options: &$crate::core::options::DumpOptions<'_, '_>,
+            toml_path: &[String],
+            depth: usize,
+            open_values: &mut rustc_hash::FxHashSet<*mut pyo3::ffi::PyObject>,
+        ) -> pyo3::PyResult<Step<'py>> {
+            let mut frame = match convert(py, &value, options, toml_path)? {
+                Step::Open(frame) => frame,
+                step @ Step::Done(_) => return Ok(step),
+            };
+
+            if let Some(max) = options.max_depth
+                && depth > max
+            {
+                return Err($crate::toml_rs::TOMLEncodeError::new_err(format!(
+                    "Value at '{path}' nests deeper than max_depth ({max})",
+                    path = toml_path.join(".")
+                )));
+            }
+            // Nothing else stops a value that contains itself from being
+            // converted until memory runs out.
+            if !open_values.insert(value.as_ptr()) {
+                return Err($crate::toml_rs::TOMLEncodeError::new_err(format!(
+                    "Value at '{path}' contains itself",
+                    path = toml_path.join(".")
+                )));
+            }
+
+            // Conversions such as `attrs_to_dict` make a new mapping on every
+            // call, so the frame is marked with the value it was opened for.
+            frame.value = value;
+            Ok(Step::Open(frame))
+        }
+
+        enum Step<'py> {
+            // A finished value.
+            Done(Item),
+            // A table or array whose values are still to convert.
+            Open(Frame<'py>),
+        }
+
+        struct Frame<'py> {
+            // Held so its address stays unique while it is in `open_values`.
+            value: pyo3::Bound<'py, pyo3::PyAny>,
+            // Key-value pairs for tables, elements for arrays.
+            items: pyo3::Bound<'py, pyo3::types::PyIterator>,
+            // The index of the next array element.
+            index: usize,
+            container: Container,
+        }
+
+        enum Container {
+            Table(Table),
+            InlineTable(InlineTable),
+            Array(Array),
+        }
+
+        impl<'py> Frame<'py> {
+            fn new(
+                value: &pyo3::Bound<'py, pyo3::PyAny>,
+                items: pyo3::Bound<'py, pyo3::types::PyIterator>,
+                container: Container,
+            ) -> Self {
+                Self {
+                    value: value.clone(),
+                    items,
+                    index: 0,
+                    container,
+                }
+            }
+
+            // The next value to convert, with its key or index entered in
+            // `toml_path` and the hook's path until it is added.
+            fn next_child(
+                &mut self,
+                options: &$crate::core::options::DumpOptions<'_, 'py>,
+                toml_path: &mut smallvec::SmallVec<String, 32>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::PyAny>>> {
+                let Some(item) = self.items.next() else {
+                    return Ok(None);
+                };
+                let item = item?;
+
+                if matches!(self.container, Container::Array(_)) {
+                    $crate::core::hook::push(options.encode_hook, self.index)?;
+                    self.index += 1;
+                    return Ok(Some(item));
+                }
+
+                let py_tuple = item.cast_into::<pyo3::types::PyTuple>()?;
+                let py_key = py_tuple.get_item(0)?;
+                let key = py_key
+                    .clone()
+                    .cast_into::<pyo3::types::PyString>()
+                    .map_err(|_| {
+                        $crate::toml_rs::TOMLEncodeError::new_err(format!(
+                            "TOML table keys must be strings, got {py_type}",
+                            py_type = $crate::get_type!(py_key)
+                        ))
+                    })?;
+                let key_str =
+                    $crate::core::surrogates::to_str(&key, options.surrogates, toml_path, true)?
+                        .into_owned();
+
+                toml_path.push(key_str);
+                $crate::core::hook::push(options.encode_hook, &key)?;
+                Ok(Some(py_tuple.get_item(1)?))
+            }
+
+            // Adds the converted value of the last `next_child`.
+            fn add(
+                &mut self,
+                item: Item,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &mut smallvec::SmallVec<String, 32>,
+            ) -> pyo3::PyResult<()> {
+                $crate::core::hook::leave(options.encode_hook);
+                match &mut self.container {
+                    Container::Table(table) => {
+                        let key = toml_path.pop().unwrap_or_default();
+                        table.insert(&key, item);
+                    }
+                    Container::InlineTable(inline_table) => {
+                        let key = toml_path.pop().unwrap_or_default();
+                        let Item::Value(value) = item else {
+                            return Err($crate::toml_rs::TOMLEncodeError::new_err(
+                                "Inline tables can only contain values, not nested tables",
+                            ));
+                        };
+                        inline_table.insert(key, value);
+                    }
+                    Container::Array(array) => match item {
+                        Item::Value(value) => {
+                            array.push(value);
+                        }
+                        Item::Table(table) => {
+                            let inline_table = table.into_inline_table();
+                            array.push(Value::InlineTable(inline_table));
+                        }
+                        _ => {
+                            return Err($crate::toml_rs::TOMLEncodeError::new_err(
+                                "Arrays can only contain values or inline tables",
+                            ));
+                        }
+                    },
+                }
+                Ok(())
+            }
+
+            fn finish(
+                self,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &[String],
+            ) -> pyo3::PyResult<Item> {
+                match self.container {
+                    Container::Table(mut table) => {
+                        if let Some(order) = key_order(options, toml_path) {
+                            table.sort_values_by(|a, _, b, _| {
+                                key_rank(order, a).cmp(&key_rank(order, b))
+                            });
+                        }
+                        order_sections(&mut table, options.preserve_order);
+                        $to_toml_macro!(TomlTable, table)
+                    }
+                    Container::InlineTable(mut inline_table) => {
+                        if let Some(order) = key_order(options, toml_path) {
+                            inline_table.sort_values_by(|a, _, b, _| {
+                                key_rank(order, a).cmp(&key_rank(order, b))
+                            });
+                        }
+                        $to_toml_macro!(TomlInlineTable, inline_table)
+                    }
+                    Container::Array(array) => $to_toml_macro!(TomlArray, array),
+                }
+            }
+        }
+
+        fn key_order<'a>(
+            options: &$crate::core::options::DumpOptions<'a, '_>,
+            toml_path: &[String],
+        ) -> Option<&'a [String]> {
+            options
+                .key_order?
+                .get(&toml_path.join("."))
+                .map(Vec::as_slice)
+        }
+
+        // A table's key-values are written before its sub-tables, so a key-value
+        // that follows a sub-table in the dict has to move ahead of it. With
+        // `preserve_order`, the sub-table is written inline instead, and only
+        // the sub-tables after the last key-value keep their own section.
+        fn order_sections(table: &mut Table, preserve_order: bool) {
+            if !preserve_order {
+                table.sort_values_by(|_, a, _, b| b.is_value().cmp(&a.is_value()));
+                return;
+            }
+
+            let mut values_left = table.iter().filter(|(_, item)| item.is_value()).count();
+            for (_, item) in table.iter_mut() {
+                if item.is_value() {
+                    values_left -= 1;
+                } else if values_left > 0 {
+                    item.make_value();
+                }
+            }
+        }
+
+        // Listed keys sort first, in the order given. The sort is stable,
+        // so the rest keep their dict order after them.
+        fn key_rank(order: &[String], key: &Key) -> usize {
+            order
+                .iter()
+                .position(|listed| listed == key.get())
+                .unwrap_or(order.len())
+        }
+
+        fn convert<'py>(
+            py: pyo3::Python<'py>,
+            obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            options: &$crate::core::options::DumpOptions<'_, '_>,
+            toml_path: &[String],
+        ) -> pyo3::PyResult<Step<'py>> {
             fn get_decimal_type(
                 py: pyo3::Python<'_>,
             ) -> pyo3::PyResult<&pyo3::Bound<'_, pyo3::types::PyType>> {
@@ -71,6 +496,15 @@ macro_rules! impl_dumps {
                 MAPPING_TYPE.import(py, "collections.abc", "Mapping")
             }
 
+            fn get_formatted_type(
+                py: pyo3::Python<'_>,
+            ) -> pyo3::PyResult<&pyo3::Bound<'_, pyo3::types::PyType>> {
+                static FORMATTED_TYPE: pyo3::sync::PyOnceLock<pyo3::Py<pyo3::types::PyType>> =
+                    pyo3::sync::PyOnceLock::new();
+
+                FORMATTED_TYPE.import(py, "toml_rs.items", "Formatted")
+            }
+
             fn get_isinstance_func(
                 py: pyo3::Python<'_>,
             ) -> pyo3::PyResult<&pyo3::Bound<'_, pyo3::PyAny>> {
@@ -233,88 +667,443 @@ macro_rules! impl_dumps {
                 Ok(std::borrow::Cow::Borrowed(trimmed))
             }
 
-            fn mapping_to_toml_impl<'py>(
+            // numpy is never imported by us: values can only be numpy objects
+            // if the caller already imported it.
+            fn numpy_to_python<'py>(
                 py: pyo3::Python<'py>,
                 obj: &pyo3::Bound<'py, pyo3::PyAny>,
-                inline_tables: Option<&rustc_hash::FxHashSet<String>>,
-                toml_path: &mut smallvec::SmallVec<String, 32>,
-            ) -> pyo3::PyResult<Item> {
-                let items = obj.call_method0(pyo3::intern!(py, "items"))?;
-                if items.len()? == 0 {
-                    return $to_toml_macro!(TomlTable, Table::new());
-                }
-
-                let inline = inline_tables.is_some_and(|set| set.contains(&toml_path.join(".")));
-
-                if inline {
-                    let mut inline_table = InlineTable::new();
-                    for item in items.try_iter()? {
-                        let py_tuple = item?.cast_into::<pyo3::types::PyTuple>()?;
-                        let py_key = py_tuple.get_item(0)?;
-                        let key = py_key
-                            .clone()
-                            .cast_into::<pyo3::types::PyString>()
-                            .map_err(|_| {
-                                $crate::toml_rs::TOMLEncodeError::new_err(format!(
-                                    "TOML table keys must be strings, got {py_type}",
-                                    py_type = $crate::get_type!(py_key)
-                                ))
-                            })?;
-                        let value = py_tuple.get_item(1)?;
-                        let key_str = key.to_str()?;
-
-                        toml_path.push(key_str.to_owned());
-                        let item = to_toml_impl(py, &value, inline_tables, toml_path)?;
-                        toml_path.pop();
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::PyAny>>> {
+                let modules = py
+                    .import(pyo3::intern!(py, "sys"))?
+                    .getattr(pyo3::intern!(py, "modules"))?;
+                let Some(numpy) = modules
+                    .cast::<pyo3::types::PyDict>()?
+                    .get_item(pyo3::intern!(py, "numpy"))?
+                else {
+                    return Ok(None);
+                };
 
-                        if let Item::Value(val) = item {
-                            inline_table.insert(key_str, val);
-                        } else {
-                            return Err($crate::toml_rs::TOMLEncodeError::new_err(
-                                "Inline tables can only contain values, not nested tables",
-                            ));
+                if get_isinstance_func(py)?
+                    .call1((obj, numpy.getattr(pyo3::intern!(py, "datetime64"))?))?
+                    .is_truthy()?
+                {
+                    let unit = numpy
+                        .call_method1(
+                            pyo3::intern!(py, "datetime_data"),
+                            (obj.getattr(pyo3::intern!(py, "dtype"))?,),
+                        )?
+                        .get_item(0)?
+                        .extract::<String>()?;
+                    let target = if matches!(unit.as_str(), "Y" | "M" | "W" | "D") {
+                        "datetime64[D]"
+                    } else {
+                        "datetime64[us]"
+                    };
+
+                    let converted = obj.call_method1(pyo3::intern!(py, "astype"), (target,))?;
+                    if !converted.eq(obj)? && !obj.ne(obj)? {
+                        $crate::core::lossy::report(
+                            py,
+                            options.strict,
+                            format!(
+                                "{py_type} truncated to {target}",
+                                py_type = $crate::get_type!(obj)
+                            ),
+                            $crate::toml_rs::TOMLEncodeError::new_err,
+                        )?;
+                    }
+
+                    let value = converted.call_method0(pyo3::intern!(py, "item"))?;
+                    if value.is_none() {
+                        return Err($crate::toml_rs::TOMLEncodeError::new_err(
+                            "Cannot serialize numpy.datetime64('NaT') to TOML",
+                        ));
+                    }
+                    return Ok(Some(value));
+                }
+
+                if get_isinstance_func(py)?
+                    .call1((obj, numpy.getattr(pyo3::intern!(py, "ndarray"))?))?
+                    .is_truthy()?
+                {
+                    let ndim = obj.getattr(pyo3::intern!(py, "ndim"))?.extract::<usize>()?;
+                    let kind = obj
+                        .getattr(pyo3::intern!(py, "dtype"))?
+                        .getattr(pyo3::intern!(py, "kind"))?
+                        .extract::<String>()?;
+
+                    if ndim == 1 && matches!(kind.as_str(), "b" | "i" | "u" | "f") {
+                        return Ok(Some(obj.call_method0(pyo3::intern!(py, "tolist"))?));
+                    }
+                }
+
+                Ok(None)
+            }
+
+            // Only a working `utcoffset()` is required, so datetimes from pendulum,
+            // arrow or pytz with their own tzinfo types are handled alike.
+            fn utc_offset(
+                py: pyo3::Python<'_>,
+                obj: &pyo3::Bound<'_, pyo3::PyAny>,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &[String],
+            ) -> pyo3::PyResult<Option<Offset>> {
+                // A broken tzinfo is reported rather than written as a naive
+                // datetime, with the original error as the cause.
+                let failed = |err: pyo3::PyErr| {
+                    let error = $crate::toml_rs::TOMLEncodeError::new_err(format!(
+                        "Cannot get the UTC offset of {py_type} at '{path}'",
+                        py_type = $crate::get_type!(obj),
+                        path = toml_path.join(".")
+                    ));
+                    error.set_cause(py, Some(err));
+                    error
+                };
+
+                let utc_offset = obj
+                    .call_method0(pyo3::intern!(py, "utcoffset"))
+                    .map_err(failed)?;
+                if utc_offset.is_none() {
+                    return Ok(None);
+                }
+
+                let seconds = if let Ok(delta) = utc_offset.cast::<pyo3::types::PyDelta>() {
+                    delta.get_days() * 86400 + delta.get_seconds()
+                } else {
+                    utc_offset
+                        .call_method0(pyo3::intern!(py, "total_seconds"))
+                        .and_then(|seconds| seconds.extract::<f64>())
+                        .map_err(failed)? as i32
+                };
+
+                if seconds % 60 != 0 {
+                    $crate::core::lossy::report(
+                        py,
+                        options.strict,
+                        format!(
+                            "UTC offset of {py_type} at '{path}' truncated to whole minutes",
+                            py_type = $crate::get_type!(obj),
+                            path = toml_path.join(".")
+                        ),
+                        $crate::toml_rs::TOMLEncodeError::new_err,
+                    )?;
+                }
+
+                let minutes = seconds / 60;
+                let minutes = i16::try_from(minutes).map_err(|_| {
+                    $crate::toml_rs::TOMLEncodeError::new_err(format!(
+                        "UTC offset of {minutes} minutes at '{path}' is out of range for TOML",
+                        path = toml_path.join(".")
+                    ))
+                })?;
+                Ok(Some(Offset::Custom { minutes }))
+            }
+
+            // attrs classes list their fields in `__attrs_attrs__`; a field can opt
+            // out with `metadata={"toml": {"skip": True}}` or be renamed with
+            // `metadata={"toml": {"name": ...}}`.
+            fn attrs_to_dict<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::types::PyDict>>> {
+                let Ok(fields) = obj.get_type().getattr(pyo3::intern!(py, "__attrs_attrs__"))
+                else {
+                    return Ok(None);
+                };
+
+                let dict = pyo3::types::PyDict::new(py);
+                for field in fields.try_iter()? {
+                    let field = field?;
+                    let name = field.getattr(pyo3::intern!(py, "name"))?;
+                    let mut key = name.clone();
+
+                    let options = field
+                        .getattr(pyo3::intern!(py, "metadata"))?
+                        .call_method1(pyo3::intern!(py, "get"), (pyo3::intern!(py, "toml"),))?;
+                    if !options.is_none() {
+                        if options
+                            .call_method1(pyo3::intern!(py, "get"), (pyo3::intern!(py, "skip"),))?
+                            .is_truthy()?
+                        {
+                            continue;
+                        }
+
+                        let rename = options
+                            .call_method1(pyo3::intern!(py, "get"), (pyo3::intern!(py, "name"),))?;
+                        if !rename.is_none() {
+                            key = rename;
                         }
                     }
 
-                    return $to_toml_macro!(TomlInlineTable, inline_table);
-                }
-
-                let mut table = Table::new();
-                for item in items.try_iter()? {
-                    let py_tuple = item?.cast_into::<pyo3::types::PyTuple>()?;
-                    let py_key = py_tuple.get_item(0)?;
-                    let key = py_key
-                        .clone()
-                        .cast_into::<pyo3::types::PyString>()
-                        .map_err(|_| {
-                            $crate::toml_rs::TOMLEncodeError::new_err(format!(
-                                "TOML table keys must be strings, got {py_type}",
-                                py_type = $crate::get_type!(py_key)
-                            ))
-                        })?;
-                    let value = py_tuple.get_item(1)?;
-                    let key_str = key.to_str()?;
-
-                    toml_path.push(key_str.to_owned());
-                    let item = to_toml_impl(py, &value, inline_tables, toml_path)?;
-                    toml_path.pop();
+                    dict.set_item(key, obj.getattr(name.cast::<pyo3::types::PyString>()?)?)?;
+                }
+
+                Ok(Some(dict))
+            }
+
+            // pydantic models are dumped through `model_dump`, which already applies
+            // field aliases and `exclude` flags and converts nested models.
+            fn pydantic_to_python<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::PyAny>>> {
+                let modules = py
+                    .import(pyo3::intern!(py, "sys"))?
+                    .getattr(pyo3::intern!(py, "modules"))?;
+                let Some(pydantic) = modules
+                    .cast::<pyo3::types::PyDict>()?
+                    .get_item(pyo3::intern!(py, "pydantic"))?
+                else {
+                    return Ok(None);
+                };
+
+                if get_isinstance_func(py)?
+                    .call1((obj, pydantic.getattr(pyo3::intern!(py, "BaseModel"))?))?
+                    .is_truthy()?
+                {
+                    let kwargs = pyo3::types::PyDict::new(py);
+                    kwargs.set_item(pyo3::intern!(py, "by_alias"), true)?;
+                    return Ok(Some(obj.call_method(
+                        pyo3::intern!(py, "model_dump"),
+                        (),
+                        Some(&kwargs),
+                    )?));
+                }
+
+                Ok(None)
+            }
+
+            // `arrow.Arrow` wraps a `datetime` instead of subclassing it.
+            fn arrow_to_python<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::PyAny>>> {
+                let modules = py
+                    .import(pyo3::intern!(py, "sys"))?
+                    .getattr(pyo3::intern!(py, "modules"))?;
+                let Some(arrow) = modules
+                    .cast::<pyo3::types::PyDict>()?
+                    .get_item(pyo3::intern!(py, "arrow"))?
+                else {
+                    return Ok(None);
+                };
+
+                if get_isinstance_func(py)?
+                    .call1((obj, arrow.getattr(pyo3::intern!(py, "Arrow"))?))?
+                    .is_truthy()?
+                {
+                    return Ok(Some(obj.getattr(pyo3::intern!(py, "datetime"))?));
+                }
+
+                Ok(None)
+            }
+
+            // Proxies and mocks (e.g. freezegun's `FakeDatetime` behind a lazy
+            // proxy) can pass `isinstance(obj, datetime)` through `__class__`
+            // while failing the C-API type check. They are rebuilt from their
+            // fields, read as attributes.
+            fn datetime_like_to_python<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::PyAny>>> {
+                const TYPES: [(&str, &[&str]); 3] = [
+                    (
+                        "datetime",
+                        &[
+                            "year",
+                            "month",
+                            "day",
+                            "hour",
+                            "minute",
+                            "second",
+                            "microsecond",
+                            "tzinfo",
+                        ],
+                    ),
+                    ("date", &["year", "month", "day"]),
+                    (
+                        "time",
+                        &["hour", "minute", "second", "microsecond", "tzinfo"],
+                    ),
+                ];
+
+                let module = py.import(pyo3::intern!(py, "datetime"))?;
+                for (name, fields) in TYPES {
+                    let class = module.getattr(name)?;
+                    if get_isinstance_func(py)?.call1((obj, &class))?.is_truthy()? {
+                        let args = fields
+                            .iter()
+                            .map(|field| obj.getattr(*field))
+                            .collect::<pyo3::PyResult<Vec<_>>>()?;
+                        let kwargs = pyo3::types::PyDict::new(py);
+                        if name != "date" {
+                            kwargs.set_item("fold", obj.getattr(pyo3::intern!(py, "fold"))?)?;
+                        }
+                        return Ok(Some(
+                            class.call(pyo3::types::PyTuple::new(py, args)?, Some(&kwargs))?,
+                        ));
+                    }
+                }
+
+                Ok(None)
+            }
 
-                    table.insert(key_str, item);
+            // `pandas.Timestamp` subclasses `datetime` but carries nanoseconds the
+            // datetime C-API can't see, which `nanoseconds="truncate"` drops.
+            // `pandas.NaT` is a `datetime` whose fields are meaningless.
+            fn pandas_nanosecond(
+                py: pyo3::Python<'_>,
+                obj: &pyo3::Bound<'_, pyo3::PyAny>,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &[String],
+            ) -> pyo3::PyResult<u32> {
+                let modules = py
+                    .import(pyo3::intern!(py, "sys"))?
+                    .getattr(pyo3::intern!(py, "modules"))?;
+                let Some(pandas) = modules
+                    .cast::<pyo3::types::PyDict>()?
+                    .get_item(pyo3::intern!(py, "pandas"))?
+                else {
+                    return Ok(0);
+                };
+
+                if obj.is(&pandas.getattr(pyo3::intern!(py, "NaT"))?) {
+                    return Err($crate::toml_rs::TOMLEncodeError::new_err(format!(
+                        "Cannot serialize pandas.NaT at '{path}' to TOML",
+                        path = toml_path.join(".")
+                    )));
+                }
+
+                if !get_isinstance_func(py)?
+                    .call1((obj, pandas.getattr(pyo3::intern!(py, "Timestamp"))?))?
+                    .is_truthy()?
+                {
+                    return Ok(0);
+                }
+
+                let nanosecond = obj
+                    .getattr(pyo3::intern!(py, "nanosecond"))?
+                    .extract::<u32>()?;
+                if nanosecond != 0
+                    && options.nanoseconds == $crate::core::options::Nanoseconds::Truncate
+                {
+                    $crate::core::lossy::report(
+                        py,
+                        options.strict,
+                        format!(
+                            "{py_type} at '{path}' truncated to microseconds",
+                            py_type = $crate::get_type!(obj),
+                            path = toml_path.join(".")
+                        ),
+                        $crate::toml_rs::TOMLEncodeError::new_err,
+                    )?;
+                    return Ok(0);
+                }
+                Ok(nanosecond)
+            }
+
+            // The items of an array `sort_arrays` applies to, in order, or
+            // `None` to keep them as they are.
+            fn sorted_items<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &[String],
+            ) -> pyo3::PyResult<Option<pyo3::Bound<'py, pyo3::types::PyList>>> {
+                use $crate::core::options::SortArrays;
+
+                let applies = match &options.sort_arrays {
+                    SortArrays::Off => false,
+                    SortArrays::All => true,
+                    SortArrays::Strings => {
+                        let mut strings = true;
+                        for item in obj.try_iter()? {
+                            strings &= item?.is_instance_of::<pyo3::types::PyString>();
+                        }
+                        strings
+                    }
+                    SortArrays::Paths(paths) => paths.contains(&toml_path.join(".")),
+                };
+                if !applies {
+                    return Ok(None);
+                }
+
+                let items = obj.try_iter()?.collect::<pyo3::PyResult<Vec<_>>>()?;
+                let sorted = pyo3::types::PyList::new(py, items)?;
+                match sorted.sort() {
+                    Ok(()) => Ok(Some(sorted)),
+                    Err(err) if err.is_instance_of::<pyo3::exceptions::PyTypeError>(py) => Ok(None),
+                    Err(err) => Err(err),
+                }
+            }
+
+            fn open_mapping<'py>(
+                py: pyo3::Python<'py>,
+                obj: &pyo3::Bound<'py, pyo3::PyAny>,
+                options: &$crate::core::options::DumpOptions<'_, '_>,
+                toml_path: &[String],
+            ) -> pyo3::PyResult<Step<'py>> {
+                let items = obj.call_method0(pyo3::intern!(py, "items"))?;
+                if items.len()? == 0 {
+                    return $to_toml_macro!(TomlTable, Table::new()).map(Step::Done);
+                }
+
+                let inline = options
+                    .inline_tables
+                    .is_some_and(|set| set.contains(&toml_path.join(".")));
+                let container = if inline {
+                    Container::InlineTable(InlineTable::new())
+                } else {
+                    Container::Table(Table::new())
+                };
+                Ok(Step::Open(Frame::new(obj, items.try_iter()?, container)))
+            }
+
+            // `toml_rs.items` values subclass the builtin scalars, so plain
+            // builtins can skip the `raw` lookup entirely.
+            if !obj.is_exact_instance_of::<pyo3::types::PyString>()
+                && !obj.is_exact_instance_of::<pyo3::types::PyInt>()
+                && !obj.is_exact_instance_of::<pyo3::types::PyFloat>()
+                && !obj.is_exact_instance_of::<pyo3::types::PyBool>()
+                && !obj.is_exact_instance_of::<pyo3::types::PyDict>()
+                && !obj.is_exact_instance_of::<pyo3::types::PyList>()
+                && get_isinstance_func(py)?
+                    .call1((obj, get_formatted_type(py)?))?
+                    .is_truthy()?
+            {
+                let raw = obj.getattr(pyo3::intern!(py, "raw"))?;
+                if let Ok(raw) = raw.cast::<pyo3::types::PyString>()
+                    && let Ok(value) = raw.to_str()?.parse::<Value>()
+                {
+                    return Ok(Step::Done(Item::Value(value)));
                 }
-                $to_toml_macro!(TomlTable, table)
             }
 
             if let Ok(s) = obj.cast::<pyo3::types::PyString>() {
-                return $to_toml_macro!(String, s.to_str()?.to_owned());
+                let text =
+                    $crate::core::surrogates::to_str(&s, options.surrogates, toml_path, false)?;
+                if options.parse_datetime_strings
+                    && let Ok(datetime) = text.parse::<Datetime>()
+                {
+                    return $to_toml_macro!(Datetime, datetime).map(Step::Done);
+                }
+                return $to_toml_macro!(String, text.into_owned()).map(Step::Done);
             }
             if let Ok(b) = obj.cast::<pyo3::types::PyBool>() {
-                return $to_toml_macro!(Boolean, b.is_true());
+                return $to_toml_macro!(Boolean, b.is_true()).map(Step::Done);
             }
             if let Ok(int) = obj.cast::<pyo3::types::PyInt>() {
-                return $to_toml_macro!(BigNum, int.str()?.to_str()?);
+                return $to_toml_macro!(BigNum, int.str()?.to_str()?).map(Step::Done);
             }
             if let Ok(float) = obj.cast::<pyo3::types::PyFloat>() {
-                return $to_toml_macro!(BigNum, float.str()?.to_str()?);
+                if let Some(text) =
+                    $crate::core::options::FloatFormat::find(options.float_formats, toml_path)
+                        .and_then(|format| format.write(float.value()))
+                {
+                    return $to_toml_macro!(BigNum, text.as_str()).map(Step::Done);
+                }
+                return $to_toml_macro!(BigNum, float.str()?.to_str()?).map(Step::Done);
             }
 
             if get_isinstance_func(py)?
@@ -323,102 +1112,120 @@ macro_rules! impl_dumps {
             {
                 let py_str = obj.str()?;
                 let normalized = normalize_decimal_str(py_str.to_str()?)?;
-                return $to_toml_macro!(BigNum, normalized.as_ref());
+                return $to_toml_macro!(BigNum, normalized.as_ref()).map(Step::Done);
             }
 
             if let Ok(py_datetime) = obj.cast::<pyo3::types::PyDateTime>() {
-                let date = $toml_dt_macro!(Date, py_datetime);
-                let time = $toml_dt_macro!(Time, py_datetime);
-
-                let offset = py_datetime.get_tzinfo().and_then(|tzinfo| {
-                    let utc_offset = tzinfo
-                        .call_method1(pyo3::intern!(py, "utcoffset"), (py_datetime,))
-                        .ok()?;
-                    if utc_offset.is_none() {
-                        return None;
+                let nanosecond = if obj.is_exact_instance_of::<pyo3::types::PyDateTime>() {
+                    0
+                } else {
+                    pandas_nanosecond(py, obj, options, toml_path)?
+                };
+
+                use $crate::core::options::DatetimeFormat;
+
+                let format = DatetimeFormat::find(options.datetime_formats, toml_path);
+                let (py_datetime, offset) = match format {
+                    Some(DatetimeFormat::Utc)
+                        if !obj.call_method0(pyo3::intern!(py, "utcoffset"))?.is_none() =>
+                    {
+                        let utc = obj
+                            .call_method1(
+                                pyo3::intern!(py, "astimezone"),
+                                (pyo3::types::PyTzInfo::utc(py)?,),
+                            )?
+                            .cast_into::<pyo3::types::PyDateTime>()?;
+                        (utc, Some(Offset::Z))
                     }
-                    let delta = utc_offset.cast::<pyo3::types::PyDelta>().ok()?;
-                    let seconds = delta.get_days() * 86400 + delta.get_seconds();
-                    Some(Offset::Custom {
-                        minutes: i16::try_from(seconds / 60).ok()?,
-                    })
-                });
+                    // A naive datetime could be in any zone, so it has no UTC
+                    // time to write.
+                    Some(DatetimeFormat::Utc) => {
+                        return Err($crate::toml_rs::TOMLEncodeError::new_err(format!(
+                            "Cannot write naive datetime at '{path}' as UTC",
+                            path = toml_path.join(".")
+                        )));
+                    }
+                    Some(DatetimeFormat::Date | DatetimeFormat::Local) => {
+                        (py_datetime.clone(), None)
+                    }
+                    None => (
+                        py_datetime.clone(),
+                        utc_offset(py, obj, options, toml_path)?,
+                    ),
+                };
+
+                let date = $toml_dt_macro!(Date, py_datetime);
+                let time = if format == Some(DatetimeFormat::Date) {
+                    None
+                } else {
+                    Some($toml_dt_macro!(Time, py_datetime, nanosecond))
+                };
 
-                let datetime = $toml_dt_macro!(Datetime, Some(date), Some(time), offset);
-                return $to_toml_macro!(Datetime, datetime);
+                let datetime = $toml_dt_macro!(Datetime, Some(date), time, offset);
+                return $to_toml_macro!(Datetime, datetime).map(Step::Done);
             } else if let Ok(py_date) = obj.cast::<pyo3::types::PyDate>() {
                 let date = $toml_dt_macro!(Date, py_date);
                 let datetime = $toml_dt_macro!(Datetime, Some(date), None, None);
-                return $to_toml_macro!(Datetime, datetime);
+                return $to_toml_macro!(Datetime, datetime).map(Step::Done);
             } else if let Ok(py_time) = obj.cast::<pyo3::types::PyTime>() {
                 let time = $toml_dt_macro!(Time, py_time);
                 let datetime = $toml_dt_macro!(Datetime, None, Some(time), None);
-                return $to_toml_macro!(Datetime, datetime);
+                return $to_toml_macro!(Datetime, datetime).map(Step::Done);
             }
 
             if let Ok(dict) = obj.cast::<pyo3::types::PyDict>() {
-                return mapping_to_toml_impl(py, dict.as_any(), inline_tables, toml_path);
+                return open_mapping(py, dict.as_any(), options, toml_path);
             }
 
             if get_isinstance_func(py)?
                 .call1((obj, get_mapping_type(py)?))?
                 .is_truthy()?
             {
-                return mapping_to_toml_impl(py, obj, inline_tables, toml_path);
+                return open_mapping(py, obj, options, toml_path);
             }
 
-            if let Ok(list) = obj.cast::<pyo3::types::PyList>() {
-                if list.is_empty() {
-                    return $to_toml_macro!(TomlArray, Array::new());
+            if obj.is_instance_of::<pyo3::types::PyList>()
+                || obj.is_instance_of::<pyo3::types::PyTuple>()
+            {
+                if obj.is_empty()? {
+                    return $to_toml_macro!(TomlArray, Array::new()).map(Step::Done);
                 }
 
-                let mut array = Array::new();
-                for item in list.iter() {
-                    let items = to_toml_impl(py, &item, inline_tables, toml_path)?;
-                    match items {
-                        Item::Value(value) => {
-                            array.push(value);
-                        }
-                        Item::Table(table) => {
-                            let inline_table = table.into_inline_table();
-                            array.push(Value::InlineTable(inline_table));
-                        }
-                        _ => {
-                            return Err($crate::toml_rs::TOMLEncodeError::new_err(
-                                "Arrays can only contain values or inline tables",
-                            ));
-                        }
-                    }
-                }
+                let items = match sorted_items(py, obj, options, toml_path)? {
+                    Some(sorted) => sorted.into_any().try_iter()?,
+                    None => obj.try_iter()?,
+                };
+                return Ok(Step::Open(Frame::new(
+                    obj,
+                    items,
+                    Container::Array(Array::new()),
+                )));
+            }
 
-                return $to_toml_macro!(TomlArray, array);
+            if let Some(value) = pydantic_to_python(py, obj)? {
+                return convert(py, &value, options, toml_path);
             }
 
-            if let Ok(py_tuple) = obj.cast::<pyo3::types::PyTuple>() {
-                if py_tuple.is_empty() {
-                    return $to_toml_macro!(TomlArray, Array::new());
-                }
+            if let Some(dict) = attrs_to_dict(py, obj)? {
+                return open_mapping(py, dict.as_any(), options, toml_path);
+            }
 
-                let mut array = Array::new();
-                for item in py_tuple.iter() {
-                    let items = to_toml_impl(py, &item, inline_tables, toml_path)?;
-                    match items {
-                        Item::Value(value) => {
-                            array.push(value);
-                        }
-                        Item::Table(table) => {
-                            let inline_table = table.into_inline_table();
-                            array.push(Value::InlineTable(inline_table));
-                        }
-                        _ => {
-                            return Err($crate::toml_rs::TOMLEncodeError::new_err(
-                                "Arrays can only contain values or inline tables",
-                            ));
-                        }
-                    }
-                }
+            if let Some(value) = numpy_to_python(py, obj, options)? {
+                return convert(py, &value, options, toml_path);
+            }
 
-                return $to_toml_macro!(TomlArray, array);
+            if let Some(value) = arrow_to_python(py, obj)? {
+                return convert(py, &value, options, toml_path);
+            }
+
+            if let Some(value) = datetime_like_to_python(py, obj)? {
+                return convert(py, &value, options, toml_path);
+            }
+
+            if options.fallback == $crate::core::options::Fallback::Vars
+                && let Ok(vars) = obj.getattr(pyo3::intern!(py, "__dict__"))
+            {
+                return open_mapping(py, &vars, options, toml_path);
             }
 
             Err($crate::toml_rs::TOMLEncodeError::new_err(format!(
@@ -434,8 +1241,16 @@ macro_rules! get_type {
     ($obj:expr) => {
         format!(
             "{} ({})",
+            // Long reprs are cut to their first 80 characters, enough to
+            // recognize the value without flooding the message.
             $obj.repr()
-                .map(|s| s.to_string())
+                .map(|s| {
+                    let s = s.to_string();
+                    match s.char_indices().nth(80) {
+                        Some((end, _)) => format!("{}...", &s[..end]),
+                        None => s,
+                    }
+                })
                 .unwrap_or_else(|_| String::from("<unknown>")),
             $obj.get_type()
                 .repr()
@@ -0,0 +1,725 @@
+#[macro_export]
+macro_rules! impl_edit {
+    (
+        $update_fn:ident,
+        $array_insert_fn:ident,
+        $array_remove_fn:ident,
+        $decorate_fn:ident,
+        $graft_fn:ident,
+        $move_fn:ident,
+        $trivia_fn:ident,
+        $set_trivia_fn:ident,
+        $python_to_toml_fn:ident
+    ) => {
+        pub fn $update_fn(
+            py: pyo3::Python<'_>,
+            doc: &str,
+            changes: &pyo3::Bound<'_, pyo3::types::PyDict>,
+        ) -> pyo3::PyResult<String> {
+            let mut parsed = parse_document(doc)?;
+
+            for (path, value) in changes.iter() {
+                let path = path.extract::<String>()?;
+                let keys = key_path(&path)?;
+                set_path(py, parsed.as_table_mut(), &path, &keys, &value)?;
+            }
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Inserts like `list.insert`, or appends when `index` is `None`. A
+        // single-line array that grows past `line_width` is reflowed to one
+        // element per line.
+        pub fn $array_insert_fn(
+            py: pyo3::Python<'_>,
+            doc: &str,
+            path: &str,
+            index: Option<isize>,
+            value: &pyo3::Bound<'_, pyo3::PyAny>,
+            line_width: usize,
+        ) -> pyo3::PyResult<String> {
+            let mut parsed = parse_document(doc)?;
+            let value = to_value(py, value)?;
+            let keys = key_path(path)?;
+            let Some((last, parents)) = keys.split_last() else {
+                return Err(invalid_path(path));
+            };
+
+            let array = table_at(parsed.as_table_mut(), path, parents)?
+                .entry(last)
+                .or_insert_with(|| Item::Value(Value::Array(Array::new())))
+                .as_array_mut()
+                .ok_or_else(|| not_an_array(path, last))?;
+
+            let len = array.len();
+            let index = index.map_or(len, |index| list_index(index, len));
+            insert_value(array, index, value);
+
+            // `key = [...]`
+            if !is_multiline(array) && last.len() + 3 + array.to_string().trim().len() > line_width
+            {
+                reflow(array);
+            }
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Removes the first element equal to `value`, like `list.remove`.
+        pub fn $array_remove_fn(
+            py: pyo3::Python<'_>,
+            doc: &str,
+            path: &str,
+            value: &pyo3::Bound<'_, pyo3::PyAny>,
+        ) -> pyo3::PyResult<String> {
+            let mut parsed = parse_document(doc)?;
+            let value = to_value(py, value)?;
+            let keys = key_path(path)?;
+            let Some(last) = keys.last() else {
+                return Err(invalid_path(path));
+            };
+
+            let array = lookup_mut(parsed.as_table_mut(), &keys)
+                .ok_or_else(|| {
+                    pyo3::exceptions::PyKeyError::new_err(format!("Key path not found: {path:?}"))
+                })?
+                .as_array_mut()
+                .ok_or_else(|| not_an_array(path, last))?;
+
+            let index = array
+                .iter()
+                .position(|element| values_equal(element, &value))
+                .ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "{} not in {path:?}",
+                        value.to_string().trim()
+                    ))
+                })?;
+            remove_value(array, index);
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Sets the comment after the item at `path` and the raw text on the
+        // lines before it. An empty comment removes the existing one.
+        pub fn $decorate_fn(
+            doc: &str,
+            path: &str,
+            comment: Option<&str>,
+            before: Option<&str>,
+        ) -> pyo3::PyResult<String> {
+            let mut parsed = parse_document(doc)?;
+            let (mut key, item) = entry_mut(parsed.as_table_mut(), path)?;
+
+            let decor = match item {
+                Item::Value(value) => {
+                    if let Some(before) = before {
+                        key.leaf_decor_mut().set_prefix(before);
+                    }
+                    value.decor_mut()
+                }
+                Item::Table(table) => {
+                    if let Some(before) = before {
+                        table.decor_mut().set_prefix(before);
+                    }
+                    table.decor_mut()
+                }
+                Item::ArrayOfTables(_) | Item::None => return Err(not_decoratable(path)),
+            };
+            match comment {
+                Some("") => decor.set_suffix(""),
+                Some(comment) => decor.set_suffix(format!(" # {comment}")),
+                None => {}
+            }
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // The text around the item at `path`: `before` its key or header,
+        // `before_eq` and `after_eq` around the `=` of a value, and `after` it
+        // to the end of the line, trailing comment included.
+        pub fn $trivia_fn(doc: &str, path: &str) -> pyo3::PyResult<Vec<(&'static str, String)>> {
+            let mut parsed = parse_document(doc)?;
+            match entry_mut(parsed.as_table_mut(), path)? {
+                (key, Item::Value(value)) => Ok(vec![
+                    ("before", raw_text(key.leaf_decor().prefix())),
+                    ("before_eq", raw_text(key.leaf_decor().suffix())),
+                    ("after_eq", raw_text(value.decor().prefix())),
+                    ("after", raw_text(value.decor().suffix())),
+                ]),
+                (_, Item::Table(table)) => Ok(vec![
+                    ("before", raw_text(table.decor().prefix())),
+                    ("after", raw_text(table.decor().suffix())),
+                ]),
+                (_, Item::ArrayOfTables(_) | Item::None) => Err(not_decoratable(path)),
+            }
+        }
+
+        // Replaces the parts of the text around the item at `path` that are
+        // given, named like `trivia` names them. `before` may hold blank and
+        // comment lines, `after` a comment, and the rest only spaces and tabs.
+        pub fn $set_trivia_fn(
+            doc: &str,
+            path: &str,
+            before: Option<&str>,
+            before_eq: Option<&str>,
+            after_eq: Option<&str>,
+            after: Option<&str>,
+        ) -> pyo3::PyResult<String> {
+            let invalid = |name: &str, text: &str| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid {name} for {path:?}: {text:?}"
+                ))
+            };
+            if let Some(text) = before
+                && !text.rsplit_once('\n').map_or_else(
+                    || is_blank(text),
+                    |(lines, last)| lines.split('\n').all(is_line_end) && is_blank(last),
+                )
+            {
+                return Err(invalid("before", text));
+            }
+            for (name, text) in [("before_eq", before_eq), ("after_eq", after_eq)] {
+                if let Some(text) = text
+                    && !is_blank(text)
+                {
+                    return Err(invalid(name, text));
+                }
+            }
+            if let Some(text) = after
+                && (text.contains('\n') || !is_line_end(text))
+            {
+                return Err(invalid("after", text));
+            }
+
+            let mut parsed = parse_document(doc)?;
+            let (decor, is_table) = match entry_mut(parsed.as_table_mut(), path)? {
+                (mut key, Item::Value(value)) => {
+                    if let Some(before) = before {
+                        key.leaf_decor_mut().set_prefix(before);
+                    }
+                    if let Some(before_eq) = before_eq {
+                        key.leaf_decor_mut().set_suffix(before_eq);
+                    }
+                    (value.decor_mut(), false)
+                }
+                (_, Item::Table(table)) => {
+                    if let Some(before) = before {
+                        table.decor_mut().set_prefix(before);
+                    }
+                    (table.decor_mut(), true)
+                }
+                (_, Item::ArrayOfTables(_) | Item::None) => return Err(not_decoratable(path)),
+            };
+            if is_table && (before_eq.is_some() || after_eq.is_some()) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "{path:?} is a table, which has no `=`"
+                )));
+            }
+            if let Some(after_eq) = after_eq {
+                decor.set_prefix(after_eq);
+            }
+            if let Some(after) = after {
+                decor.set_suffix(after);
+            }
+
+            // Catches what the checks above let through, like a comment after
+            // a value in an inline table.
+            let rendered = $crate::core::buffer::render(&parsed)?;
+            match rendered.parse::<DocumentMut>() {
+                Ok(_) => Ok(rendered),
+                Err(_) => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid trivia for {path:?}"
+                ))),
+            }
+        }
+
+        // The key and item at `path`, to change how they are written.
+        fn entry_mut<'a>(
+            table: &'a mut Table,
+            path: &str,
+        ) -> pyo3::PyResult<(KeyMut<'a>, &'a mut Item)> {
+            let keys = key_path(path)?;
+            let Some((last, parents)) = keys.split_last() else {
+                return Err(invalid_path(path));
+            };
+
+            let not_found =
+                || pyo3::exceptions::PyKeyError::new_err(format!("Key path not found: {path:?}"));
+            let parent: &mut dyn TableLike = if parents.is_empty() {
+                table
+            } else {
+                lookup_mut(table, parents)
+                    .and_then(Item::as_table_like_mut)
+                    .ok_or_else(not_found)?
+            };
+            parent.get_key_value_mut(last).ok_or_else(not_found)
+        }
+
+        fn not_decoratable(path: &str) -> pyo3::PyErr {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Cannot decorate {path:?}: not a value or table"
+            ))
+        }
+
+        fn raw_text(raw: Option<&RawString>) -> String {
+            raw.and_then(RawString::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        }
+
+        fn is_blank(text: &str) -> bool {
+            text.chars().all(|ch| ch == ' ' || ch == '\t')
+        }
+
+        // Whether `text` can end a line after a key or header: blanks and an
+        // optional comment.
+        fn is_line_end(text: &str) -> bool {
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            is_blank(text.split_once('#').map_or(text, |(blank, _)| blank))
+        }
+
+        // Copies an item from another document to `path`, formatting and
+        // comments included. `source_key` picks a top-level item of `source`;
+        // without it the whole document becomes a table.
+        pub fn $graft_fn(
+            doc: &str,
+            path: &str,
+            source: &str,
+            source_key: Option<&str>,
+        ) -> pyo3::PyResult<String> {
+            let mut parsed = parse_document(doc)?;
+            let source_doc = parse_document(source)?;
+            let keys = key_path(path)?;
+            let Some((last, parents)) = keys.split_last() else {
+                return Err(invalid_path(path));
+            };
+
+            let mut item = match source_key {
+                Some(key) => source_doc.as_table().get(key).cloned().ok_or_else(|| {
+                    pyo3::exceptions::PyKeyError::new_err(format!("Key not found: {key:?}"))
+                })?,
+                None => Item::Table(source_doc.as_table().clone()),
+            };
+            clear_positions(&mut item);
+
+            table_at(parsed.as_table_mut(), path, parents)?.insert(last, item);
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Moves the table at `path`, its sub-tables and the comments and blank
+        // lines above their headers, to just before or after the table at the
+        // other path and its own sub-tables.
+        pub fn $move_fn(
+            doc: &str,
+            path: &str,
+            before: Option<&str>,
+            after: Option<&str>,
+        ) -> pyo3::PyResult<String> {
+            let (anchor, after) = match (before, after) {
+                (Some(anchor), None) => (anchor, false),
+                (None, Some(anchor)) => (anchor, true),
+                _ => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "Pass exactly one of before and after",
+                    ));
+                }
+            };
+            let mut parsed = parse_document(doc)?;
+            let keys = key_path(path)?;
+            let anchor_keys = key_path(anchor)?;
+            if anchor_keys.starts_with(&keys) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Cannot move {path:?} next to {anchor:?}, which is inside it"
+                )));
+            }
+
+            // Every header with whether it is written, in the order they are
+            // visited and then in the order they are written.
+            let mut headers = Vec::new();
+            let mut last_position = 0;
+            visit_headers(
+                parsed.as_table_mut(),
+                &mut Vec::new(),
+                &mut |keys, table| {
+                    if let Some(position) = table.position() {
+                        last_position = position;
+                    }
+                    headers.push((keys.to_vec(), last_position, !table.is_implicit()));
+                },
+            );
+            let mut order = (0..headers.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&i| headers[i].1);
+            let first = |order: &[usize]| order.iter().copied().find(|&i| headers[i].2);
+            let old_first = first(&order);
+
+            let (moved, mut order): (Vec<_>, Vec<_>) = order
+                .into_iter()
+                .partition(|&i| headers[i].0.starts_with(&keys));
+            if moved.is_empty() {
+                return Err(table_not_found(path));
+            }
+            let mut anchored = order
+                .iter()
+                .enumerate()
+                .filter(|&(_, &i)| headers[i].0.starts_with(&anchor_keys))
+                .map(|(at, _)| at);
+            let at = if after {
+                anchored.last().map(|at| at + 1)
+            } else {
+                anchored.next()
+            }
+            .ok_or_else(|| table_not_found(anchor))?;
+
+            order.splice(at..at, moved);
+            let new_first = first(&order);
+
+            // The first header is written without a blank line above it, so
+            // the blank line goes with the header that is no longer first.
+            let has_body = parsed
+                .as_table()
+                .iter()
+                .any(|(_, item)| item.is_value() || item.as_table().is_some_and(Table::is_dotted));
+            let swap_blank_line = !has_body && old_first != new_first;
+
+            let mut ranks = vec![0; headers.len()];
+            for (rank, &i) in (0..).zip(&order) {
+                ranks[i] = rank;
+            }
+            let mut index = 0;
+            visit_headers(parsed.as_table_mut(), &mut Vec::new(), &mut |_, table| {
+                table.set_position(Some(ranks[index]));
+                if swap_blank_line {
+                    let prefix = table
+                        .decor()
+                        .prefix()
+                        .and_then(RawString::as_str)
+                        .unwrap_or_default()
+                        .to_owned();
+                    if Some(index) == new_first {
+                        let prefix = prefix.strip_prefix('\n').unwrap_or(&prefix).to_owned();
+                        table.decor_mut().set_prefix(prefix);
+                    } else if Some(index) == old_first && !prefix.starts_with('\n') {
+                        table.decor_mut().set_prefix(format!("\n{prefix}"));
+                    }
+                }
+                index += 1;
+            });
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Calls `f` with every table written under a header and its key path,
+        // in the order toml_edit visits them when it writes the document.
+        fn visit_headers(
+            table: &mut Table,
+            keys: &mut Vec<String>,
+            f: &mut impl FnMut(&[String], &mut Table),
+        ) {
+            for (key, item) in table.iter_mut() {
+                keys.push(key.get().to_owned());
+                match item {
+                    Item::Table(table) => {
+                        if !table.is_dotted() {
+                            f(keys, table);
+                        }
+                        visit_headers(table, keys, f);
+                    }
+                    Item::ArrayOfTables(tables) => {
+                        for table in tables.iter_mut() {
+                            f(keys, table);
+                            visit_headers(table, keys, f);
+                        }
+                    }
+                    Item::Value(_) | Item::None => {}
+                }
+                keys.pop();
+            }
+        }
+
+        fn table_not_found(path: &str) -> pyo3::PyErr {
+            pyo3::exceptions::PyKeyError::new_err(format!("Table not found: {path:?}"))
+        }
+
+        // Tables without a position are written right after the table before
+        // them, which is where a grafted table belongs.
+        fn clear_positions(item: &mut Item) {
+            let clear = |table: &mut Table| {
+                table.set_position(None);
+                for (_, child) in table.iter_mut() {
+                    clear_positions(child);
+                }
+            };
+
+            match item {
+                Item::Table(table) => clear(table),
+                Item::ArrayOfTables(tables) => tables.iter_mut().for_each(clear),
+                Item::Value(_) | Item::None => {}
+            }
+        }
+
+        pub fn parse_document(doc: &str) -> pyo3::PyResult<DocumentMut> {
+            doc.parse::<DocumentMut>()
+                .map_err(|err| $crate::error::parse_error(&err, doc))
+        }
+
+        fn key_path(path: &str) -> pyo3::PyResult<Vec<String>> {
+            $crate::document::parse_key_path(path).ok_or_else(|| invalid_path(path))
+        }
+
+        fn invalid_path(path: &str) -> pyo3::PyErr {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid key path: {path:?}"))
+        }
+
+        fn not_an_array(path: &str, key: &str) -> pyo3::PyErr {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Cannot update {path:?}: {key:?} is not an array"
+            ))
+        }
+
+        pub fn to_value(
+            py: pyo3::Python<'_>,
+            obj: &pyo3::Bound<'_, pyo3::PyAny>,
+        ) -> pyo3::PyResult<Value> {
+            $python_to_toml_fn(py, obj, &$crate::core::options::DumpOptions::default())?
+                .into_value()
+                .map_err(|_| {
+                    $crate::toml_rs::TOMLEncodeError::new_err(format!(
+                        "Cannot serialize {py_type} to a TOML value",
+                        py_type = $crate::get_type!(obj)
+                    ))
+                })
+        }
+
+        fn list_index(index: isize, len: usize) -> usize {
+            if index < 0 {
+                len.saturating_sub(index.unsigned_abs())
+            } else {
+                index.unsigned_abs().min(len)
+            }
+        }
+
+        // Compares what the values mean, not how they are written, so `'a'`
+        // matches `"a"` and `0x10` matches `16`.
+        fn values_equal(a: &Value, b: &Value) -> bool {
+            match (a, b) {
+                (Value::String(a), Value::String(b)) => a.value() == b.value(),
+                (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+                (Value::Float(a), Value::Float(b)) => a.value().total_cmp(b.value()).is_eq(),
+                (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+                (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+                (Value::Array(a), Value::Array(b)) => {
+                    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| values_equal(a, b))
+                }
+                (Value::InlineTable(a), Value::InlineTable(b)) => {
+                    a.len() == b.len()
+                        && a.iter()
+                            .all(|(key, a)| b.get(key).is_some_and(|b| values_equal(a, b)))
+                }
+                _ => false,
+            }
+        }
+
+        fn is_multiline(array: &Array) -> bool {
+            array
+                .trailing()
+                .as_str()
+                .is_some_and(|trailing| trailing.contains('\n'))
+                || array.iter().any(|value| line_prefix(value).is_some())
+        }
+
+        // Same layout as `pretty` output: one element per line, indented by
+        // four spaces, with a trailing comma.
+        fn reflow(array: &mut Array) {
+            for value in array.iter_mut() {
+                value.decor_mut().set_prefix("\n    ");
+                value.decor_mut().set_suffix("");
+            }
+            array.set_trailing("\n");
+            array.set_trailing_comma(true);
+        }
+
+        pub fn lookup_mut<'a>(
+            table: &'a mut Table,
+            keys: &[impl AsRef<str>],
+        ) -> Option<&'a mut Item> {
+            let (first, rest) = keys.split_first()?;
+            let mut item = table.get_mut(first.as_ref())?;
+            for key in rest {
+                item = item.get_mut(key.as_ref())?;
+            }
+            Some(item)
+        }
+
+        // `None` removes the key. Replacing a scalar keeps the whitespace and
+        // trailing comment around the old value, so only the value itself changes.
+        fn set_path(
+            py: pyo3::Python<'_>,
+            table: &mut Table,
+            path: &str,
+            keys: &[String],
+            value: &pyo3::Bound<'_, pyo3::PyAny>,
+        ) -> pyo3::PyResult<()> {
+            let Some((last, parents)) = keys.split_last() else {
+                return Ok(());
+            };
+
+            let current = table_at(table, path, parents)?;
+
+            if value.is_none() {
+                current.remove(last);
+                return Ok(());
+            }
+
+            let item =
+                $python_to_toml_fn(py, value, &$crate::core::options::DumpOptions::default())?;
+
+            match (current.get_mut(last), item) {
+                (Some(Item::Value(existing)), Item::Value(new)) => replace_value(existing, new),
+                (Some(existing @ Item::Table(_)), mut item @ Item::Table(_)) => {
+                    keep_item_format(existing, &mut item);
+                    *existing = item;
+                }
+                (_, item) => {
+                    current.insert(last, item);
+                }
+            }
+
+            Ok(())
+        }
+
+        pub fn replace_value(existing: &mut Value, mut new: Value) {
+            keep_format(existing, &mut new);
+            *existing = new;
+        }
+
+        // Values rebuilt from Python objects lose how they were written, so
+        // every part of `new` that still holds the same value as `old` is
+        // written the way `old` was: `0xDEAD_BEEF` in an array that gained an
+        // element stays as is. Changed parts keep the layout around them.
+        fn keep_format(old: &Value, new: &mut Value) {
+            if values_equal(old, new) {
+                *new = old.clone();
+                return;
+            }
+
+            match (old, &mut *new) {
+                (Value::Array(old), Value::Array(new)) => {
+                    for (index, value) in new.iter_mut().enumerate() {
+                        if let Some(old) = old.get(index) {
+                            keep_format(old, value);
+                        } else if let Some(last) = old.iter().last() {
+                            *value.decor_mut() = last.decor().clone();
+                        }
+                    }
+                    new.set_trailing(old.trailing().clone());
+                    new.set_trailing_comma(old.trailing_comma());
+                }
+                (Value::InlineTable(old), Value::InlineTable(new)) => {
+                    for (key, value) in new.iter_mut() {
+                        if let Some(old) = old.get(key.get()) {
+                            keep_format(old, value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            *new.decor_mut() = old.decor().clone();
+        }
+
+        fn keep_item_format(old: &Item, new: &mut Item) {
+            match (old, new) {
+                (Item::Value(old), Item::Value(new)) => keep_format(old, new),
+                (Item::Table(old), Item::Table(new)) => {
+                    for (key, item) in new.iter_mut() {
+                        if let Some(old) = old.get(key.get()) {
+                            keep_item_format(old, item);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Walks down to the table at `keys`, creating missing ones as implicit
+        // tables so no empty headers are written for them.
+        pub fn table_at<'a>(
+            table: &'a mut Table,
+            path: &str,
+            keys: &[impl AsRef<str>],
+        ) -> pyo3::PyResult<&'a mut dyn TableLike> {
+            let mut current: &mut dyn TableLike = table;
+            for key in keys {
+                let key = key.as_ref();
+                current = current
+                    .entry(key)
+                    .or_insert_with(|| {
+                        let mut table = Table::new();
+                        table.set_implicit(true);
+                        Item::Table(table)
+                    })
+                    .as_table_like_mut()
+                    .ok_or_else(|| {
+                        pyo3::exceptions::PyValueError::new_err(format!(
+                            "Cannot update {path:?}: {key:?} is not a table"
+                        ))
+                    })?;
+            }
+            Ok(current)
+        }
+
+        // Indentation of a multi-line array element, without any comment lines
+        // that belong to the element.
+        fn line_prefix(value: &Value) -> Option<String> {
+            let prefix = value.decor().prefix().and_then(RawString::as_str)?;
+            prefix.rfind('\n').map(|nl| prefix[nl..].to_owned())
+        }
+
+        // Inserts `value` laid out like its neighbours: on its own line in a
+        // multi-line array, separated by a space otherwise. Padding before the
+        // closing bracket stays at the end.
+        pub fn insert_value(array: &mut Array, index: usize, mut value: Value) {
+            let neighbour = array.get(index).or_else(|| array.iter().last());
+            if let Some(prefix) = neighbour.and_then(line_prefix) {
+                value.decor_mut().set_prefix(prefix);
+                value.decor_mut().set_suffix("");
+            } else if index == 0
+                && let Some(first) = array.get_mut(0)
+            {
+                if let Some(prefix) = first.decor().prefix().cloned() {
+                    value.decor_mut().set_prefix(prefix);
+                }
+                first.decor_mut().set_prefix(" ");
+            }
+
+            if index == array.len()
+                && let Some(last) = array.iter_mut().last()
+                && let Some(suffix) = last.decor().suffix().cloned()
+            {
+                value.decor_mut().set_suffix(suffix);
+                last.decor_mut().set_suffix("");
+            }
+
+            array.insert_formatted(index, value);
+        }
+
+        pub fn remove_value(array: &mut Array, index: usize) -> Value {
+            let removed = array.remove(index);
+            if line_prefix(&removed).is_none() {
+                if index == 0
+                    && let Some(first) = array.get_mut(0)
+                    && let Some(prefix) = removed.decor().prefix()
+                {
+                    first.decor_mut().set_prefix(prefix.clone());
+                }
+                if index == array.len()
+                    && let Some(last) = array.iter_mut().last()
+                    && let Some(suffix) = removed.decor().suffix()
+                {
+                    last.decor_mut().set_suffix(suffix.clone());
+                }
+            }
+            removed
+        }
+    };
+}
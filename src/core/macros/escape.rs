@@ -0,0 +1,76 @@
+#[macro_export]
+macro_rules! impl_escapes {
+    () => {
+        // Rewrites strings and keys holding control characters so their
+        // numeric escapes use one form. Tables with such a key have all their
+        // keys re-inserted, in order, keeping how the others are written.
+        pub struct Escapes(pub $crate::core::options::EscapeForm);
+
+        impl Escapes {
+            fn key(&self, key: &Key) -> Key {
+                if !$crate::core::escape::needs_escape(key.get()) {
+                    return key.clone();
+                }
+                Key::parse(&$crate::core::escape::basic_string(key.get(), self.0))
+                    .ok()
+                    .and_then(|keys| keys.into_iter().next())
+                    .unwrap_or_else(|| key.clone())
+            }
+        }
+
+        impl visit_mut::VisitMut for Escapes {
+            fn visit_table_mut(&mut self, node: &mut Table) {
+                if node
+                    .iter()
+                    .any(|(key, _)| $crate::core::escape::needs_escape(key))
+                {
+                    let keys = node
+                        .iter()
+                        .filter_map(|(key, _)| node.get_key_value(key))
+                        .map(|(key, _)| self.key(key))
+                        .collect::<Vec<_>>();
+                    for key in keys {
+                        if let Some(item) = node.remove(key.get()) {
+                            node.insert_formatted(&key, item);
+                        }
+                    }
+                }
+
+                visit_mut::visit_table_mut(self, node);
+            }
+
+            fn visit_inline_table_mut(&mut self, node: &mut InlineTable) {
+                if node
+                    .iter()
+                    .any(|(key, _)| $crate::core::escape::needs_escape(key))
+                {
+                    let keys = node
+                        .iter()
+                        .filter_map(|(key, _)| node.get_key_value(key))
+                        .map(|(key, _)| self.key(key))
+                        .collect::<Vec<_>>();
+                    for key in keys {
+                        if let Some(value) = node.remove(key.get()) {
+                            node.insert_formatted(&key, value);
+                        }
+                    }
+                }
+
+                visit_mut::visit_inline_table_mut(self, node);
+            }
+
+            fn visit_value_mut(&mut self, node: &mut Value) {
+                if let Value::String(string) = node
+                    && $crate::core::escape::needs_escape(string.value())
+                    && let Ok(mut escaped) =
+                        $crate::core::escape::basic_string(string.value(), self.0).parse::<Value>()
+                {
+                    *escaped.decor_mut() = node.decor().clone();
+                    *node = escaped;
+                }
+
+                visit_mut::visit_value_mut(self, node);
+            }
+        }
+    };
+}
@@ -0,0 +1,66 @@
+#[macro_export]
+macro_rules! impl_table_iter {
+    ($iter:ident) => {
+        // Yields `(key, value)` for each top-level entry of a document, converting
+        // a value to Python only when the iterator reaches it.
+        #[pyo3::pyclass(module = "toml_rs")]
+        pub struct $iter {
+            // Borrows from `doc`, so it is declared first to be dropped first.
+            entries: std::vec::IntoIter<(
+                Spanned<std::borrow::Cow<'static, str>>,
+                Spanned<DeValue<'static>>,
+            )>,
+            parse_float: pyo3::Py<pyo3::PyAny>,
+            doc: Box<str>,
+        }
+
+        impl $iter {
+            pub fn new(
+                py: pyo3::Python<'_>,
+                doc: &str,
+                parse_float: pyo3::Py<pyo3::PyAny>,
+            ) -> pyo3::PyResult<Self> {
+                let doc = Box::<str>::from(doc);
+                // SAFETY: the parsed entries borrow from the heap allocation behind
+                // `doc`, which is stored next to them, never mutated, and outlives
+                // them because `entries` is dropped first.
+                let source: &'static str = unsafe { &*std::ptr::from_ref::<str>(&doc) };
+
+                let parsed = py
+                    .detach(|| DeTable::parse(source))
+                    .map_err(|err| $crate::error::parse_error(&err, source))?;
+
+                Ok(Self {
+                    entries: parsed
+                        .into_inner()
+                        .into_iter()
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                    parse_float,
+                    doc,
+                })
+            }
+        }
+
+        #[pyo3::pymethods]
+        impl $iter {
+            fn __iter__(slf: pyo3::PyRef<'_, Self>) -> pyo3::PyRef<'_, Self> {
+                slf
+            }
+
+            fn __next__<'py>(
+                &mut self,
+                py: pyo3::Python<'py>,
+            ) -> pyo3::PyResult<Option<(String, pyo3::Bound<'py, pyo3::PyAny>)>> {
+                let Some((key, value)) = self.entries.next() else {
+                    return Ok(None);
+                };
+
+                let options = $crate::core::options::LoadOptions::plain(self.parse_float.bind(py));
+                let value = toml_to_python(py, &value, &options, &self.doc)?;
+
+                Ok(Some((key.into_inner().into_owned(), value)))
+            }
+        }
+    };
+}
@@ -0,0 +1,137 @@
+#[macro_export]
+macro_rules! impl_pyproject {
+    ($add_fn:ident, $remove_fn:ident) => {
+        pub fn $add_fn(doc: &str, requirement: &str, group: Option<&str>) -> pyo3::PyResult<String> {
+            let (raw_name, spec) = $crate::core::pyproject::split_requirement(requirement)
+                .ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "Invalid requirement: {requirement:?}"
+                    ))
+                })?;
+            let name = $crate::core::pyproject::normalize_name(raw_name);
+
+            let mut parsed = parse_document(doc)?;
+            let poetry = uses_poetry(&parsed);
+            let path = dependencies_path(poetry, group);
+            let dotted = path.join(".");
+
+            if poetry {
+                if spec.starts_with('[') || spec.contains(';') {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Cannot add {requirement:?} to {dotted:?}: extras and markers are not supported"
+                    )));
+                }
+                let constraint = if spec.is_empty() { "*" } else { spec };
+
+                let table = table_at(parsed.as_table_mut(), &dotted, &path)?;
+                let key = poetry_key(table, &name).unwrap_or_else(|| raw_name.to_owned());
+                match table.get_mut(&key) {
+                    Some(Item::Value(Value::InlineTable(inline))) => {
+                        match inline.get_mut("version") {
+                            Some(existing) => replace_value(existing, constraint.into()),
+                            None => {
+                                inline.insert("version", constraint.into());
+                            }
+                        }
+                    }
+                    Some(Item::Value(existing)) => replace_value(existing, constraint.into()),
+                    _ => {
+                        table.insert(&key, Item::Value(constraint.into()));
+                    }
+                }
+            } else {
+                let Some((key, parents)) = path.split_last() else {
+                    unreachable!("dependency paths are never empty");
+                };
+                let array = table_at(parsed.as_table_mut(), &dotted, parents)?
+                    .entry(key)
+                    .or_insert_with(|| Item::Value(Value::Array(Array::new())))
+                    .as_array_mut()
+                    .ok_or_else(|| {
+                        pyo3::exceptions::PyValueError::new_err(format!(
+                            "Cannot update {dotted:?}: {key:?} is not an array"
+                        ))
+                    })?;
+
+                match array.iter().position(|value| requirement_matches(value, &name)) {
+                    Some(index) => {
+                        array.replace(index, requirement);
+                    }
+                    None => {
+                        let len = array.len();
+                        insert_value(array, len, requirement.into());
+                    }
+                }
+            }
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        pub fn $remove_fn(doc: &str, requirement: &str, group: Option<&str>) -> pyo3::PyResult<String> {
+            let name = $crate::core::pyproject::split_requirement(requirement)
+                .map(|(name, _)| $crate::core::pyproject::normalize_name(name))
+                .ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "Invalid requirement: {requirement:?}"
+                    ))
+                })?;
+
+            let mut parsed = parse_document(doc)?;
+            let poetry = uses_poetry(&parsed);
+            let path = dependencies_path(poetry, group);
+            let item = lookup_mut(parsed.as_table_mut(), &path);
+
+            if poetry {
+                if let Some(table) = item.and_then(Item::as_table_like_mut)
+                    && let Some(key) = poetry_key(table, &name)
+                {
+                    table.remove(&key);
+                }
+            } else if let Some(array) = item.and_then(Item::as_array_mut) {
+                for index in (0..array.len()).rev() {
+                    if array.get(index).is_some_and(|value| requirement_matches(value, &name)) {
+                        remove_value(array, index);
+                    }
+                }
+            }
+
+            $crate::core::buffer::render(&parsed)
+        }
+
+        // Poetry keeps dependencies in a table keyed by name, everything else
+        // in PEP 621 requirement arrays.
+        fn uses_poetry(doc: &DocumentMut) -> bool {
+            let project = doc
+                .get("project")
+                .and_then(|project| project.get("dependencies"));
+            let poetry = doc
+                .get("tool")
+                .and_then(|tool| tool.get("poetry"))
+                .and_then(|poetry| poetry.get("dependencies"));
+            project.is_none() && poetry.is_some()
+        }
+
+        fn dependencies_path(poetry: bool, group: Option<&str>) -> Vec<&str> {
+            match (poetry, group) {
+                (true, Some(group)) => vec!["tool", "poetry", "group", group, "dependencies"],
+                (true, None) => vec!["tool", "poetry", "dependencies"],
+                (false, Some(group)) => vec!["project", "optional-dependencies", group],
+                (false, None) => vec!["project", "dependencies"],
+            }
+        }
+
+        fn poetry_key(table: &dyn TableLike, name: &str) -> Option<String> {
+            table
+                .iter()
+                .find(|(key, _)| $crate::core::pyproject::normalize_name(key) == name)
+                .map(|(key, _)| key.to_owned())
+        }
+
+        fn requirement_matches(value: &Value, name: &str) -> bool {
+            value
+                .as_str()
+                .and_then($crate::core::pyproject::split_requirement)
+                .is_some_and(|(other, _)| $crate::core::pyproject::normalize_name(other) == name)
+        }
+    };
+}
@@ -0,0 +1,48 @@
+#[macro_export]
+macro_rules! impl_quote_keys {
+    () => {
+        // Writes every key as a basic string, including keys that could be
+        // bare. Keys are re-inserted in order so the table layout is kept.
+        pub struct QuoteKeys;
+
+        fn quoted(key: &str) -> Key {
+            let repr =
+                $crate::core::escape::basic_string(key, $crate::core::options::EscapeForm::Unicode);
+
+            Key::parse(&repr)
+                .ok()
+                .and_then(|keys| keys.into_iter().next())
+                .unwrap_or_else(|| Key::new(key))
+        }
+
+        impl visit_mut::VisitMut for QuoteKeys {
+            fn visit_table_mut(&mut self, node: &mut Table) {
+                let keys = node
+                    .iter()
+                    .map(|(key, _)| key.to_owned())
+                    .collect::<Vec<_>>();
+                for key in keys {
+                    if let Some(item) = node.remove(&key) {
+                        node.insert_formatted(&quoted(&key), item);
+                    }
+                }
+
+                visit_mut::visit_table_mut(self, node);
+            }
+
+            fn visit_inline_table_mut(&mut self, node: &mut InlineTable) {
+                let keys = node
+                    .iter()
+                    .map(|(key, _)| key.to_owned())
+                    .collect::<Vec<_>>();
+                for key in keys {
+                    if let Some(value) = node.remove(&key) {
+                        node.insert_formatted(&quoted(&key), value);
+                    }
+                }
+
+                visit_mut::visit_inline_table_mut(self, node);
+            }
+        }
+    };
+}
@@ -0,0 +1,240 @@
+#[macro_export]
+macro_rules! impl_raw {
+    ($raw_span_fn:ident, $visit_fn:ident, $layout_fn:ident) => {
+        // Where the item at `path` is written in `doc`, as character offsets,
+        // or `None` when there is no such item. A table with a header runs
+        // from the header to its last key-value, leaving out sub-tables that
+        // have headers of their own.
+        pub fn $raw_span_fn(doc: &str, path: &str) -> pyo3::PyResult<Option<(usize, usize)>> {
+            let parsed =
+                Document::parse(doc).map_err(|err| $crate::error::parse_error(&err, doc))?;
+            let keys = $crate::document::parse_key_path(path).unwrap_or_default();
+            let Some((last, parents)) = keys.split_last() else {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid key path: {path:?}"
+                )));
+            };
+
+            let mut parent: &dyn TableLike = parsed.as_table();
+            for key in parents {
+                match parent.get(key).and_then(Item::as_table_like) {
+                    Some(table) => parent = table,
+                    None => return Ok(None),
+                }
+            }
+
+            let Some((key, item)) = parent.get_key_value(last) else {
+                return Ok(None);
+            };
+            // A dotted table starts at its key, like `limits` in `limits.cpu = 2`.
+            let key_span = item
+                .as_table()
+                .filter(|table| table.is_dotted())
+                .and_then(|_| key.span());
+
+            Ok([key_span, item_span(item)]
+                .into_iter()
+                .flatten()
+                .reduce(union)
+                .map(|span| char_span(doc, span)))
+        }
+
+        // Calls `callback(path, span, kind)` for every item in `doc`, parents
+        // before children. `path` is a tuple of keys and array indices, `span`
+        // is as in `$raw_span_fn` and `kind` is the item's TOML type name, like
+        // "table" or "inline table". When the callback returns `False` the
+        // item's children are skipped. Without `spans` every span is `None`,
+        // for a `doc` the caller never sees.
+        pub fn $visit_fn(
+            doc: &str,
+            callback: &pyo3::Bound<'_, pyo3::PyAny>,
+            spans: bool,
+        ) -> pyo3::PyResult<()> {
+            let parsed =
+                Document::parse(doc).map_err(|err| $crate::error::parse_error(&err, doc))?;
+            let mut visitor = Visitor {
+                doc,
+                callback,
+                spans,
+                path: Vec::new(),
+            };
+            visitor.entries(parsed.as_table())
+        }
+
+        // Where every value of `doc` is written, and how long every table and
+        // array of tables is, by key path.
+        pub fn $layout_fn(doc: &str) -> pyo3::PyResult<$crate::template::Layout> {
+            fn visit_table(
+                table: &Table,
+                path: &mut Vec<$crate::template::Part>,
+                layout: &mut $crate::template::Layout,
+            ) {
+                layout.tables.push((path.clone(), table.len()));
+                for (key, item) in table.iter() {
+                    path.push($crate::template::Part::Key(key.to_owned()));
+                    match item {
+                        Item::Value(value) => {
+                            if let Some(span) = value.span() {
+                                layout.values.push((path.clone(), span));
+                            }
+                        }
+                        Item::Table(table) => visit_table(table, path, layout),
+                        Item::ArrayOfTables(array) => {
+                            layout.arrays.push((path.clone(), array.len()));
+                            for (index, table) in array.iter().enumerate() {
+                                path.push($crate::template::Part::Index(index));
+                                visit_table(table, path, layout);
+                                path.pop();
+                            }
+                        }
+                        Item::None => {}
+                    }
+                    path.pop();
+                }
+            }
+
+            let parsed =
+                Document::parse(doc).map_err(|err| $crate::error::parse_error(&err, doc))?;
+            let mut layout = $crate::template::Layout::default();
+            visit_table(parsed.as_table(), &mut Vec::new(), &mut layout);
+            Ok(layout)
+        }
+
+        struct Visitor<'a, 'py> {
+            doc: &'a str,
+            callback: &'a pyo3::Bound<'py, pyo3::PyAny>,
+            spans: bool,
+            path: Vec<pyo3::Bound<'py, pyo3::PyAny>>,
+        }
+
+        impl<'py> Visitor<'_, 'py> {
+            fn entries(&mut self, table: &dyn TableLike) -> pyo3::PyResult<()> {
+                for (key, item) in table.iter() {
+                    self.enter(key, |visitor| visitor.item(item, table.key(key)))?;
+                }
+                Ok(())
+            }
+
+            fn item(&mut self, item: &Item, key: Option<&Key>) -> pyo3::PyResult<()> {
+                match item {
+                    Item::Value(value) => self.value(value),
+                    Item::Table(table) => {
+                        let key_span = key.filter(|_| table.is_dotted()).and_then(Key::span);
+                        let span = [key_span, table_span(table, false)]
+                            .into_iter()
+                            .flatten()
+                            .reduce(union);
+                        if self.call(span, item.type_name())? {
+                            self.entries(table)?;
+                        }
+                        Ok(())
+                    }
+                    Item::ArrayOfTables(array) => {
+                        if self.call(item_span(item), item.type_name())? {
+                            for (index, table) in array.iter().enumerate() {
+                                self.enter(index, |visitor| {
+                                    if visitor.call(table_span(table, false), "table")? {
+                                        visitor.entries(table)?;
+                                    }
+                                    Ok(())
+                                })?;
+                            }
+                        }
+                        Ok(())
+                    }
+                    Item::None => Ok(()),
+                }
+            }
+
+            fn value(&mut self, value: &Value) -> pyo3::PyResult<()> {
+                if !self.call(value.span(), value.type_name())? {
+                    return Ok(());
+                }
+                if let Some(array) = value.as_array() {
+                    for (index, value) in array.iter().enumerate() {
+                        self.enter(index, |visitor| visitor.value(value))?;
+                    }
+                } else if let Some(table) = value.as_inline_table() {
+                    self.entries(table)?;
+                }
+                Ok(())
+            }
+
+            fn enter(
+                &mut self,
+                part: impl pyo3::IntoPyObject<'py>,
+                f: impl FnOnce(&mut Self) -> pyo3::PyResult<()>,
+            ) -> pyo3::PyResult<()> {
+                use pyo3::IntoPyObjectExt;
+
+                self.path.push(part.into_bound_py_any(self.callback.py())?);
+                let result = f(self);
+                self.path.pop();
+                result
+            }
+
+            // Whether to go on into the item's children.
+            fn call(
+                &self,
+                span: Option<std::ops::Range<usize>>,
+                kind: &str,
+            ) -> pyo3::PyResult<bool> {
+                use pyo3::types::PyAnyMethods;
+
+                let path = pyo3::types::PyTuple::new(self.callback.py(), &self.path)?;
+                let span = span
+                    .filter(|_| self.spans)
+                    .map(|span| char_span(self.doc, span));
+                let descend = self.callback.call1((path, span, kind))?;
+                Ok(!matches!(descend.extract::<bool>(), Ok(false)))
+            }
+        }
+
+        fn char_span(doc: &str, span: std::ops::Range<usize>) -> (usize, usize) {
+            let start = doc[..span.start].chars().count();
+            (start, start + doc[span].chars().count())
+        }
+
+        fn item_span(item: &Item) -> Option<std::ops::Range<usize>> {
+            match item {
+                Item::Value(value) => value.span(),
+                Item::Table(table) => table_span(table, false),
+                Item::ArrayOfTables(array) => array
+                    .iter()
+                    .filter_map(|table| table_span(table, false))
+                    .reduce(union),
+                Item::None => None,
+            }
+        }
+
+        // With `subtables` the span also takes in sub-tables that have headers
+        // of their own. Tables without a header only exist through their
+        // children, so they always do.
+        fn table_span(table: &Table, subtables: bool) -> Option<std::ops::Range<usize>> {
+            let headed = !table.is_implicit() && !table.is_dotted();
+            let subtables = subtables || !headed;
+
+            let mut span = if headed { table.span() } else { None };
+            for (key, item) in table.iter() {
+                let key_span = table.key(key).and_then(Key::span);
+                let entry = match item {
+                    Item::Table(child) if !subtables && !child.is_dotted() => continue,
+                    Item::ArrayOfTables(_) if !subtables => continue,
+                    Item::Value(value) => value.span(),
+                    Item::Table(child) => table_span(child, subtables),
+                    Item::ArrayOfTables(array) => array
+                        .iter()
+                        .filter_map(|table| table_span(table, subtables))
+                        .reduce(union),
+                    Item::None => continue,
+                };
+                span = [span, key_span, entry].into_iter().flatten().reduce(union);
+            }
+            span
+        }
+
+        fn union(a: std::ops::Range<usize>, b: std::ops::Range<usize>) -> std::ops::Range<usize> {
+            a.start.min(b.start)..a.end.max(b.end)
+        }
+    };
+}
@@ -0,0 +1,72 @@
+#[macro_export]
+macro_rules! impl_tokenize {
+    ($tokenize_fn:ident) => {
+        // Tokens come from the parser's events rather than the bare lexer, so
+        // keys and values are told apart. Syntax errors become `error` tokens
+        // instead of raising, since highlighters see incomplete documents all the
+        // time. Offsets are in characters, to slice the Python string directly.
+        pub fn $tokenize_fn<'py>(
+            py: pyo3::Python<'py>,
+            doc: &str,
+        ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyList>> {
+            let source = Source::new(doc);
+            let events = py.detach(|| {
+                let tokens = source.lex().collect::<Vec<_>>();
+                let mut events = Vec::<Event>::new();
+                parse_document(&tokens, &mut events, &mut ());
+                events
+            });
+
+            let out = pyo3::types::PyList::empty(py);
+            let mut cursor = (0, 0);
+            for event in &events {
+                let kind = match event.kind() {
+                    EventKind::SimpleKey => "key",
+                    EventKind::Scalar => match source
+                        .get(event)
+                        .map(|raw| raw.decode_scalar(&mut (), &mut ()))
+                    {
+                        Some(ScalarKind::String) => "string",
+                        Some(ScalarKind::Boolean(_)) => "boolean",
+                        Some(ScalarKind::DateTime) => "datetime",
+                        Some(ScalarKind::Float | ScalarKind::Integer(_)) => "number",
+                        None => "error",
+                    },
+                    EventKind::Comment => "comment",
+                    EventKind::Whitespace => "whitespace",
+                    EventKind::Newline => "newline",
+                    EventKind::Error => "error",
+                    EventKind::StdTableOpen
+                    | EventKind::StdTableClose
+                    | EventKind::ArrayTableOpen
+                    | EventKind::ArrayTableClose
+                    | EventKind::InlineTableOpen
+                    | EventKind::InlineTableClose
+                    | EventKind::ArrayOpen
+                    | EventKind::ArrayClose
+                    | EventKind::KeySep
+                    | EventKind::KeyValSep
+                    | EventKind::ValueSep => "punctuation",
+                };
+
+                let span = event.span();
+                let start = char_offset(doc, &mut cursor, span.start());
+                let end = char_offset(doc, &mut cursor, span.end());
+                pyo3::types::PyListMethods::append(&out, (kind, start, end))?;
+            }
+
+            Ok(out)
+        }
+
+        // Events arrive in document order, so counting resumes from the previous
+        // offset instead of the start of the document.
+        fn char_offset(doc: &str, cursor: &mut (usize, usize), byte: usize) -> usize {
+            if byte < cursor.0 {
+                *cursor = (0, 0);
+            }
+            cursor.1 += doc.get(cursor.0..byte).map_or(0, |s| s.chars().count());
+            cursor.0 = byte;
+            cursor.1
+        }
+    };
+}
@@ -0,0 +1,36 @@
+use std::{borrow::Cow, ops::Range};
+
+use pyo3::{prelude::*, types::PyDict};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+use crate::{core::options::NormalizeKeys, error::duplicate_key};
+
+// Sets `key` on a table being loaded. With `NormalizeKeys::Nfc` the key is
+// composed to NFC first, so two spellings of the same key are reported as a
+// duplicate instead of ending up side by side in the dict.
+pub fn set_key<'py>(
+    py_dict: &Bound<'py, PyDict>,
+    key: &str,
+    value: Bound<'py, PyAny>,
+    normalize: NormalizeKeys,
+    doc: &str,
+    span: Range<usize>,
+) -> PyResult<()> {
+    if normalize == NormalizeKeys::None {
+        return py_dict.set_item(key, value);
+    }
+
+    let key = if is_nfc(key) {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(key.nfc().collect())
+    };
+    if py_dict.contains(key.as_ref())? {
+        return Err(duplicate_key(
+            format!("duplicate key `{key}` after NFC normalization"),
+            doc,
+            span,
+        ));
+    }
+    py_dict.set_item(key.as_ref(), value)
+}
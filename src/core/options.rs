@@ -0,0 +1,522 @@
+use pyo3::{IntoPyObjectExt, exceptions::PyValueError, prelude::*, types::PyDict};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::document::parse_key_path;
+
+use crate::core::{
+    hook::{TableHook, ValueHook},
+    intern::{StringCache, TimezoneCache},
+    located::Locations,
+};
+
+// What `dumps` does with a value it has no conversion for.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fallback {
+    #[default]
+    Error,
+    // Serialize the object's `__dict__`, like `vars(obj)`.
+    Vars,
+}
+
+impl Fallback {
+    pub fn parse(value: Option<&str>) -> PyResult<Self> {
+        match value {
+            None => Ok(Self::Error),
+            Some("vars") => Ok(Self::Vars),
+            Some(other) => Err(PyValueError::new_err(format!(
+                "Unsupported fallback: {other}"
+            ))),
+        }
+    }
+}
+
+// What `dumps` does with lone surrogates, which Python strings can hold
+// (e.g. file names decoded with `surrogateescape`) but UTF-8 cannot.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Surrogates {
+    #[default]
+    Error,
+    // Write U+FFFD in their place.
+    Replace,
+    // Write them as the text `\udcxx`, like Python's `backslashreplace`.
+    BackslashReplace,
+}
+
+impl Surrogates {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "error" => Ok(Self::Error),
+            "replace" => Ok(Self::Replace),
+            "backslashreplace" => Ok(Self::BackslashReplace),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported surrogates policy: {other}"
+            ))),
+        }
+    }
+}
+
+// How `dumps` writes control characters that have no short escape. `\x`
+// escapes are new in TOML 1.1.0.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum EscapeForm {
+    // `\u001B`
+    #[default]
+    Unicode,
+    // `\U0000001B`
+    LongUnicode,
+    // `\x1B`
+    Hex,
+}
+
+impl EscapeForm {
+    pub fn parse(value: &str, toml_version: &str) -> PyResult<Self> {
+        match (value, toml_version) {
+            ("u", _) => Ok(Self::Unicode),
+            ("U", _) => Ok(Self::LongUnicode),
+            ("x", "1.0.0") => Err(PyValueError::new_err(
+                "escape_form=\"x\" requires TOML 1.1.0",
+            )),
+            ("x", _) => Ok(Self::Hex),
+            (other, _) => Err(PyValueError::new_err(format!(
+                "Unsupported escape_form: {other}"
+            ))),
+        }
+    }
+}
+
+// Which arrays `dumps` sorts. Arrays whose items Python cannot order, like
+// tables, are always left as they are.
+#[derive(Default)]
+pub enum SortArrays {
+    #[default]
+    Off,
+    All,
+    // Only arrays made of strings.
+    Strings,
+    // Only the arrays at these key paths.
+    Paths(FxHashSet<String>),
+}
+
+impl SortArrays {
+    pub fn extract(value: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(value) = value else {
+            return Ok(Self::Off);
+        };
+        if let Ok(paths) = value.extract::<FxHashSet<String>>() {
+            return Ok(Self::Paths(paths));
+        }
+        match value.extract::<&str>()? {
+            "all" => Ok(Self::All),
+            "strings" => Ok(Self::Strings),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported sort_arrays: {other}"
+            ))),
+        }
+    }
+}
+
+// The dotted key path `pattern`, where a bare `*` stands for any one key
+// and `None` holds its place. Keys are split at the dots outside quotes and
+// read as TOML keys, so `"a.b".c` is two keys.
+fn key_pattern(pattern: &str) -> PyResult<Vec<Option<String>>> {
+    let invalid = || PyValueError::new_err(format!("Invalid key pattern: {pattern:?}"));
+
+    let mut keys = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in pattern.char_indices().chain([(pattern.len(), '.')]) {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '.' => {
+                let key = pattern[start..i].trim();
+                keys.push(if key == "*" {
+                    None
+                } else {
+                    let mut path = parse_key_path(key).ok_or_else(invalid)?;
+                    if path.len() != 1 {
+                        return Err(invalid());
+                    }
+                    path.pop()
+                });
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    if quote.is_some() {
+        return Err(invalid());
+    }
+    Ok(keys)
+}
+
+fn pattern_matches(pattern: &[Option<String>], path: &[String]) -> bool {
+    pattern.len() == path.len()
+        && pattern
+            .iter()
+            .zip(path)
+            .all(|(pattern, key)| pattern.as_ref().is_none_or(|pattern| pattern == key))
+}
+
+// A `float_formats` entry of `dumps`. Floats at key paths that match
+// `pattern` get `precision` digits after the point, and are never written
+// with an exponent when `fixed` is set.
+pub struct FloatFormat {
+    pattern: Vec<Option<String>>,
+    precision: Option<usize>,
+    fixed: bool,
+}
+
+impl FloatFormat {
+    pub fn extract(value: Option<&Bound<'_, PyAny>>) -> PyResult<Vec<Self>> {
+        let Some(value) = value else {
+            return Ok(Vec::new());
+        };
+
+        let mut formats = Vec::new();
+        for (pattern, format) in value.cast::<PyDict>()?.iter() {
+            let mut precision = None;
+            let mut fixed = false;
+            for (option, value) in format.cast::<PyDict>()?.iter() {
+                match option.extract::<&str>()? {
+                    "precision" => precision = Some(value.extract::<usize>()?),
+                    "fixed" => fixed = value.extract::<bool>()?,
+                    other => {
+                        return Err(PyValueError::new_err(format!(
+                            "Unsupported float format option: {other}"
+                        )));
+                    }
+                }
+            }
+            formats.push(Self {
+                pattern: key_pattern(pattern.extract::<&str>()?)?,
+                precision,
+                fixed,
+            });
+        }
+        Ok(formats)
+    }
+
+    // The first of `formats` whose pattern matches `path`.
+    pub fn find<'a>(formats: &'a [Self], path: &[String]) -> Option<&'a Self> {
+        formats
+            .iter()
+            .find(|format| pattern_matches(&format.pattern, path))
+    }
+
+    // `value` as TOML, or `None` to write it as usual. `nan` and `inf` have
+    // no digits to format.
+    pub fn write(&self, value: f64) -> Option<String> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        // Where Python's `repr` switches to an exponent.
+        let exponent = value != 0.0 && !(1e-4..1e16).contains(&value.abs());
+        let mut text = match (self.precision, self.fixed) {
+            (None, false) => return None,
+            (None, true) => format!("{value}"),
+            (Some(precision), false) if exponent => format!("{value:.precision$e}"),
+            (Some(precision), _) => format!("{value:.precision$}"),
+        };
+        // Without a point or an exponent it would read back as an integer.
+        if !text.contains(['.', 'e']) {
+            text.push_str(".0");
+        }
+        Some(text)
+    }
+}
+
+// How `dumps` writes the datetimes at the paths in `datetime_formats`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DatetimeFormat {
+    // Only the date.
+    Date,
+    // Without the UTC offset, as the wall time it holds.
+    Local,
+    // Converted to UTC and written with `Z`. Naive datetimes are refused,
+    // having no zone to convert from.
+    Utc,
+}
+
+impl DatetimeFormat {
+    pub fn extract(value: Option<&Bound<'_, PyAny>>) -> PyResult<Vec<(Vec<Option<String>>, Self)>> {
+        let Some(value) = value else {
+            return Ok(Vec::new());
+        };
+
+        let mut formats = Vec::new();
+        for (pattern, format) in value.cast::<PyDict>()?.iter() {
+            let format = match format.extract::<&str>()? {
+                "date" => Self::Date,
+                "local" => Self::Local,
+                "utc" => Self::Utc,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unsupported datetime format: {other}"
+                    )));
+                }
+            };
+            formats.push((key_pattern(pattern.extract::<&str>()?)?, format));
+        }
+        Ok(formats)
+    }
+
+    // The format of the first pattern in `formats` that matches `path`.
+    pub fn find(formats: &[(Vec<Option<String>>, Self)], path: &[String]) -> Option<Self> {
+        formats
+            .iter()
+            .find(|(pattern, _)| pattern_matches(pattern, path))
+            .map(|(_, format)| *format)
+    }
+}
+
+// What `dumps` does with the nanoseconds of a `pandas.Timestamp`, which TOML
+// can hold but Python's `datetime`, and so `loads`, cannot.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Nanoseconds {
+    #[default]
+    Keep,
+    // Write only the microseconds, reporting what is dropped like other lossy
+    // conversions.
+    Truncate,
+}
+
+impl Nanoseconds {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "keep" => Ok(Self::Keep),
+            "truncate" => Ok(Self::Truncate),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported nanoseconds policy: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DumpOptions<'a, 'py> {
+    pub inline_tables: Option<&'a FxHashSet<String>>,
+    pub fallback: Fallback,
+    pub strict: bool,
+    pub surrogates: Surrogates,
+    pub sort_arrays: SortArrays,
+    // Keys to write first, in this order, per table path. The top-level
+    // table is the empty path.
+    pub key_order: Option<&'a FxHashMap<String, Vec<String>>>,
+    // Called as `hook(path, value)` for every value before it is written,
+    // with the value to write in its place.
+    pub encode_hook: Option<&'a ValueHook<'py>>,
+    // How deep tables and arrays may nest, the top-level table being 0.
+    pub max_depth: Option<usize>,
+    // Never move a key ahead of the keys before it in the dict.
+    pub preserve_order: bool,
+    pub float_formats: &'a [FloatFormat],
+    pub datetime_formats: &'a [(Vec<Option<String>>, DatetimeFormat)],
+    // Write strings that hold a TOML datetime, date or time as one.
+    pub parse_datetime_strings: bool,
+    pub nanoseconds: Nanoseconds,
+    pub pretty: bool,
+    pub quote_keys: bool,
+    pub escape_form: EscapeForm,
+    // What wrapped arrays are indented by per level; four spaces when unset.
+    pub indent: Option<&'a str>,
+    pub array_style: ArrayStyle,
+    // Comments to write above the keys and tables at these key paths.
+    pub comments: Option<&'a FxHashMap<String, String>>,
+}
+
+impl DumpOptions<'_, '_> {
+    // Whether nothing is asked of `dumps` beyond converting values, so it
+    // can write them straight out without building a document.
+    pub fn is_plain(&self) -> bool {
+        !self.pretty
+            && !self.quote_keys
+            && self.escape_form == EscapeForm::Unicode
+            && self.inline_tables.is_none()
+            && matches!(self.sort_arrays, SortArrays::Off)
+            && self.key_order.is_none()
+            && self.encode_hook.is_none()
+            && self.max_depth.is_none()
+            && !self.preserve_order
+            && self.comments.is_none()
+            && self.float_formats.is_empty()
+            && self.datetime_formats.is_empty()
+            && !self.parse_datetime_strings
+            && self.array_style == ArrayStyle::Auto
+    }
+}
+
+// How `dumps` lays out arrays.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayStyle {
+    // On one line, or with `pretty`, one element per line once there are
+    // two or more.
+    #[default]
+    Auto,
+    // One element per line with a trailing comma, whenever there is one.
+    OnePerLine,
+}
+
+impl ArrayStyle {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "auto" => Ok(Self::Auto),
+            "one_per_line" => Ok(Self::OnePerLine),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported array_style: {other}"
+            ))),
+        }
+    }
+}
+
+// The text `pretty` output indents wrapped arrays by, once per level: a tab
+// or a number of spaces.
+pub fn parse_indent(value: Option<&Bound<'_, PyAny>>) -> PyResult<String> {
+    let Some(value) = value else {
+        return Ok(String::from("    "));
+    };
+    if let Ok(spaces) = value.extract::<usize>() {
+        return Ok(" ".repeat(spaces));
+    }
+    match value.extract::<&str>() {
+        Ok("\t") => Ok(String::from("\t")),
+        _ => Err(PyValueError::new_err(format!(
+            "Unsupported indent_char: {}",
+            value.repr()?
+        ))),
+    }
+}
+
+// What `loads` does with a `:60` second, which TOML allows and Python's
+// `time` and `datetime` do not.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum LeapSeconds {
+    #[default]
+    Error,
+    // Read it as second 59 of the same minute.
+    Clamp,
+}
+
+impl LeapSeconds {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "error" => Ok(Self::Error),
+            "clamp" => Ok(Self::Clamp),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported leap_seconds policy: {other}"
+            ))),
+        }
+    }
+}
+
+// What `loads` returns for datetimes, dates and times.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Datetimes {
+    #[default]
+    Objects,
+    // Their RFC 3339 text, for output that goes straight to JSON.
+    IsoStrings,
+}
+
+impl Datetimes {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "objects" => Ok(Self::Objects),
+            "iso_strings" => Ok(Self::IsoStrings),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported datetimes: {other}"
+            ))),
+        }
+    }
+}
+
+// What `loads` does to keys before setting them, see `normalize::set_key`.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalizeKeys {
+    #[default]
+    None,
+    // Compose them to NFC.
+    Nfc,
+}
+
+impl NormalizeKeys {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "none" => Ok(Self::None),
+            "nfc" => Ok(Self::Nfc),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported normalize_keys: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LoadOptions<'a, 'py> {
+    pub parse_float: &'a Bound<'py, PyAny>,
+    pub preserve_format: bool,
+    pub strict: bool,
+    pub allow_nan: bool,
+    // Key paths to convert; everything else is parsed but left out.
+    pub only: Option<&'a [Vec<String>]>,
+    pub strings: Option<&'a StringCache>,
+    pub timezones: Option<&'a TimezoneCache>,
+    pub normalize_keys: NormalizeKeys,
+    pub leap_seconds: LeapSeconds,
+    pub datetimes: Datetimes,
+    pub value_hook: Option<&'a ValueHook<'py>>,
+    // Longest string value, in characters, that is converted.
+    pub max_string_length: Option<usize>,
+    // Most keys plus array elements, counted over the whole document.
+    pub max_items: Option<usize>,
+    // Deepest nesting of arrays and tables below the top-level table.
+    pub max_depth: Option<usize>,
+    // Called with every parsed integer, like `parse_float` is for floats.
+    pub int_factory: Option<&'a Bound<'py, PyAny>>,
+    // Arrays of tables to turn into dicts, each keyed by the value of a
+    // field of its tables.
+    pub key_by: Option<&'a [(Vec<String>, String)]>,
+    // Wrap scalars in `Located` objects that record where they were written.
+    pub locations: Option<&'a Locations<'a, 'py>>,
+    // Called with every converted table below the top level, see `TableHook`.
+    pub table_hook: Option<&'a TableHook<'py>>,
+}
+
+impl<'a, 'py> LoadOptions<'a, 'py> {
+    // `loads` with every option at its default but `parse_float`.
+    pub const fn plain(parse_float: &'a Bound<'py, PyAny>) -> Self {
+        Self {
+            parse_float,
+            preserve_format: false,
+            strict: false,
+            allow_nan: true,
+            only: None,
+            strings: None,
+            timezones: None,
+            normalize_keys: NormalizeKeys::None,
+            leap_seconds: LeapSeconds::Error,
+            datetimes: Datetimes::Objects,
+            value_hook: None,
+            max_string_length: None,
+            max_items: None,
+            max_depth: None,
+            int_factory: None,
+            key_by: None,
+            locations: None,
+            table_hook: None,
+        }
+    }
+
+    pub fn make_int(&self, int: impl IntoPyObject<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let int = int.into_bound_py_any(self.parse_float.py())?;
+        match self.int_factory {
+            Some(int_factory) => int_factory.call1((int,)),
+            None => Ok(int),
+        }
+    }
+}
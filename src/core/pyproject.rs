@@ -0,0 +1,31 @@
+// Splits a PEP 508 requirement into its project name and the rest of it
+// (extras, version specifiers and markers).
+pub fn split_requirement(requirement: &str) -> Option<(&str, &str)> {
+    let requirement = requirement.trim();
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+        .unwrap_or(requirement.len());
+    let name = requirement[..end].trim_end_matches(['.', '_', '-']);
+
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    Some((name, requirement[name.len()..].trim()))
+}
+
+// PEP 503: names compare case-insensitively, with runs of `-`, `_` and `.`
+// treated as a single `-`.
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '.' | '_' | '-') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
@@ -0,0 +1,69 @@
+use std::{borrow::Cow, fmt::Write};
+
+use pyo3::{
+    intern,
+    prelude::*,
+    types::{PyBytes, PyString},
+};
+
+use crate::{core::options::Surrogates, toml_rs::TOMLEncodeError};
+
+// The text of a string being dumped. `path` is where the string sits: the
+// table holding it for a key, the value's own path otherwise.
+pub fn to_str<'a>(
+    py_str: &'a Bound<'_, PyString>,
+    surrogates: Surrogates,
+    path: &[String],
+    is_key: bool,
+) -> PyResult<Cow<'a, str>> {
+    if let Ok(text) = py_str.to_str() {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    // `surrogatepass` lets the lone surrogates through as code points, which
+    // is all that is needed to find and rewrite them.
+    let py = py_str.py();
+    let encoded = py_str.call_method1(
+        intern!(py, "encode"),
+        (intern!(py, "utf-32-le"), intern!(py, "surrogatepass")),
+    )?;
+
+    let mut text = String::new();
+    let mut escaped = String::new();
+    let mut surrogate = None;
+    for code in encoded.cast::<PyBytes>()?.as_bytes().chunks_exact(4) {
+        let code = u32::from_le_bytes([code[0], code[1], code[2], code[3]]);
+        if let Some(char) = char::from_u32(code) {
+            text.push(char);
+            escaped.push(char);
+            continue;
+        }
+
+        surrogate.get_or_insert(code);
+        let _ = write!(escaped, "\\u{code:04x}");
+        match surrogates {
+            Surrogates::Error => {}
+            Surrogates::Replace => text.push(char::REPLACEMENT_CHARACTER),
+            Surrogates::BackslashReplace => {
+                let _ = write!(text, "\\u{code:04x}");
+            }
+        }
+    }
+
+    match (surrogates, surrogate) {
+        (Surrogates::Error, Some(code)) => {
+            let path = path.join(".");
+            let what = match (is_key, path.is_empty()) {
+                (true, true) => format!("key '{escaped}'"),
+                (true, false) => format!("key '{escaped}' in '{path}'"),
+                (false, true) => format!("string '{escaped}'"),
+                (false, false) => format!("string at '{path}'"),
+            };
+            Err(TOMLEncodeError::new_err(format!(
+                "{what} contains the lone surrogate U+{code:04X}, which cannot be encoded as UTF-8; \
+                 pass surrogates=\"replace\" or surrogates=\"backslashreplace\" to write it anyway"
+            )))
+        }
+        _ => Ok(Cow::Owned(text)),
+    }
+}
@@ -0,0 +1,100 @@
+use pyo3::{
+    exceptions::PyTypeError,
+    intern,
+    prelude::*,
+    types::{
+        PyBool, PyDate, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyString, PyTime, PyTzInfo,
+        PyTzInfoAccess,
+    },
+};
+
+// Canonical form used by `digest`:
+//
+// * tables are written as `{"key":value,...}` with keys sorted by code point
+// * arrays are written as `[value,...]`
+// * scalars carry a type tag, so `1`, `1.0` and `"1"` never collide
+// * offset datetimes are normalized to UTC, so the same instant written
+//   with different offsets produces the same output
+pub fn canonicalize(py: Python<'_>, obj: &Bound<'_, PyAny>) -> PyResult<String> {
+    let mut buf = String::new();
+    write_canonical(py, obj, &mut buf)?;
+    Ok(buf)
+}
+
+fn write_canonical(py: Python<'_>, obj: &Bound<'_, PyAny>, buf: &mut String) -> PyResult<()> {
+    if let Ok(s) = obj.cast::<PyString>() {
+        write_escaped(buf, s.to_str()?);
+    } else if let Ok(b) = obj.cast::<PyBool>() {
+        buf.push_str(if b.is_true() { "b:true" } else { "b:false" });
+    } else if let Ok(int) = obj.cast::<PyInt>() {
+        buf.push_str("i:");
+        buf.push_str(int.str()?.to_str()?);
+    } else if let Ok(float) = obj.cast::<PyFloat>() {
+        buf.push_str("f:");
+        buf.push_str(float.repr()?.to_str()?);
+    } else if let Ok(datetime) = obj.cast::<PyDateTime>() {
+        if datetime.get_tzinfo().is_some() {
+            let utc = datetime
+                .call_method1(intern!(py, "astimezone"), (PyTzInfo::utc(py)?.to_owned(),))?;
+            buf.push_str("odt:");
+            buf.push_str(utc.str()?.to_str()?);
+        } else {
+            buf.push_str("ldt:");
+            buf.push_str(datetime.str()?.to_str()?);
+        }
+    } else if let Ok(date) = obj.cast::<PyDate>() {
+        buf.push_str("ld:");
+        buf.push_str(date.str()?.to_str()?);
+    } else if let Ok(time) = obj.cast::<PyTime>() {
+        buf.push_str("lt:");
+        buf.push_str(time.str()?.to_str()?);
+    } else if let Ok(list) = obj.cast::<PyList>() {
+        buf.push('[');
+        for (i, item) in list.iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            write_canonical(py, &item, buf)?;
+        }
+        buf.push(']');
+    } else if let Ok(dict) = obj.cast::<PyDict>() {
+        let mut items = dict
+            .iter()
+            .map(|(key, value)| -> PyResult<_> { Ok((key.extract::<String>()?, value)) })
+            .collect::<PyResult<Vec<_>>>()?;
+        items.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        buf.push('{');
+        for (i, (key, value)) in items.iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            write_escaped(buf, key);
+            buf.push(':');
+            write_canonical(py, value, buf)?;
+        }
+        buf.push('}');
+    } else {
+        return Err(PyTypeError::new_err(format!(
+            "Cannot canonicalize {py_type}",
+            py_type = crate::get_type!(obj)
+        )));
+    }
+
+    Ok(())
+}
+
+fn write_escaped(buf: &mut String, value: &str) {
+    buf.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            ch if ch.is_control() => {
+                buf.push_str(&format!("\\u{:04x}", u32::from(ch)));
+            }
+            ch => buf.push(ch),
+        }
+    }
+    buf.push('"');
+}
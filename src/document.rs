@@ -1,20 +1,30 @@
+use std::borrow::Cow;
+
 use pyo3::{
     Bound, Py, PyAny, PyResult, Python,
-    exceptions::{PyKeyError, PyTypeError},
+    exceptions::{PyKeyError, PyTypeError, PyValueError},
+    intern,
     prelude::PyAnyMethods,
     pyclass, pymethods,
-    types::PyDict,
+    types::{PyDict, PyFloat, PyIterator, PyTuple},
 };
 
-#[pyclass]
+use crate::core::options::LoadOptions;
+
+#[pyclass(mapping)]
 pub struct TOMLDocument {
     #[pyo3(get)]
     pub value: Py<PyAny>,
     #[pyo3(get)]
     pub meta: Py<PyAny>,
+    #[pyo3(get)]
+    pub toml_version: &'static str,
+    // The text `value` and `meta` were parsed from, which `visit` walks
+    // while `value` still matches it.
+    pub text: String,
 }
 
-fn parse_key_path(path: &str) -> Option<Vec<String>> {
+pub fn parse_key_path(path: &str) -> Option<Vec<String>> {
     let source = toml_parser::Source::new(path);
     let mut errors = Vec::new();
     let keys = toml_edit::parse_key_path(source, &mut errors);
@@ -28,6 +38,153 @@ fn parse_key_path(path: &str) -> Option<Vec<String>> {
 
 #[pymethods]
 impl TOMLDocument {
+    // Pickled as TOML text and re-parsed on load. An unchanged document keeps
+    // the text it was parsed from, with its comments and layout; a changed
+    // one is written out again, and its metadata is for that text.
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        let text = self.current_text(py)?;
+        let load_with_metadata = py
+            .import(intern!(py, "toml_rs"))?
+            .getattr(intern!(py, "load_with_metadata"))?;
+
+        PyTuple::new(
+            py,
+            [
+                load_with_metadata,
+                PyTuple::new(py, [&*text, self.toml_version])?.into_any(),
+            ],
+        )
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (data, /, toml_version = "1.0.0"))]
+    fn from_dict(
+        py: Python<'_>,
+        data: &Bound<'_, PyAny>,
+        toml_version: &str,
+    ) -> PyResult<Py<PyAny>> {
+        let text = crate::toml_rs::dumps_default(py, data, toml_version)?;
+        crate::toml_rs::parse_metadata_from_string(py, &text, toml_version)
+    }
+
+    fn unwrap<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import(intern!(py, "copy"))?
+            .getattr(intern!(py, "deepcopy"))?
+            .call1((self.value.bind(py),))
+    }
+
+    fn __copy__(&self, py: Python<'_>) -> PyResult<Self> {
+        Ok(Self {
+            value: self
+                .value
+                .bind(py)
+                .call_method0(intern!(py, "copy"))?
+                .unbind(),
+            meta: self
+                .meta
+                .bind(py)
+                .call_method0(intern!(py, "copy"))?
+                .unbind(),
+            toml_version: self.toml_version,
+            text: self.text.clone(),
+        })
+    }
+
+    fn __deepcopy__(&self, py: Python<'_>, memo: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let deepcopy = py
+            .import(intern!(py, "copy"))?
+            .getattr(intern!(py, "deepcopy"))?;
+
+        Ok(Self {
+            value: deepcopy.call1((self.value.bind(py), memo))?.unbind(),
+            meta: deepcopy.call1((self.meta.bind(py), memo))?.unbind(),
+            toml_version: self.toml_version,
+            text: self.text.clone(),
+        })
+    }
+
+    // Walks the items of the text the document was parsed from, with spans
+    // into it. Once the document has been changed, it is written out again
+    // and that text is walked instead, without spans.
+    fn visit(&self, py: Python<'_>, callback: &Bound<'_, PyAny>) -> PyResult<()> {
+        let text = self.current_text(py)?;
+        let spans = matches!(text, Cow::Borrowed(_));
+
+        match self.toml_version {
+            "1.0.0" => crate::v1::raw::visit(&text, callback, spans),
+            "1.1.0" => crate::v1_1::raw::visit(&text, callback, spans),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {other}",
+            ))),
+        }
+    }
+
+    fn __len__(&self, py: Python<'_>) -> PyResult<usize> {
+        self.value.bind(py).len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        self.value.bind(py).try_iter()
+    }
+
+    // Only top-level keys, like `__iter__`; dotted paths go through `get_path`.
+    fn __contains__<'py>(&self, py: Python<'py>, key: Bound<'py, PyAny>) -> PyResult<bool> {
+        self.value.bind(py).contains(key)
+    }
+
+    fn __eq__<'py>(&self, py: Python<'py>, other: &Bound<'py, PyAny>) -> PyResult<bool> {
+        if let Ok(doc) = other.cast::<Self>() {
+            return self.value.bind(py).eq(doc.borrow().value.bind(py));
+        }
+
+        self.value.bind(py).eq(other)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("TOMLDocument({})", self.value.bind(py).repr()?))
+    }
+
+    fn keys<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.value.bind(py).call_method0(intern!(py, "keys"))
+    }
+
+    fn values<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.value.bind(py).call_method0(intern!(py, "values"))
+    }
+
+    fn items<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.value.bind(py).call_method0(intern!(py, "items"))
+    }
+
+    #[pyo3(signature = (key, default=None))]
+    fn get<'py>(
+        &self,
+        py: Python<'py>,
+        key: Bound<'py, PyAny>,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let val = self.value.bind(py);
+        if val.contains(&key)? {
+            return val.get_item(key).map(Some);
+        }
+        Ok(default)
+    }
+
+    // Looks up a dotted key path like `server.port`, as `__getitem__` does.
+    #[pyo3(signature = (path, default=None))]
+    fn get_path<'py>(
+        &self,
+        py: Python<'py>,
+        path: Bound<'py, PyAny>,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        match self.__getitem__(py, path) {
+            Ok(item) => Ok(Some(item)),
+            Err(err) if err.is_instance_of::<PyKeyError>(py) => Ok(default),
+            Err(err) => Err(err),
+        }
+    }
+
     fn __getitem__<'py>(
         &self,
         py: Python<'py>,
@@ -154,3 +311,24 @@ impl TOMLDocument {
         Ok(())
     }
 }
+
+impl TOMLDocument {
+    // `text` while `value` still matches it, or `value` written out again
+    // once it has been changed.
+    fn current_text(&self, py: Python<'_>) -> PyResult<Cow<'_, str>> {
+        let value = self.value.bind(py);
+        let float = py.get_type::<PyFloat>();
+        let parsed = crate::toml_rs::loads_with_options(
+            py,
+            &self.text,
+            self.toml_version,
+            &LoadOptions::plain(float.as_any()),
+        )?;
+
+        if value.eq(parsed.bind(py))? {
+            Ok(Cow::Borrowed(&self.text))
+        } else {
+            crate::toml_rs::dumps_default(py, value, self.toml_version).map(Cow::Owned)
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    borrow::Cow,
+    ffi::{CStr, CString},
+    ops::Range,
+};
+
+use encoding_rs::{DecoderResult, Encoding};
+use pyo3::{
+    exceptions::{PyLookupError, PyMemoryError, PyUnicodeDecodeError},
+    prelude::*,
+};
+
+use crate::core::buffer::decode_utf8;
+
+// Byte order marks of encodings a document read as UTF-8 is clearly not in,
+// longest first so UTF-32LE is not taken for UTF-16LE.
+const FOREIGN_BOMS: [(&[u8], &str); 4] = [
+    (b"\x00\x00\xFE\xFF", "UTF-32BE"),
+    (b"\xFF\xFE\x00\x00", "UTF-32LE"),
+    (b"\xFE\xFF", "UTF-16BE"),
+    (b"\xFF\xFE", "UTF-16LE"),
+];
+
+// Decodes a document read as bytes. Without `label` it must be UTF-8, and a
+// leading byte order mark is dropped. Otherwise `label` is any WHATWG label
+// (`latin-1`, `shift_jis`, `utf-16le`, ...). Undecodable bytes raise
+// `UnicodeDecodeError` with their byte offsets in `data`.
+pub fn decode(py: Python<'_>, data: Vec<u8>, label: Option<&str>) -> PyResult<String> {
+    let Some(label) = label else {
+        check_bom(py, &data)?;
+        let mut text = decode_utf8(py, data)?;
+        if text.starts_with('\u{feff}') {
+            text.drain(..'\u{feff}'.len_utf8());
+        }
+        return Ok(text);
+    };
+    decode_label(py, &data, label)
+}
+
+// Like `decode`, for bytes owned by Python. UTF-8 documents are validated
+// where they are rather than copied.
+pub fn decode_borrowed<'a>(
+    py: Python<'_>,
+    data: &'a [u8],
+    label: Option<&str>,
+) -> PyResult<Cow<'a, str>> {
+    let Some(label) = label else {
+        check_bom(py, data)?;
+        let text =
+            std::str::from_utf8(data).map_err(|err| {
+                match PyUnicodeDecodeError::new_utf8(py, data, err) {
+                    Ok(exc) => PyErr::from_value(exc.into_any()),
+                    Err(err) => err,
+                }
+            })?;
+        return Ok(Cow::Borrowed(text.strip_prefix('\u{feff}').unwrap_or(text)));
+    };
+    decode_label(py, data, label).map(Cow::Owned)
+}
+
+fn check_bom(py: Python<'_>, data: &[u8]) -> PyResult<()> {
+    if let Some((bom, name)) = FOREIGN_BOMS.iter().find(|(bom, _)| data.starts_with(bom)) {
+        let reason = CString::new(format!(
+            "found a {name} byte order mark, TOML documents are UTF-8"
+        ))
+        .unwrap_or_default();
+        return Err(decode_error(py, c"utf-8", data, 0..bom.len(), &reason));
+    }
+    Ok(())
+}
+
+fn decode_label(py: Python<'_>, data: &[u8], label: &str) -> PyResult<String> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| PyLookupError::new_err(format!("unknown encoding: {label}")))?;
+
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+    let mut text = String::new();
+    decoder
+        .max_utf8_buffer_length_without_replacement(data.len())
+        .and_then(|len| text.try_reserve_exact(len).ok())
+        .ok_or_else(|| PyMemoryError::new_err("out of memory while decoding TOML document"))?;
+
+    let (result, read) =
+        py.detach(|| decoder.decode_to_string_without_replacement(data, &mut text, true));
+    match result {
+        DecoderResult::InputEmpty => Ok(text),
+        DecoderResult::Malformed(bad, after) => {
+            let end = read - usize::from(after);
+            let start = end - usize::from(bad);
+            let name = CString::new(encoding.name()).unwrap_or_default();
+            Err(decode_error(
+                py,
+                &name,
+                data,
+                start..end,
+                c"undecodable bytes",
+            ))
+        }
+        // The buffer was sized for the worst case up front.
+        DecoderResult::OutputFull => unreachable!(),
+    }
+}
+
+fn decode_error(
+    py: Python<'_>,
+    encoding: &CStr,
+    data: &[u8],
+    range: Range<usize>,
+    reason: &CStr,
+) -> PyErr {
+    match PyUnicodeDecodeError::new(py, encoding, data, range, reason) {
+        Ok(exc) => PyErr::from_value(exc.into_any()),
+        Err(err) => err,
+    }
+}
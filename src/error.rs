@@ -1,3 +1,318 @@
+use std::{
+    ops::Range,
+    sync::{Mutex, PoisonError},
+};
+
+use pyo3::{prelude::*, sync::PyOnceLock};
+
+use crate::{
+    core::buffer::try_to_owned,
+    toml_rs::{
+        TOMLDuplicateKeyError, TOMLInvalidValueError, TOMLResourceLimitError, TOMLSyntaxError,
+    },
+};
+
+static ERROR_FORMATTER: Mutex<Option<Py<PyAny>>> = Mutex::new(None);
+
+pub fn set_error_formatter(formatter: Option<Py<PyAny>>) {
+    *ERROR_FORMATTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = formatter;
+}
+
+// Errors from the different parsers, which all carry a bare message and the
+// span it refers to, and the TOML version of the grammar they parse.
+pub trait SourceError: std::fmt::Display {
+    const TOML_VERSION: &'static str;
+
+    fn message(&self) -> &str;
+    fn span(&self) -> Option<Range<usize>>;
+
+    // The parsers have no error codes: every error is a `ParseError` whose
+    // description is a fixed text, which the message starts with and only
+    // adds what was expected to.
+    fn kind(&self) -> ErrorKind {
+        let message = self.message();
+        let description = message
+            .split_once(", expected ")
+            .map_or(message, |(description, _)| description);
+        match description {
+            "duplicate key" => ErrorKind::DuplicateKey,
+            _ => ErrorKind::Syntax,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax,
+    // A key or table defined twice.
+    DuplicateKey,
+}
+
+macro_rules! impl_source_error {
+    ($($error:ty => $toml_version:literal),* $(,)?) => {
+        $(
+            impl SourceError for $error {
+                const TOML_VERSION: &'static str = $toml_version;
+
+                fn message(&self) -> &str {
+                    <$error>::message(self)
+                }
+
+                fn span(&self) -> Option<Range<usize>> {
+                    <$error>::span(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_source_error!(
+    toml::de::Error => "1.1.0",
+    toml_v1::de::Error => "1.0.0",
+    toml_edit::TomlError => "1.1.0",
+    toml_edit_v1::TomlError => "1.0.0",
+);
+
+pub fn parse_error<E: SourceError>(err: &E, doc: &str) -> PyErr {
+    let span = err.span().unwrap_or_default();
+    let pos = span.start;
+    let duplicate = err.kind() == ErrorKind::DuplicateKey;
+
+    let expected = || {
+        if duplicate {
+            Vec::new()
+        } else {
+            expected_at(doc, E::TOML_VERSION)
+        }
+    };
+    let message = format_message(err.message(), doc, span, expected, || {
+        if !duplicate {
+            return err.to_string();
+        }
+        // The parser only reports the conflicting definition, so the original
+        // one is looked up in the part of the document before it.
+        match duplicate_key_origin(doc, pos, E::TOML_VERSION) {
+            Some(span) => format!(
+                "{err}{note}",
+                note = Note {
+                    label: "first defined",
+                    input: doc,
+                    span,
+                }
+            ),
+            None => err.to_string(),
+        }
+    });
+    let (message, doc) = match (message, try_to_owned(doc)) {
+        (Ok(message), Ok(doc)) => (message, doc),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    if duplicate {
+        TOMLDuplicateKeyError::new_err((message, doc, pos))
+    } else {
+        TOMLSyntaxError::new_err((message, doc, pos))
+    }
+}
+
+// A value the parser accepted but Python cannot represent, or the options
+// rule out.
+pub fn invalid_value(message: String, doc: &str, span: Range<usize>) -> PyErr {
+    custom_error(message, doc, span, TOMLInvalidValueError::new_err)
+}
+
+// Keys the parser accepted as distinct that the options turn into one.
+pub fn duplicate_key(message: String, doc: &str, span: Range<usize>) -> PyErr {
+    custom_error(message, doc, span, TOMLDuplicateKeyError::new_err)
+}
+
+// A value larger than the options allow. The message is completed with the
+// key path the value sits under.
+pub fn resource_limit(message: &str, doc: &str, span: Range<usize>) -> PyErr {
+    let path = key_path_at(doc, span.start)
+        .unwrap_or_default()
+        .iter()
+        .map(toml_edit::Key::get)
+        .collect::<Vec<_>>()
+        .join(".");
+    let message = if path.is_empty() {
+        message.to_owned()
+    } else {
+        format!("{message} at `{path}`")
+    };
+    custom_error(message, doc, span, TOMLResourceLimitError::new_err)
+}
+
+fn custom_error(
+    message: String,
+    doc: &str,
+    span: Range<usize>,
+    new_err: fn((String, String, usize)) -> PyErr,
+) -> PyErr {
+    let pos = span.start;
+    let mut err = TomlError::custom(message, Some(span.clone()));
+    err.set_input(Some(doc));
+
+    // The document parsed, so the grammar expected nothing else.
+    let message = format_message(&err.message, doc, span, Vec::new, || err.to_string());
+    let (message, doc) = match (message, try_to_owned(doc)) {
+        (Ok(message), Ok(doc)) => (message, doc),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    new_err((message, doc, pos))
+}
+
+fn get_error_details_type(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    static ERROR_DETAILS: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+    ERROR_DETAILS
+        .get_or_try_init(py, || {
+            py.import("toml_rs._lib")?
+                .getattr("ErrorDetails")
+                .map(Bound::unbind)
+        })
+        .map(|details| details.bind(py))
+}
+
+// Hands the parts of an error to the formatter registered with
+// `set_error_formatter`, falling back to the built-in message without one.
+fn format_message(
+    message: &str,
+    doc: &str,
+    span: Range<usize>,
+    expected: impl FnOnce() -> Vec<String>,
+    default: impl FnOnce() -> String,
+) -> PyResult<String> {
+    Python::attach(|py| {
+        let formatter = ERROR_FORMATTER
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|formatter| formatter.clone_ref(py));
+        let Some(formatter) = formatter else {
+            return Ok(default());
+        };
+
+        let path = key_path_at(doc, span.start)
+            .unwrap_or_default()
+            .iter()
+            .map(|key| key.get().to_owned())
+            .collect::<Vec<_>>();
+        let span = (char_index(doc, span.start), char_index(doc, span.end));
+        let details = get_error_details_type(py)?.call1((message, span, expected(), path))?;
+
+        formatter.bind(py).call1((details,))?.extract::<String>()
+    })
+}
+
+fn char_index(doc: &str, byte: usize) -> usize {
+    doc.get(..byte)
+        .map_or_else(|| doc.chars().count(), |prefix| prefix.chars().count())
+}
+
+// What the grammar of `toml_version` allowed at the first syntax error,
+// e.g. "`=`" or "value".
+fn expected_at(doc: &str, toml_version: &str) -> Vec<String> {
+    macro_rules! expected_at {
+        ($toml_parser:ident) => {{
+            use $toml_parser::{Expected, ParseError, Source, parser::parse_document};
+
+            let tokens = Source::new(doc).lex().collect::<Vec<_>>();
+            let mut errors = Vec::<ParseError>::new();
+            parse_document(&tokens, &mut (), &mut errors);
+
+            errors
+                .first()
+                .and_then(ParseError::expected)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|expected| match expected {
+                    Expected::Literal(literal) => Some(format!("`{literal}`")),
+                    Expected::Description(description) => Some((*description).to_owned()),
+                    _ => None,
+                })
+                .collect()
+        }};
+    }
+
+    match toml_version {
+        "1.0.0" => expected_at!(toml_parser_v1),
+        _ => expected_at!(toml_parser),
+    }
+}
+
+fn header_path(line: &str) -> Option<Vec<toml_edit::Key>> {
+    let header = line.trim_start().strip_prefix('[')?;
+    let header = header.strip_prefix('[').unwrap_or(header);
+    toml_edit::Key::parse(&header[..header.find(']')?]).ok()
+}
+
+fn line_start(doc: &str, pos: usize) -> usize {
+    doc[..pos].rfind('\n').map_or(0, |nl| nl + 1)
+}
+
+// The key path of the header or key-value on the line containing `pos`.
+fn key_path_at(doc: &str, pos: usize) -> Option<Vec<toml_edit::Key>> {
+    let line_start = line_start(doc, pos);
+    let line_end = doc[pos..].find('\n').map_or(doc.len(), |nl| pos + nl);
+    let line = &doc[line_start..line_end];
+
+    if line.trim_start().starts_with('[') {
+        header_path(line)
+    } else {
+        let mut path = doc[..line_start]
+            .lines()
+            .rev()
+            .find(|line| line.trim_start().starts_with('['))
+            .map_or(Some(Vec::new()), header_path)?;
+        path.extend(toml_edit::Key::parse(line.split_once('=')?.0).ok()?);
+        Some(path)
+    }
+}
+
+fn duplicate_key_origin(doc: &str, pos: usize, toml_version: &str) -> Option<Range<usize>> {
+    macro_rules! key_origin {
+        ($toml:ident, $prefix:expr, $path:expr) => {{
+            use $toml::de::{DeTable, DeValue};
+
+            let parsed = DeTable::parse($prefix).ok()?;
+            let mut table = parsed.get_ref();
+            let mut origin = None;
+
+            for (i, segment) in $path.iter().enumerate() {
+                let (key, value) = table
+                    .iter()
+                    .find(|(key, _)| key.get_ref() == segment.get())?;
+                origin = Some(key.span());
+
+                if i + 1 < $path.len() {
+                    table = match value.get_ref() {
+                        DeValue::Table(table) => table,
+                        DeValue::Array(array) => match array.last()?.get_ref() {
+                            DeValue::Table(table) => table,
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                }
+            }
+
+            origin
+        }};
+    }
+
+    let path = key_path_at(doc, pos)?;
+    let prefix = &doc[..line_start(doc, pos)];
+
+    match toml_version {
+        "1.0.0" => key_origin!(toml_v1, prefix, path),
+        _ => key_origin!(toml, prefix, path),
+    }
+}
+
 // https://github.com/toml-rs/toml/blob/v0.25.12/crates/toml_edit/src/error.rs
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct TomlError {
@@ -46,40 +361,13 @@ impl std::fmt::Display for TomlError {
             context = true;
 
             let (line, column) = translate_position(input.as_bytes(), span.start);
-            let line_num = line + 1;
-            let col_num = column + 1;
-            let gutter = line_num.to_string().len();
-            let content = input.split('\n').nth(line).expect("valid line number");
-            let highlight_len = span.end - span.start;
-            // Allow highlight to go one past the line
-            let highlight_len = highlight_len.min(content.len().saturating_sub(column));
-
-            writeln!(f, "TOML parse error at line {line_num}, column {col_num}")?;
-            //   |
-            for _ in 0..=gutter {
-                write!(f, " ")?;
-            }
-            writeln!(f, "|")?;
-
-            // 1 | 00:32:00.a999999
-            write!(f, "{line_num} | ")?;
-            writeln!(f, "{content}")?;
-
-            //   |          ^
-            for _ in 0..=gutter {
-                write!(f, " ")?;
-            }
-            write!(f, "|")?;
-            for _ in 0..=column {
-                write!(f, " ")?;
-            }
-            // The span will be empty at eof, so we need to make sure we always print at least
-            // one `^`
-            write!(f, "^")?;
-            for _ in 1..highlight_len {
-                write!(f, "^")?;
-            }
-            writeln!(f)?;
+            writeln!(
+                f,
+                "TOML parse error at line {}, column {}",
+                line + 1,
+                column + 1
+            )?;
+            write_snippet(f, input, &span)?;
         }
         writeln!(f, "{}", self.message)?;
         if !context && !self.keys.is_empty() {
@@ -90,6 +378,80 @@ impl std::fmt::Display for TomlError {
     }
 }
 
+// A secondary location attached to an error, e.g. where a duplicated key was
+// first defined:
+//
+// note: first defined at line 1, column 1
+//   |
+// 1 | a = 1
+//   | ^
+pub struct Note<'a> {
+    pub label: &'a str,
+    pub input: &'a str,
+    pub span: std::ops::Range<usize>,
+}
+
+impl std::fmt::Display for Note<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (line, column) = translate_position(self.input.as_bytes(), self.span.start);
+        writeln!(
+            f,
+            "note: {} at line {}, column {}",
+            self.label,
+            line + 1,
+            column + 1
+        )?;
+        write_snippet(f, self.input, &self.span)
+    }
+}
+
+fn write_snippet(
+    f: &mut std::fmt::Formatter<'_>,
+    input: &str,
+    span: &std::ops::Range<usize>,
+) -> std::fmt::Result {
+    let (line, column) = translate_position(input.as_bytes(), span.start);
+    let line_num = line + 1;
+    let gutter = line_num.to_string().len();
+    let content = input.split('\n').nth(line).expect("valid line number");
+    let highlight_len = span.end - span.start;
+    // Allow highlight to go one past the line
+    let highlight_len = highlight_len.min(content.len().saturating_sub(column));
+
+    //   |
+    for _ in 0..=gutter {
+        write!(f, " ")?;
+    }
+    writeln!(f, "|")?;
+
+    // 1 | 00:32:00.a999999
+    write!(f, "{line_num} | ")?;
+    writeln!(f, "{content}")?;
+
+    //   |          ^
+    for _ in 0..=gutter {
+        write!(f, " ")?;
+    }
+    write!(f, "|")?;
+    for _ in 0..=column {
+        write!(f, " ")?;
+    }
+    // The span will be empty at eof, so we need to make sure we always print at least
+    // one `^`
+    write!(f, "^")?;
+    for _ in 1..highlight_len {
+        write!(f, "^")?;
+    }
+    writeln!(f)
+}
+
+// The line and column of byte `offset` of `input`, both counting from 1 and
+// columns in characters, as parse error messages give them.
+pub fn position(input: &str, offset: usize) -> (usize, usize) {
+    let (line, column) = translate_position(input.as_bytes(), offset);
+    (line + 1, column + 1)
+}
+
 fn translate_position(input: &[u8], index: usize) -> (usize, usize) {
     if input.is_empty() {
         return (0, index);
@@ -0,0 +1,33 @@
+use pyo3::prelude::*;
+
+// Reads everything from a file descriptor the caller keeps owning, so the
+// descriptor is left open when we are done.
+#[cfg(unix)]
+pub fn read_fd(py: Python<'_>, fd: i32) -> PyResult<String> {
+    use std::{fs::File, io::Read, os::fd::BorrowedFd};
+
+    if fd < 0 {
+        return Err(pyo3::exceptions::PyOSError::new_err(format!(
+            "Bad file descriptor: {fd}"
+        )));
+    }
+
+    let bytes = py.detach(|| {
+        // SAFETY: `fd` is not negative and is only borrowed to duplicate it; a
+        // descriptor that is not open makes the duplication fail with EBADF.
+        let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+        // Reading through the duplicate, which is closed when dropped, leaves
+        // the caller's descriptor open.
+        let mut bytes = Vec::new();
+        File::from(owned).read_to_end(&mut bytes).map(|_| bytes)
+    })?;
+
+    crate::encoding::decode(py, bytes, None)
+}
+
+#[cfg(not(unix))]
+pub fn read_fd(_py: Python<'_>, _fd: i32) -> PyResult<String> {
+    Err(pyo3::exceptions::PyNotImplementedError::new_err(
+        "Reading from file descriptors is only supported on Unix",
+    ))
+}
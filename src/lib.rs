@@ -1,38 +1,237 @@
+mod binary;
+mod compress;
+mod config;
 mod core;
+mod digest;
 mod document;
+mod encoding;
 mod error;
+mod fd;
+mod merge;
+mod schema;
+mod split;
+mod template;
 mod v1;
 mod v1_1;
+mod watch;
+mod writer;
 
-#[cfg(feature = "mimalloc")]
+#[cfg(any(
+    all(feature = "alloc-mimalloc", feature = "alloc-snmalloc"),
+    all(feature = "alloc-mimalloc", feature = "alloc-system"),
+    all(feature = "alloc-snmalloc", feature = "alloc-system"),
+))]
+compile_error!("only one of the `alloc-*` features can be enabled");
+
+#[cfg(feature = "alloc-mimalloc")]
 #[global_allocator]
 static GLOBAL_ALLOCATOR: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-#[cfg(feature = "snmalloc")]
+#[cfg(feature = "alloc-snmalloc")]
 #[global_allocator]
 static GLOBAL_ALLOCATOR: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 
+const ALLOCATOR: &str = if cfg!(feature = "alloc-mimalloc") {
+    "mimalloc"
+} else if cfg!(feature = "alloc-snmalloc") {
+    "snmalloc"
+} else {
+    "system"
+};
+
 #[pyo3::pymodule(name = "_toml_rs")]
 mod toml_rs {
-    use pyo3::{exceptions::PyValueError, import_exception, prelude::*};
-    use rustc_hash::FxHashSet;
+    use std::{borrow::Cow, path::PathBuf, time::Duration};
+
+    use pyo3::{
+        exceptions::{PyMemoryError, PyTypeError, PyValueError},
+        import_exception,
+        prelude::*,
+        types::{IntoPyDict, PyBytes, PyDict, PyString},
+    };
+    use rustc_hash::{FxHashMap, FxHashSet};
+
+    use crate::{
+        binary::Format,
+        compress::Compression,
+        core::{
+            buffer::render,
+            hook::{TableHook, ValueHook},
+            intern::{StringCache, TimezoneCache},
+            located::Locations,
+            options::{
+                ArrayStyle, DatetimeFormat, Datetimes, DumpOptions, EscapeForm, Fallback,
+                FloatFormat, LeapSeconds, LoadOptions, Nanoseconds, NormalizeKeys, SortArrays,
+                Surrogates, parse_indent,
+            },
+        },
+        merge::ArrayMerge,
+        watch::Watcher,
+    };
 
+    #[pymodule_export]
+    use crate::config::ConfigStack;
+    #[pymodule_export]
+    use crate::core::located::Located;
     #[pymodule_export]
     use crate::document::TOMLDocument;
+    use crate::document::parse_key_path;
+    #[pymodule_export]
+    use crate::schema::Schema;
+    #[pymodule_export]
+    use crate::split::ArrayTableSplitter;
+    #[pymodule_export]
+    use crate::template::DumpTemplate;
+    #[pymodule_export]
+    use crate::writer::TomlWriter;
 
     #[pymodule_export]
     const _VERSION: &str = env!("CARGO_PKG_VERSION");
 
+    #[pymodule_export]
+    const _ALLOCATOR: &str = crate::ALLOCATOR;
+
     import_exception!(toml_rs, TOMLDecodeError);
+    import_exception!(toml_rs, TOMLSyntaxError);
+    import_exception!(toml_rs, TOMLInvalidValueError);
+    import_exception!(toml_rs, TOMLDuplicateKeyError);
+    import_exception!(toml_rs, TOMLResourceLimitError);
     import_exception!(toml_rs, TOMLEncodeError);
 
+    #[expect(clippy::too_many_arguments)]
     #[pyfunction(name = "_loads")]
     fn load_toml_from_string(
         py: Python,
-        toml_string: &str,
+        toml: &Bound<'_, PyAny>,
+        parse_float: &Bound<'_, PyAny>,
+        toml_version: &str,
+        preserve_format: bool,
+        strict: bool,
+        allow_nan: bool,
+        only: Option<Vec<String>>,
+        intern_strings: bool,
+        normalize_keys: &str,
+        leap_seconds: &str,
+        value_hook: Option<Bound<'_, PyAny>>,
+        max_string_length: Option<usize>,
+        int_factory: Option<Bound<'_, PyAny>>,
+        key_by: Option<Vec<(String, String)>>,
+        locations: bool,
+        filename: Option<Bound<'_, PyAny>>,
+        table_hook: Option<Bound<'_, PyAny>>,
+        max_items: Option<usize>,
+        encoding: Option<&str>,
+        max_depth: Option<usize>,
+        datetimes: &str,
+    ) -> PyResult<Py<PyAny>> {
+        // Bytes read from a file are parsed where they are, so the document
+        // is never held twice.
+        let toml_string = match toml.cast::<PyBytes>() {
+            Ok(bytes) => crate::encoding::decode_borrowed(py, bytes.as_bytes(), encoding)?,
+            Err(_) => Cow::Borrowed(toml.cast::<PyString>()?.to_str()?),
+        };
+        let toml_string = toml_string.as_ref();
+        let key_path = |path: &str| {
+            parse_key_path(path)
+                .ok_or_else(|| PyValueError::new_err(format!("Invalid key path: {path:?}")))
+        };
+        let only = only
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|path| key_path(path))
+                    .collect::<PyResult<Vec<_>>>()
+            })
+            .transpose()?;
+        let key_by = key_by
+            .map(|fields| {
+                fields
+                    .into_iter()
+                    .map(|(path, field)| Ok((key_path(&path)?, field)))
+                    .collect::<PyResult<Vec<_>>>()
+            })
+            .transpose()?;
+        let strings = intern_strings.then(StringCache::default);
+        let timezones = TimezoneCache::default();
+        let value_hook = value_hook.map(ValueHook::new);
+        let table_hook = table_hook.map(TableHook::new);
+        let locations = locations.then(|| {
+            let doc = toml_string.strip_prefix('\u{feff}').unwrap_or(toml_string);
+            Locations::new(doc, filename)
+        });
+
+        let options = LoadOptions {
+            parse_float,
+            preserve_format,
+            strict,
+            allow_nan,
+            only: only.as_deref(),
+            strings: strings.as_ref(),
+            timezones: Some(&timezones),
+            normalize_keys: NormalizeKeys::parse(normalize_keys)?,
+            leap_seconds: LeapSeconds::parse(leap_seconds)?,
+            datetimes: Datetimes::parse(datetimes)?,
+            value_hook: value_hook.as_ref(),
+            max_string_length,
+            max_items,
+            max_depth,
+            int_factory: int_factory.as_ref(),
+            key_by: key_by.as_deref(),
+            locations: locations.as_ref(),
+            table_hook: table_hook.as_ref(),
+        };
+
+        loads_with_options(py, toml_string, toml_version, &options)
+    }
+
+    #[pyfunction(name = "_loads_chunks")]
+    fn load_toml_from_chunks(
+        py: Python,
+        chunks: &Bound<'_, PyAny>,
         parse_float: &Bound<'_, PyAny>,
         toml_version: &str,
+        strict: bool,
+        allow_nan: bool,
+    ) -> PyResult<Py<PyAny>> {
+        let mut toml_string = String::new();
+        for chunk in chunks.try_iter()? {
+            let chunk = chunk?;
+            let chunk = chunk.cast::<PyString>().map_err(|_| {
+                PyTypeError::new_err(format!(
+                    "Expected str chunks, not '{}'",
+                    chunk
+                        .get_type()
+                        .qualname()
+                        .map_or_else(|_| String::from("<unknown>"), |name| name.to_string())
+                ))
+            })?;
+            let chunk = chunk.to_str()?;
+            toml_string
+                .try_reserve(chunk.len())
+                .map_err(|_| PyMemoryError::new_err("out of memory while reading TOML chunks"))?;
+            toml_string.push_str(chunk);
+        }
+
+        let timezones = TimezoneCache::default();
+        let options = LoadOptions {
+            strict,
+            allow_nan,
+            timezones: Some(&timezones),
+            ..LoadOptions::plain(parse_float)
+        };
+
+        loads_with_options(py, &toml_string, toml_version, &options)
+    }
+
+    pub(crate) fn loads_with_options<'py>(
+        py: Python<'py>,
+        toml_string: &str,
+        toml_version: &str,
+        options: &LoadOptions<'_, 'py>,
     ) -> PyResult<Py<PyAny>> {
+        // Text decoded by the caller may still start with a byte order mark.
+        let toml_string = toml_string.strip_prefix('\u{feff}').unwrap_or(toml_string);
+
         match toml_version {
             "1.0.0" => {
                 use toml_v1::{
@@ -40,18 +239,14 @@ mod toml_rs {
                     de::{DeTable, DeValue},
                 };
 
-                let parsed = DeTable::parse(toml_string).map_err(|err| {
-                    TOMLDecodeError::new_err((
-                        err.to_string(),
-                        toml_string.to_string(),
-                        err.span().map_or(0, |s| s.start),
-                    ))
-                })?;
+                let parsed = py
+                    .detach(|| DeTable::parse(toml_string))
+                    .map_err(|err| crate::error::parse_error(&err, toml_string))?;
 
                 let toml = crate::v1::loads::toml_to_python(
                     py,
                     &Spanned::new(parsed.span(), DeValue::Table(parsed.into_inner())),
-                    parse_float,
+                    options,
                     toml_string,
                 )?;
 
@@ -63,18 +258,14 @@ mod toml_rs {
                     de::{DeTable, DeValue},
                 };
 
-                let parsed = DeTable::parse(toml_string).map_err(|err| {
-                    TOMLDecodeError::new_err((
-                        err.to_string(),
-                        toml_string.to_string(),
-                        err.span().map_or(0, |s| s.start),
-                    ))
-                })?;
+                let parsed = py
+                    .detach(|| DeTable::parse(toml_string))
+                    .map_err(|err| crate::error::parse_error(&err, toml_string))?;
 
                 let toml = crate::v1_1::loads::toml_to_python(
                     py,
                     &Spanned::new(parsed.span(), DeValue::Table(parsed.into_inner())),
-                    parse_float,
+                    options,
                     toml_string,
                 )?;
 
@@ -86,63 +277,364 @@ mod toml_rs {
         }
     }
 
-    #[expect(clippy::needless_pass_by_value)]
+    #[expect(clippy::needless_pass_by_value, clippy::too_many_arguments)]
     #[pyfunction(name = "_dumps")]
-    fn dumps_toml(
-        py: Python,
-        obj: &Bound<'_, PyAny>,
+    pub(crate) fn dumps_toml<'py>(
+        py: Python<'py>,
+        obj: &Bound<'py, PyAny>,
         pretty: bool,
         inline_tables: Option<FxHashSet<String>>,
         toml_version: &str,
+        fallback: Option<&str>,
+        strict: bool,
+        quote_keys: bool,
+        surrogates: &str,
+        escape_form: &str,
+        sort_arrays: Option<Bound<'py, PyAny>>,
+        key_order: Option<FxHashMap<String, Vec<String>>>,
+        encode_hook: Option<Bound<'py, PyAny>>,
+        max_depth: Option<usize>,
+        indent_char: Option<Bound<'py, PyAny>>,
+        preserve_order: bool,
+        comments: Option<FxHashMap<String, String>>,
+        float_formats: Option<Bound<'py, PyAny>>,
+        datetime_formats: Option<Bound<'py, PyAny>>,
+        parse_datetime_strings: bool,
+        array_style: &str,
+        nanoseconds: &str,
+    ) -> PyResult<String> {
+        let encode_hook = encode_hook.map(ValueHook::new);
+        let float_formats = FloatFormat::extract(float_formats.as_ref())?;
+        let datetime_formats = DatetimeFormat::extract(datetime_formats.as_ref())?;
+        let indent = parse_indent(indent_char.as_ref())?;
+        let options = DumpOptions {
+            inline_tables: inline_tables.as_ref(),
+            fallback: Fallback::parse(fallback)?,
+            strict,
+            surrogates: Surrogates::parse(surrogates)?,
+            sort_arrays: SortArrays::extract(sort_arrays.as_ref())?,
+            key_order: key_order.as_ref(),
+            encode_hook: encode_hook.as_ref(),
+            max_depth,
+            preserve_order,
+            float_formats: &float_formats,
+            datetime_formats: &datetime_formats,
+            parse_datetime_strings,
+            nanoseconds: Nanoseconds::parse(nanoseconds)?,
+            pretty,
+            quote_keys,
+            escape_form: EscapeForm::parse(escape_form, toml_version)?,
+            indent: Some(&indent),
+            array_style: ArrayStyle::parse(array_style)?,
+            comments: comments.as_ref(),
+        };
+
+        dumps_with_options(py, obj, toml_version, &options)
+    }
+
+    // `dumps` with every option at its default, for callers on the Rust side.
+    pub(crate) fn dumps_default<'py>(
+        py: Python<'py>,
+        obj: &Bound<'py, PyAny>,
+        toml_version: &str,
     ) -> PyResult<String> {
+        dumps_with_options(py, obj, toml_version, &DumpOptions::default())
+    }
+
+    pub(crate) fn dumps_with_options<'py>(
+        py: Python<'py>,
+        obj: &Bound<'py, PyAny>,
+        toml_version: &str,
+        options: &DumpOptions<'_, 'py>,
+    ) -> PyResult<String> {
+        // Copied out of `options`, whose hooks cannot leave the GIL.
+        let DumpOptions {
+            inline_tables,
+            preserve_order,
+            pretty,
+            quote_keys,
+            escape_form,
+            array_style,
+            comments,
+            ..
+        } = *options;
+        let indent = options.indent.unwrap_or("    ");
+        let plain = options.is_plain();
+
         match toml_version {
             "1.0.0" => {
                 use toml_edit_v1::{DocumentMut, Item::Table, visit_mut::VisitMut};
 
                 use crate::v1::{
-                    dumps::{python_to_toml, validate_inline_paths},
-                    pretty::Pretty,
+                    comments::add_comments,
+                    dumps::{dumps_plain, python_to_toml, validate_inline_paths},
+                    escape::Escapes,
+                    pretty::{OnePerLine, Pretty},
+                    quote::QuoteKeys,
                 };
 
+                if plain && let Some(toml) = dumps_plain(py, obj, options)? {
+                    return Ok(toml);
+                }
+
                 let mut doc = DocumentMut::new();
 
-                if let Table(table) = python_to_toml(py, obj, inline_tables.as_ref())? {
+                if let Table(table) = python_to_toml(py, obj, options)? {
                     *doc.as_table_mut() = table;
                 }
 
-                if let Some(ref paths) = inline_tables {
-                    validate_inline_paths(doc.as_item(), paths)?;
-                }
+                py.detach(|| {
+                    if let Some(paths) = inline_tables {
+                        validate_inline_paths(doc.as_item(), paths)?;
+                    }
 
-                if pretty {
-                    Pretty::new(inline_tables.is_none()).visit_document_mut(&mut doc);
-                }
+                    if pretty {
+                        Pretty::new(inline_tables.is_none() && !preserve_order, indent)
+                            .visit_document_mut(&mut doc);
+                    }
 
-                Ok(doc.to_string())
+                    if array_style == ArrayStyle::OnePerLine {
+                        OnePerLine::new(indent).visit_document_mut(&mut doc);
+                    }
+
+                    if quote_keys {
+                        QuoteKeys.visit_document_mut(&mut doc);
+                    }
+
+                    if escape_form != EscapeForm::Unicode {
+                        Escapes(escape_form).visit_document_mut(&mut doc);
+                    }
+
+                    if let Some(comments) = comments {
+                        add_comments(&mut doc, comments)?;
+                    }
+
+                    render(&doc)
+                })
             }
             "1.1.0" => {
                 use toml_edit::{DocumentMut, Item::Table, visit_mut::VisitMut};
 
                 use crate::v1_1::{
-                    dumps::{python_to_toml, validate_inline_paths},
-                    pretty::Pretty,
+                    comments::add_comments,
+                    dumps::{dumps_plain, python_to_toml, validate_inline_paths},
+                    escape::Escapes,
+                    pretty::{OnePerLine, Pretty},
+                    quote::QuoteKeys,
                 };
 
+                if plain && let Some(toml) = dumps_plain(py, obj, options)? {
+                    return Ok(toml);
+                }
+
                 let mut doc = DocumentMut::new();
 
-                if let Table(table) = python_to_toml(py, obj, inline_tables.as_ref())? {
+                if let Table(table) = python_to_toml(py, obj, options)? {
                     *doc.as_table_mut() = table;
                 }
 
-                if let Some(ref paths) = inline_tables {
-                    validate_inline_paths(doc.as_item(), paths)?;
-                }
+                py.detach(|| {
+                    if let Some(paths) = inline_tables {
+                        validate_inline_paths(doc.as_item(), paths)?;
+                    }
 
-                if pretty {
-                    Pretty::new(inline_tables.is_none()).visit_document_mut(&mut doc);
-                }
+                    if pretty {
+                        Pretty::new(inline_tables.is_none() && !preserve_order, indent)
+                            .visit_document_mut(&mut doc);
+                    }
+
+                    if array_style == ArrayStyle::OnePerLine {
+                        OnePerLine::new(indent).visit_document_mut(&mut doc);
+                    }
+
+                    if quote_keys {
+                        QuoteKeys.visit_document_mut(&mut doc);
+                    }
+
+                    if escape_form != EscapeForm::Unicode {
+                        Escapes(escape_form).visit_document_mut(&mut doc);
+                    }
+
+                    if let Some(comments) = comments {
+                        add_comments(&mut doc, comments)?;
+                    }
+
+                    render(&doc)
+                })
+            }
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_watch")]
+    fn watch(
+        path: PathBuf,
+        callback: Py<PyAny>,
+        parse_float: Py<PyAny>,
+        debounce: f64,
+        toml_version: &str,
+    ) -> PyResult<Watcher> {
+        let toml_version = match toml_version {
+            "1.0.0" => "1.0.0",
+            "1.1.0" => "1.1.0",
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+        let debounce = Duration::try_from_secs_f64(debounce)
+            .map_err(|_| PyValueError::new_err(format!("Invalid debounce: {debounce}")))?;
+
+        Watcher::start(&path, callback, parse_float, debounce, toml_version)
+    }
+
+    #[pyfunction(name = "_stop_watchers")]
+    fn stop_watchers(py: Python) {
+        crate::watch::stop_all(py);
+    }
+
+    #[pyfunction(name = "_update_document")]
+    fn update_document(
+        py: Python,
+        toml_string: &str,
+        changes: &Bound<'_, PyDict>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::update_document(py, toml_string, changes),
+            "1.1.0" => crate::v1_1::edit::update_document(py, toml_string, changes),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_array_insert")]
+    fn array_insert(
+        py: Python,
+        toml_string: &str,
+        path: &str,
+        index: Option<isize>,
+        value: &Bound<'_, PyAny>,
+        line_width: usize,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => {
+                crate::v1::edit::array_insert(py, toml_string, path, index, value, line_width)
+            }
+            "1.1.0" => {
+                crate::v1_1::edit::array_insert(py, toml_string, path, index, value, line_width)
+            }
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_array_remove")]
+    fn array_remove(
+        py: Python,
+        toml_string: &str,
+        path: &str,
+        value: &Bound<'_, PyAny>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::array_remove(py, toml_string, path, value),
+            "1.1.0" => crate::v1_1::edit::array_remove(py, toml_string, path, value),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_decorate")]
+    fn decorate(
+        toml_string: &str,
+        path: &str,
+        comment: Option<&str>,
+        before: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::decorate(toml_string, path, comment, before),
+            "1.1.0" => crate::v1_1::edit::decorate(toml_string, path, comment, before),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_graft")]
+    fn graft(
+        toml_string: &str,
+        path: &str,
+        source: &str,
+        source_key: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::graft(toml_string, path, source, source_key),
+            "1.1.0" => crate::v1_1::edit::graft(toml_string, path, source, source_key),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_move_table")]
+    fn move_table(
+        toml_string: &str,
+        path: &str,
+        before: Option<&str>,
+        after: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::move_table(toml_string, path, before, after),
+            "1.1.0" => crate::v1_1::edit::move_table(toml_string, path, before, after),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_trivia")]
+    fn trivia<'py>(
+        py: Python<'py>,
+        toml_string: &str,
+        path: &str,
+        toml_version: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        match toml_version {
+            "1.0.0" => crate::v1::edit::trivia(toml_string, path)?.into_py_dict(py),
+            "1.1.0" => crate::v1_1::edit::trivia(toml_string, path)?.into_py_dict(py),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
 
-                Ok(doc.to_string())
+    #[pyfunction(name = "_set_trivia")]
+    fn set_trivia(
+        toml_string: &str,
+        path: &str,
+        before: Option<&str>,
+        before_eq: Option<&str>,
+        after_eq: Option<&str>,
+        after: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => {
+                crate::v1::edit::set_trivia(toml_string, path, before, before_eq, after_eq, after)
+            }
+            "1.1.0" => {
+                crate::v1_1::edit::set_trivia(toml_string, path, before, before_eq, after_eq, after)
             }
             _ => Err(PyValueError::new_err(format!(
                 "Unsupported TOML version: {toml_version}",
@@ -150,8 +642,230 @@ mod toml_rs {
         }
     }
 
+    #[pyfunction(name = "_add_dependency")]
+    fn add_dependency(
+        toml_string: &str,
+        requirement: &str,
+        group: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::pyproject::add_dependency(toml_string, requirement, group),
+            "1.1.0" => crate::v1_1::pyproject::add_dependency(toml_string, requirement, group),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_remove_dependency")]
+    fn remove_dependency(
+        toml_string: &str,
+        requirement: &str,
+        group: Option<&str>,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        match toml_version {
+            "1.0.0" => crate::v1::pyproject::remove_dependency(toml_string, requirement, group),
+            "1.1.0" => crate::v1_1::pyproject::remove_dependency(toml_string, requirement, group),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_decompress")]
+    fn decompress(
+        py: Python,
+        data: &[u8],
+        compression: &str,
+        encoding: Option<&str>,
+    ) -> PyResult<String> {
+        crate::compress::decompress(py, data, Compression::parse(compression)?, encoding)
+    }
+
+    #[pyfunction(name = "_decode")]
+    fn decode(py: Python, data: &[u8], encoding: Option<&str>) -> PyResult<String> {
+        crate::encoding::decode(py, data.to_vec(), encoding)
+    }
+
+    #[pyfunction(name = "_compress")]
+    fn compress<'py>(
+        py: Python<'py>,
+        toml_string: &str,
+        compression: &str,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let data = crate::compress::compress(py, toml_string, Compression::parse(compression)?)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    #[pyfunction(name = "_to_binary")]
+    fn to_binary<'py>(
+        py: Python<'py>,
+        toml_string: &str,
+        binary_format: &str,
+        toml_version: &str,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let format = Format::parse(binary_format)?;
+        let node = match toml_version {
+            "1.0.0" => crate::v1::binary::to_node(py, toml_string)?,
+            "1.1.0" => crate::v1_1::binary::to_node(py, toml_string)?,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+        let data = py.detach(|| crate::binary::encode(node, format))?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    #[pyfunction(name = "_from_binary")]
+    fn from_binary(
+        py: Python,
+        data: &[u8],
+        binary_format: &str,
+        toml_version: &str,
+    ) -> PyResult<String> {
+        let format = Format::parse(binary_format)?;
+        let node = py.detach(|| crate::binary::decode(data, format))?;
+        match toml_version {
+            "1.0.0" => crate::v1::binary::from_node(node),
+            "1.1.0" => crate::v1_1::binary::from_node(node),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_read_fd")]
+    fn read_fd(py: Python, fd: i32) -> PyResult<String> {
+        crate::fd::read_fd(py, fd)
+    }
+
+    #[pyfunction(name = "_position")]
+    fn position(text: &str, offset: usize) -> (usize, usize) {
+        crate::error::position(text, offset)
+    }
+
+    #[pyfunction(name = "_iter_tables")]
+    fn iter_tables(
+        py: Python,
+        toml_string: &str,
+        parse_float: Py<PyAny>,
+        toml_version: &str,
+    ) -> PyResult<Py<PyAny>> {
+        match toml_version {
+            "1.0.0" => Ok(Py::new(
+                py,
+                crate::v1::iter::TableIter::new(py, toml_string, parse_float)?,
+            )?
+            .into_any()),
+            "1.1.0" => Ok(Py::new(
+                py,
+                crate::v1_1::iter::TableIter::new(py, toml_string, parse_float)?,
+            )?
+            .into_any()),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_parse_comments")]
+    fn parse_comments(py: Python, toml_string: &str, toml_version: &str) -> PyResult<Py<PyAny>> {
+        match toml_version {
+            "1.0.0" => Ok(crate::v1::comments::extract_comments(py, toml_string)?
+                .into_any()
+                .unbind()),
+            "1.1.0" => Ok(crate::v1_1::comments::extract_comments(py, toml_string)?
+                .into_any()
+                .unbind()),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_get_comment")]
+    fn get_comment(
+        toml_string: &str,
+        path: &str,
+        toml_version: &str,
+    ) -> PyResult<Option<(Option<String>, Option<String>)>> {
+        match toml_version {
+            "1.0.0" => crate::v1::comments::comment_at(toml_string, path),
+            "1.1.0" => crate::v1_1::comments::comment_at(toml_string, path),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_get_raw")]
+    fn get_raw(
+        toml_string: &str,
+        path: &str,
+        toml_version: &str,
+    ) -> PyResult<Option<(usize, usize)>> {
+        match toml_version {
+            "1.0.0" => crate::v1::raw::raw_span(toml_string, path),
+            "1.1.0" => crate::v1_1::raw::raw_span(toml_string, path),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_tokenize")]
+    fn tokenize(py: Python, toml_string: &str, toml_version: &str) -> PyResult<Py<PyAny>> {
+        match toml_version {
+            "1.0.0" => Ok(crate::v1::tokens::tokenize(py, toml_string)?
+                .into_any()
+                .unbind()),
+            "1.1.0" => Ok(crate::v1_1::tokens::tokenize(py, toml_string)?
+                .into_any()
+                .unbind()),
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported TOML version: {toml_version}",
+            ))),
+        }
+    }
+
+    #[pyfunction(name = "_set_error_formatter")]
+    fn set_error_formatter(formatter: Option<Py<PyAny>>) {
+        crate::error::set_error_formatter(formatter);
+    }
+
+    #[pyfunction(name = "_canonicalize")]
+    fn canonicalize(py: Python, obj: &Bound<'_, PyAny>) -> PyResult<String> {
+        crate::digest::canonicalize(py, obj)
+    }
+
+    #[pyfunction(name = "_deep_merge")]
+    fn deep_merge<'py>(
+        base: &Bound<'py, PyDict>,
+        overlay: &Bound<'py, PyDict>,
+        arrays: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        crate::merge::deep_merge(base, overlay, ArrayMerge::parse(arrays)?)
+    }
+
+    #[pyfunction(name = "_infer_schema")]
+    fn infer_schema<'py>(
+        py: Python<'py>,
+        docs: Vec<Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        crate::schema::infer_schema(py, &docs)
+    }
+
+    #[pyfunction(name = "_typed_dicts")]
+    fn typed_dicts(schema: &Bound<'_, PyDict>, name: &str) -> PyResult<String> {
+        crate::schema::typed_dicts(schema, name)
+    }
+
     #[pyfunction(name = "_parse_metadata_from_string")]
-    fn parse_metadata_from_string(
+    pub(crate) fn parse_metadata_from_string(
         py: Python,
         toml_string: &str,
         toml_version: &str,
@@ -162,13 +876,9 @@ mod toml_rs {
 
                 use crate::v1::metadata::{extract_metadata, to_python};
 
-                let parsed = DeTable::parse(toml_string).map_err(|err| {
-                    TOMLDecodeError::new_err((
-                        err.to_string(),
-                        toml_string.to_string(),
-                        err.span().map_or(0, |s| s.start),
-                    ))
-                })?;
+                let parsed = py
+                    .detach(|| DeTable::parse(toml_string))
+                    .map_err(|err| crate::error::parse_error(&err, toml_string))?;
 
                 let meta = extract_metadata(py, &parsed, toml_string)?;
 
@@ -181,6 +891,8 @@ mod toml_rs {
                     TOMLDocument {
                         value: value.unbind(),
                         meta: meta.unbind(),
+                        text: toml_string.to_owned(),
+                        toml_version: "1.0.0",
                     },
                 )?;
 
@@ -191,13 +903,9 @@ mod toml_rs {
 
                 use crate::v1_1::metadata::{extract_metadata, to_python};
 
-                let parsed = DeTable::parse(toml_string).map_err(|err| {
-                    TOMLDecodeError::new_err((
-                        err.to_string(),
-                        toml_string.to_string(),
-                        err.span().map_or(0, |s| s.start),
-                    ))
-                })?;
+                let parsed = py
+                    .detach(|| DeTable::parse(toml_string))
+                    .map_err(|err| crate::error::parse_error(&err, toml_string))?;
 
                 let meta = extract_metadata(py, &parsed, toml_string)?;
 
@@ -210,6 +918,8 @@ mod toml_rs {
                     TOMLDocument {
                         value: value.unbind(),
                         meta: meta.unbind(),
+                        text: toml_string.to_owned(),
+                        toml_version: "1.1.0",
                     },
                 )?;
 
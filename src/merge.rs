@@ -0,0 +1,76 @@
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+
+// What `deep_merge` does when both sides have an array under the same key.
+#[derive(Clone, Copy)]
+pub enum ArrayMerge {
+    // The overlay's array wins, like any other value.
+    Replace,
+    // The overlay's items go after the base's.
+    Append,
+    // Like `Append`, skipping items equal to one already in the array.
+    Unique,
+}
+
+impl ArrayMerge {
+    pub fn parse(value: &str) -> PyResult<Self> {
+        match value {
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            "unique" => Ok(Self::Unique),
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported arrays policy: {other}"
+            ))),
+        }
+    }
+}
+
+// Merges `overlay` over `base` into a new dict. Tables are merged key by key
+// and any other value in `overlay` replaces the one in `base`. Neither input
+// is modified: merged tables and arrays are new objects, everything else is
+// shared with the inputs, as with `dict.copy`.
+pub fn deep_merge<'py>(
+    base: &Bound<'py, PyDict>,
+    overlay: &Bound<'py, PyDict>,
+    arrays: ArrayMerge,
+) -> PyResult<Bound<'py, PyDict>> {
+    let merged = base.copy()?;
+    for (key, value) in overlay.iter() {
+        let value = match merged.get_item(&key)? {
+            Some(existing) => merge_value(&existing, &value, arrays)?,
+            None => value,
+        };
+        merged.set_item(key, value)?;
+    }
+    Ok(merged)
+}
+
+fn merge_value<'py>(
+    existing: &Bound<'py, PyAny>,
+    value: &Bound<'py, PyAny>,
+    arrays: ArrayMerge,
+) -> PyResult<Bound<'py, PyAny>> {
+    if let (Ok(existing), Ok(value)) = (existing.cast::<PyDict>(), value.cast::<PyDict>()) {
+        return Ok(deep_merge(existing, value, arrays)?.into_any());
+    }
+
+    let (Ok(existing), Ok(value)) = (existing.cast::<PyList>(), value.cast::<PyList>()) else {
+        return Ok(value.clone());
+    };
+    match arrays {
+        ArrayMerge::Replace => Ok(value.clone().into_any()),
+        ArrayMerge::Append => Ok(existing.add(value)?),
+        ArrayMerge::Unique => {
+            let merged = PyList::new(existing.py(), existing.iter())?;
+            for item in value.iter() {
+                if !merged.contains(&item)? {
+                    merged.append(item)?;
+                }
+            }
+            Ok(merged.into_any())
+        }
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::BTreeSet;
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBool, PyDate, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyString, PyTime},
+};
+use rustc_hash::FxHashSet;
+
+// Strings become an `enum` when they take at most this many distinct values.
+const ENUM_MAX_VALUES: usize = 5;
+
+// Everything seen at one place in the sample documents.
+#[derive(Default)]
+struct Shape {
+    // How many values were seen here.
+    count: usize,
+    // JSON types seen, in the order they were first seen. Datetimes are
+    // `string` with a `format`.
+    types: Vec<(&'static str, Option<&'static str>)>,
+    // Distinct plain strings, until there are too many for an `enum`.
+    strings: Option<FxHashSet<String>>,
+    string_count: usize,
+    object_count: usize,
+    properties: Vec<(String, Self)>,
+    items: Option<Box<Self>>,
+}
+
+// Infers a JSON Schema that every one of `docs` validates against:
+//
+// * keys present in every sample of a table are `required`
+// * integers and floats under the same key widen to `number`
+// * dates, times and datetimes are strings with a `format`
+// * strings that take few distinct values, each seen at least twice on
+//   average, are listed as an `enum`
+pub fn infer_schema<'py>(
+    py: Python<'py>,
+    docs: &[Bound<'py, PyDict>],
+) -> PyResult<Bound<'py, PyDict>> {
+    let mut shape = Shape::default();
+    for doc in docs {
+        shape.add(doc.as_any())?;
+    }
+
+    let schema = PyDict::new(py);
+    schema.set_item("$schema", "https://json-schema.org/draft/2020-12/schema")?;
+    for (key, value) in shape.to_schema(py)?.iter() {
+        schema.set_item(key, value)?;
+    }
+    Ok(schema)
+}
+
+impl Shape {
+    fn add(&mut self, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.count += 1;
+
+        if let Ok(table) = value.cast::<PyDict>() {
+            self.see("object", None);
+            self.object_count += 1;
+            for (key, value) in table.iter() {
+                let key = key.extract::<String>()?;
+                let index = self
+                    .properties
+                    .iter()
+                    .position(|(name, _)| *name == key)
+                    .unwrap_or_else(|| {
+                        self.properties.push((key, Self::default()));
+                        self.properties.len() - 1
+                    });
+                self.properties[index].1.add(&value)?;
+            }
+        } else if let Ok(array) = value.cast::<PyList>() {
+            self.see("array", None);
+            let items = self.items.get_or_insert_with(Box::default);
+            for item in array.iter() {
+                items.add(&item)?;
+            }
+        } else if let Ok(string) = value.cast::<PyString>() {
+            self.see("string", None);
+            if self.string_count == 0 {
+                self.strings = Some(FxHashSet::default());
+            }
+            self.string_count += 1;
+            if let Some(strings) = self.strings.as_mut() {
+                strings.insert(string.to_str()?.to_owned());
+                if strings.len() > ENUM_MAX_VALUES {
+                    self.strings = None;
+                }
+            }
+        } else if value.is_instance_of::<PyBool>() {
+            self.see("boolean", None);
+        } else if value.is_instance_of::<PyInt>() {
+            self.see("integer", None);
+        } else if value.is_instance_of::<PyFloat>() {
+            self.see("number", None);
+        } else if value.is_instance_of::<PyDateTime>() {
+            self.see("string", Some("date-time"));
+        } else if value.is_instance_of::<PyDate>() {
+            self.see("string", Some("date"));
+        } else if value.is_instance_of::<PyTime>() {
+            self.see("string", Some("time"));
+        }
+        Ok(())
+    }
+
+    fn see(&mut self, kind: &'static str, format: Option<&'static str>) {
+        if !self.types.contains(&(kind, format)) {
+            self.types.push((kind, format));
+        }
+    }
+
+    fn to_schema<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let schema = PyDict::new(py);
+
+        let mut kinds = Vec::new();
+        for (kind, _) in &self.types {
+            if !kinds.contains(kind) {
+                kinds.push(*kind);
+            }
+        }
+        if kinds.contains(&"number") {
+            kinds.retain(|kind| *kind != "integer");
+        }
+        match kinds.as_slice() {
+            [] => {}
+            [kind] => schema.set_item("type", *kind)?,
+            kinds => schema.set_item("type", kinds.to_vec())?,
+        }
+
+        // A `format` only holds when every string here had it.
+        if let [("string", Some(format))] = self
+            .types
+            .iter()
+            .filter(|(kind, _)| *kind == "string")
+            .copied()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            schema.set_item("format", *format)?;
+        }
+
+        if let Some(strings) = &self.strings
+            && strings.len() * 2 <= self.string_count
+        {
+            let mut values = strings.iter().collect::<Vec<_>>();
+            values.sort_unstable();
+            schema.set_item("enum", values)?;
+        }
+
+        if let Some(items) = &self.items
+            && items.count > 0
+        {
+            schema.set_item("items", items.to_schema(py)?)?;
+        }
+
+        if self.object_count > 0 {
+            let properties = PyDict::new(py);
+            let mut required = Vec::new();
+            for (key, shape) in &self.properties {
+                properties.set_item(key, shape.to_schema(py)?)?;
+                if shape.count == self.object_count {
+                    required.push(key);
+                }
+            }
+            schema.set_item("properties", properties)?;
+            if !required.is_empty() {
+                schema.set_item("required", required)?;
+            }
+        }
+
+        Ok(schema)
+    }
+}
+
+const JSON_TYPES: [&str; 7] = [
+    "object", "array", "string", "integer", "number", "boolean", "null",
+];
+
+// Keywords that only describe a schema, so they never fail a value.
+const ANNOTATIONS: [&str; 7] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+// A JSON Schema checked once up front and kept ready to validate loaded
+// documents against. The keywords `infer_schema` writes are supported:
+// `type`, `format`, `enum`, `properties`, `required` and `items`. Any other
+// keyword is refused, rather than ignored and trusted to hold.
+#[pyclass(frozen, module = "toml_rs")]
+pub struct Schema {
+    root: Node,
+}
+
+#[derive(Default)]
+struct Node {
+    // Any type is allowed when empty.
+    types: Vec<String>,
+    format: Option<String>,
+    values: Option<Vec<Py<PyAny>>>,
+    properties: Vec<(String, Self)>,
+    required: Vec<String>,
+    items: Option<Box<Self>>,
+}
+
+#[pymethods]
+impl Schema {
+    #[staticmethod]
+    #[pyo3(signature = (schema, /))]
+    fn compile(schema: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(Self {
+            root: Node::compile(schema, "")?,
+        })
+    }
+
+    // Raises `ValueError` naming the first key path that does not match.
+    #[pyo3(signature = (data, /))]
+    fn validate(&self, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.root.validate(data, "")
+    }
+}
+
+impl Node {
+    fn compile(schema: &Bound<'_, PyAny>, path: &str) -> PyResult<Self> {
+        let schema = schema.cast::<PyDict>().map_err(|_| {
+            PyValueError::new_err(format!("Schema for {} is not an object", place(path)))
+        })?;
+
+        let mut node = Self::default();
+        for (keyword, value) in schema.iter() {
+            let keyword = keyword.extract::<String>()?;
+            match keyword.as_str() {
+                "type" => {
+                    node.types = match value.extract::<String>() {
+                        Ok(kind) => vec![kind],
+                        Err(_) => value.extract::<Vec<String>>()?,
+                    };
+                    if let Some(kind) = node
+                        .types
+                        .iter()
+                        .find(|kind| !JSON_TYPES.contains(&kind.as_str()))
+                    {
+                        return Err(PyValueError::new_err(format!(
+                            "Unknown type {kind:?} in the schema for {}",
+                            place(path)
+                        )));
+                    }
+                }
+                "format" => node.format = Some(value.extract()?),
+                "enum" => {
+                    node.values = Some(
+                        value
+                            .try_iter()?
+                            .map(|value| value.map(Bound::unbind))
+                            .collect::<PyResult<_>>()?,
+                    );
+                }
+                "properties" => {
+                    for (key, property) in value.cast::<PyDict>()?.iter() {
+                        let key = key.extract::<String>()?;
+                        let property = Self::compile(&property, &child_path(path, &key))?;
+                        node.properties.push((key, property));
+                    }
+                }
+                "required" => node.required = value.extract()?,
+                "items" => {
+                    node.items = Some(Box::new(Self::compile(&value, &format!("{path}[]"))?))
+                }
+                keyword if ANNOTATIONS.contains(&keyword) => {}
+                keyword => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unsupported schema keyword {keyword:?} in the schema for {}",
+                        place(path)
+                    )));
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    fn validate(&self, value: &Bound<'_, PyAny>, path: &str) -> PyResult<()> {
+        let invalid = |message: String| {
+            PyValueError::new_err(format!("Invalid value for {}: {message}", place(path)))
+        };
+
+        if let Some((kind, format)) = json_type(value) {
+            if !self.types.is_empty()
+                && !self
+                    .types
+                    .iter()
+                    .any(|expected| expected == kind || (expected == "number" && kind == "integer"))
+            {
+                return Err(invalid(format!(
+                    "expected {}, got {kind}",
+                    self.types.join(" or ")
+                )));
+            }
+
+            // Datetimes are loaded as objects, so their format is their type;
+            // the format of a plain string is not checked.
+            if let (Some(expected), Some(format)) = (&self.format, format)
+                && expected != format
+            {
+                return Err(invalid(format!("expected {expected}, got {format}")));
+            }
+        }
+
+        if let Some(values) = &self.values {
+            let mut allowed = false;
+            for allowed_value in values {
+                if value.eq(allowed_value)? {
+                    allowed = true;
+                    break;
+                }
+            }
+            if !allowed {
+                return Err(invalid(format!(
+                    "{} is not one of the allowed values",
+                    value.repr()?
+                )));
+            }
+        }
+
+        if let Ok(table) = value.cast::<PyDict>() {
+            for key in &self.required {
+                if !table.contains(key)? {
+                    return Err(invalid(format!("missing required key {key:?}")));
+                }
+            }
+            for (key, property) in &self.properties {
+                if let Some(child) = table.get_item(key)? {
+                    property.validate(&child, &child_path(path, key))?;
+                }
+            }
+        } else if let Ok(array) = value.cast::<PyList>()
+            && let Some(items) = &self.items
+        {
+            for (index, item) in array.iter().enumerate() {
+                items.validate(&item, &format!("{path}[{index}]"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The JSON type of a loaded value, and the `format` of a datetime, the same
+// way `infer_schema` sees them. Other objects, like the `Decimal`s of
+// `parse_float`, have no type to check.
+fn json_type(value: &Bound<'_, PyAny>) -> Option<(&'static str, Option<&'static str>)> {
+    let json_type = if value.is_instance_of::<PyDict>() {
+        ("object", None)
+    } else if value.is_instance_of::<PyList>() {
+        ("array", None)
+    } else if value.is_instance_of::<PyString>() {
+        ("string", None)
+    } else if value.is_instance_of::<PyBool>() {
+        ("boolean", None)
+    } else if value.is_instance_of::<PyInt>() {
+        ("integer", None)
+    } else if value.is_instance_of::<PyFloat>() {
+        ("number", None)
+    } else if value.is_instance_of::<PyDateTime>() {
+        ("string", Some("date-time"))
+    } else if value.is_instance_of::<PyDate>() {
+        ("string", Some("date"))
+    } else if value.is_instance_of::<PyTime>() {
+        ("string", Some("time"))
+    } else if value.is_none() {
+        ("null", None)
+    } else {
+        return None;
+    };
+    Some(json_type)
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn place(path: &str) -> String {
+    if path.is_empty() {
+        String::from("the document")
+    } else {
+        format!("{path:?}")
+    }
+}
+
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+// Python source with a `TypedDict` for every table in a JSON Schema, the one
+// for `schema` itself named `name`. Nested tables get their parent's name
+// plus their key in PascalCase, and come before the classes that use them.
+// Keys missing from `required` are `NotRequired`, which needs Python 3.11.
+pub fn typed_dicts(schema: &Bound<'_, PyDict>, name: &str) -> PyResult<String> {
+    let mut stubs = Stubs::default();
+    stubs.class(schema, name)?;
+
+    let mut source = String::new();
+    if stubs.datetime {
+        source.push_str("import datetime\n");
+    }
+    let imports = stubs.imports.into_iter().collect::<Vec<_>>();
+    source.push_str(&format!("from typing import {}\n", imports.join(", ")));
+    for class in stubs.classes {
+        source.push_str("\n\n");
+        source.push_str(&class);
+    }
+    Ok(source)
+}
+
+#[derive(Default)]
+struct Stubs {
+    // Rendered classes, each after the classes it refers to.
+    classes: Vec<String>,
+    names: FxHashSet<String>,
+    imports: BTreeSet<&'static str>,
+    datetime: bool,
+}
+
+impl Stubs {
+    fn annotation(&mut self, schema: &Bound<'_, PyAny>, name: &str) -> PyResult<String> {
+        let Ok(schema) = schema.cast::<PyDict>() else {
+            return Ok(self.any());
+        };
+
+        if let Some(values) = schema.get_item("enum")? {
+            self.imports.insert("Literal");
+            let values = values
+                .try_iter()?
+                .map(|value| literal(&value?))
+                .collect::<PyResult<Vec<_>>>()?;
+            return Ok(format!("Literal[{}]", values.join(", ")));
+        }
+
+        let kinds = match schema.get_item("type")? {
+            None => Vec::new(),
+            Some(kind) => match kind.extract::<String>() {
+                Ok(kind) => vec![kind],
+                Err(_) => kind.extract::<Vec<String>>()?,
+            },
+        };
+        let format = schema
+            .get_item("format")?
+            .map(|format| format.extract::<String>())
+            .transpose()?;
+
+        let mut parts = Vec::new();
+        for kind in kinds {
+            let part = match (kind.as_str(), format.as_deref()) {
+                ("string", Some(format @ ("date-time" | "date" | "time"))) => {
+                    self.datetime = true;
+                    match format {
+                        "date-time" => "datetime.datetime",
+                        "date" => "datetime.date",
+                        _ => "datetime.time",
+                    }
+                    .to_owned()
+                }
+                ("string", _) => "str".to_owned(),
+                ("integer", _) => "int".to_owned(),
+                ("number", _) => "float".to_owned(),
+                ("boolean", _) => "bool".to_owned(),
+                ("null", _) => "None".to_owned(),
+                ("array", _) => {
+                    let item = match schema.get_item("items")? {
+                        Some(items) => self.annotation(&items, &format!("{name}Item"))?,
+                        None => self.any(),
+                    };
+                    format!("list[{item}]")
+                }
+                ("object", _) if schema.contains("properties")? => self.class(schema, name)?,
+                ("object", _) => format!("dict[str, {}]", self.any()),
+                _ => self.any(),
+            };
+            if !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+
+        if parts.is_empty() {
+            return Ok(self.any());
+        }
+        Ok(parts.join(" | "))
+    }
+
+    // Renders the `TypedDict` for a table schema and returns its name.
+    fn class(&mut self, schema: &Bound<'_, PyDict>, name: &str) -> PyResult<String> {
+        let mut unique = name.to_owned();
+        let mut suffix = 1;
+        while self.names.contains(&unique) {
+            suffix += 1;
+            unique = format!("{name}{suffix}");
+        }
+        let name = unique;
+        self.names.insert(name.clone());
+
+        let required = schema
+            .get_item("required")?
+            .map(|required| required.extract::<Vec<String>>())
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut fields = Vec::new();
+        if let Some(properties) = schema.get_item("properties")? {
+            for (key, value) in properties.cast::<PyDict>()?.iter() {
+                let repr = literal(&key)?;
+                let key = key.extract::<String>()?;
+                let mut annotation =
+                    self.annotation(&value, &format!("{name}{}", pascal_case(&key)))?;
+                if !required.contains(&key) {
+                    self.imports.insert("NotRequired");
+                    annotation = format!("NotRequired[{annotation}]");
+                }
+                fields.push((key, repr, annotation));
+            }
+        }
+
+        self.imports.insert("TypedDict");
+        let mut class = String::new();
+        if fields.iter().all(|(key, _, _)| is_identifier(key)) {
+            class.push_str(&format!("class {name}(TypedDict):\n"));
+            if fields.is_empty() {
+                class.push_str("    pass\n");
+            }
+            for (key, _, annotation) in &fields {
+                class.push_str(&format!("    {key}: {annotation}\n"));
+            }
+        } else {
+            // Keys like `python-version` cannot be class attributes, so the
+            // class is written with the functional syntax.
+            class.push_str(&format!("{name} = TypedDict(\n    \"{name}\",\n    {{\n"));
+            for (_, repr, annotation) in &fields {
+                class.push_str(&format!("        {repr}: {annotation},\n"));
+            }
+            class.push_str("    },\n)\n");
+        }
+        self.classes.push(class);
+
+        Ok(name)
+    }
+
+    fn any(&mut self) -> String {
+        self.imports.insert("Any");
+        "Any".to_owned()
+    }
+}
+
+// `repr(value)`, with strings in double quotes where that needs no escapes.
+fn literal(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    let repr = value.repr()?.to_string();
+    if let Ok(string) = value.cast::<PyString>()
+        && !string.to_str()?.contains('"')
+        && let Some(inner) = repr
+            .strip_prefix('\'')
+            .and_then(|repr| repr.strip_suffix('\''))
+    {
+        return Ok(format!("\"{inner}\""));
+    }
+    Ok(repr)
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+        && chars.all(|ch| ch == '_' || ch.is_alphanumeric())
+        && !PYTHON_KEYWORDS.contains(&key)
+}
+
+fn pascal_case(key: &str) -> String {
+    key.split(|ch: char| !ch.is_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
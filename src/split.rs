@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::document::parse_key_path;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Newline,
+    Whitespace,
+    Other,
+}
+
+// The tokens of `text` as far as headers are concerned. The lexer needs no
+// context, so it can restart at any token boundary.
+fn lex(text: &str, toml_version: &str) -> Vec<(Token, Range<usize>)> {
+    macro_rules! lex {
+        ($toml_parser:ident) => {{
+            use $toml_parser::{Source, lexer::TokenKind};
+
+            Source::new(text)
+                .lex()
+                .map(|token| {
+                    let kind = match token.kind() {
+                        TokenKind::LeftSquareBracket | TokenKind::LeftCurlyBracket => Token::Open,
+                        TokenKind::RightSquareBracket | TokenKind::RightCurlyBracket => {
+                            Token::Close
+                        }
+                        TokenKind::Newline => Token::Newline,
+                        TokenKind::Whitespace => Token::Whitespace,
+                        _ => Token::Other,
+                    };
+                    (kind, token.span().start()..token.span().end())
+                })
+                .collect()
+        }};
+    }
+
+    match toml_version {
+        "1.0.0" => lex!(toml_parser_v1),
+        _ => lex!(toml_parser),
+    }
+}
+
+// Cuts a document into the elements of one array of tables as its lines
+// arrive, so a long file is never held whole. Only the text since the last
+// line the lexer finished, or since the start of the current element, is
+// kept. Each element starts at a line and comes with the character offset
+// and the number of lines before it in the document.
+#[pyclass(name = "_ArrayTableSplitter", module = "toml_rs")]
+pub struct ArrayTableSplitter {
+    path: Vec<String>,
+    toml_version: &'static str,
+    buffer: String,
+    // Where `buffer` starts in the document, in characters.
+    offset: usize,
+    line: usize,
+    // Where lexing resumes in `buffer`: always the start of a line, with
+    // `depth` arrays and inline tables open.
+    scan: usize,
+    depth: usize,
+    // Where the current element starts in `buffer`.
+    element: Option<usize>,
+}
+
+#[pymethods]
+impl ArrayTableSplitter {
+    #[new]
+    #[pyo3(signature = (name, /, *, toml_version = "1.0.0"))]
+    fn new(name: &str, toml_version: &str) -> PyResult<Self> {
+        let toml_version = match toml_version {
+            "1.0.0" => "1.0.0",
+            "1.1.0" => "1.1.0",
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+        let path = parse_key_path(name)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid key path: {name:?}")))?;
+
+        Ok(Self {
+            path,
+            toml_version,
+            buffer: String::new(),
+            offset: 0,
+            line: 0,
+            scan: 0,
+            depth: 0,
+            element: None,
+        })
+    }
+
+    // Adds the next lines of the document, returning the elements they end.
+    fn feed(&mut self, text: &str) -> Vec<(String, usize, usize)> {
+        self.buffer.push_str(text);
+        self.split(false)
+    }
+
+    // Ends the document, returning the element still open.
+    fn finish(&mut self) -> Vec<(String, usize, usize)> {
+        self.split(true)
+    }
+
+    #[getter]
+    fn path(&self) -> Vec<String> {
+        self.path.clone()
+    }
+}
+
+impl ArrayTableSplitter {
+    fn split(&mut self, at_end: bool) -> Vec<(String, usize, usize)> {
+        let base = self.scan;
+        let tokens = lex(&self.buffer[base..], self.toml_version);
+        // Tokens after the last newline may still grow, like a multi-line
+        // string, so they are lexed again with the next lines.
+        let limit = if at_end {
+            tokens.len()
+        } else {
+            tokens
+                .iter()
+                .rposition(|(kind, _)| *kind == Token::Newline)
+                .map_or(0, |last| last + 1)
+        };
+
+        let mut elements = Vec::new();
+        let mut line_start = true;
+        let mut i = 0;
+        while i < limit {
+            let (kind, ref span) = tokens[i];
+            match kind {
+                Token::Whitespace => {}
+                Token::Newline => {
+                    line_start = true;
+                    self.scan = base + span.end;
+                }
+                // A header, the only thing that opens a line outside of values.
+                Token::Open if line_start && self.depth == 0 => {
+                    let end = tokens[i..limit]
+                        .iter()
+                        .position(|(kind, _)| *kind == Token::Newline)
+                        .map_or(limit, |newline| i + newline);
+                    self.header(base, &tokens[i..end], &mut elements);
+                    line_start = false;
+                    i = end;
+                    continue;
+                }
+                Token::Open => self.depth += 1,
+                Token::Close => self.depth = self.depth.saturating_sub(1),
+                Token::Other => {}
+            }
+            if kind != Token::Whitespace && kind != Token::Newline {
+                line_start = false;
+            }
+            i += 1;
+        }
+
+        if at_end {
+            if let Some(start) = self.element.take() {
+                elements.push(self.cut(start, self.buffer.len()));
+            }
+            self.scan = self.buffer.len();
+        }
+        self.drain();
+        elements
+    }
+
+    // Starts or ends the current element at the header made of `tokens`.
+    // A header that is not a key path is left to the parser, as part of
+    // whatever element it is in.
+    fn header(
+        &mut self,
+        base: usize,
+        tokens: &[(Token, Range<usize>)],
+        elements: &mut Vec<(String, usize, usize)>,
+    ) {
+        // From the start of the line, so an element is cut at a line break.
+        let start = self.buffer[..base + tokens[0].1.start]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let array = tokens
+            .get(1)
+            .is_some_and(|(kind, span)| *kind == Token::Open && span.start == tokens[0].1.end);
+        let keys_start = base + tokens[usize::from(array)].1.end;
+        let Some(keys_end) = tokens
+            .iter()
+            .find(|(kind, _)| *kind == Token::Close)
+            .map(|(_, span)| base + span.start)
+        else {
+            return;
+        };
+        let Some(path) = parse_key_path(&self.buffer[keys_start..keys_end]) else {
+            return;
+        };
+
+        let starts_element = array && path == self.path;
+        let in_element = path.len() > self.path.len() && path.starts_with(&self.path);
+        if let Some(element) = self.element
+            && !in_element
+        {
+            elements.push(self.cut(element, start));
+            self.element = None;
+        }
+        if starts_element {
+            self.element = Some(start);
+        }
+    }
+
+    fn cut(&self, start: usize, end: usize) -> (String, usize, usize) {
+        let line = self.line + self.buffer[..start].matches('\n').count();
+        let offset = self.offset + self.buffer[..start].chars().count();
+        (self.buffer[start..end].to_owned(), line, offset)
+    }
+
+    // Forgets the text neither the lexer nor the current element needs.
+    fn drain(&mut self) {
+        let keep = self
+            .element
+            .map_or(self.scan, |element| element.min(self.scan));
+        self.line += self.buffer[..keep].matches('\n').count();
+        self.offset += self.buffer[..keep].chars().count();
+        self.buffer.drain(..keep);
+        self.scan -= keep;
+        self.element = self.element.map(|element| element - keep);
+    }
+}
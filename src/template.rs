@@ -0,0 +1,225 @@
+use std::{fmt::Write, ops::Range};
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBool, PyDict, PyInt, PyList, PyString, PyType},
+};
+
+#[derive(Clone)]
+pub enum Part {
+    Key(String),
+    Index(usize),
+}
+
+// How a document is laid out: the span of every value, and the number of
+// entries in every table and array of tables.
+#[derive(Default)]
+pub struct Layout {
+    pub values: Vec<(Vec<Part>, Range<usize>)>,
+    pub tables: Vec<(Vec<Part>, usize)>,
+    pub arrays: Vec<(Vec<Part>, usize)>,
+}
+
+// `dumps` output for an example, cut at its values. Dicts with the same
+// keys, tables and value types are written by putting their values in the
+// gaps, without building and rendering a document; only values with no
+// shortcut below are converted one by one.
+#[pyclass(frozen, module = "toml_rs")]
+pub struct DumpTemplate {
+    // The text around the values, one more than there are values.
+    segments: Vec<String>,
+    values: Vec<Slot>,
+    tables: Vec<(Vec<Part>, usize)>,
+    arrays: Vec<(Vec<Part>, usize)>,
+    toml_version: &'static str,
+}
+
+struct Slot {
+    path: Vec<Part>,
+    value_type: Py<PyType>,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Bool,
+    Int,
+    String,
+    Other,
+}
+
+#[pymethods]
+impl DumpTemplate {
+    #[staticmethod]
+    #[pyo3(signature = (example, /, *, toml_version = "1.0.0"))]
+    fn from_example(
+        py: Python<'_>,
+        example: &Bound<'_, PyDict>,
+        toml_version: &str,
+    ) -> PyResult<Self> {
+        let toml_version = match toml_version {
+            "1.0.0" => "1.0.0",
+            "1.1.0" => "1.1.0",
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+
+        let text = crate::toml_rs::dumps_default(py, example.as_any(), toml_version)?;
+        let mut layout = match toml_version {
+            "1.0.0" => crate::v1::raw::layout(&text)?,
+            _ => crate::v1_1::raw::layout(&text)?,
+        };
+        layout.values.sort_by_key(|(_, span)| span.start);
+
+        let mut segments = Vec::with_capacity(layout.values.len() + 1);
+        let mut values = Vec::with_capacity(layout.values.len());
+        let mut end = 0;
+        for (path, span) in layout.values {
+            let value = lookup(example.as_any(), &path)?
+                .ok_or_else(|| mismatch(&path, "the example changed while it was written"))?;
+            let kind = if value.is_exact_instance_of::<PyBool>() {
+                Kind::Bool
+            } else if value.is_exact_instance_of::<PyInt>() {
+                Kind::Int
+            } else if value.is_exact_instance_of::<PyString>() {
+                Kind::String
+            } else {
+                Kind::Other
+            };
+
+            segments.push(text[end..span.start].to_owned());
+            end = span.end;
+            values.push(Slot {
+                path,
+                value_type: value.get_type().unbind(),
+                kind,
+            });
+        }
+        segments.push(text[end..].to_owned());
+
+        Ok(Self {
+            segments,
+            values,
+            tables: layout.tables,
+            arrays: layout.arrays,
+            toml_version,
+        })
+    }
+
+    // Raises `ValueError` when `values` is not shaped like the example.
+    #[pyo3(signature = (values, /))]
+    fn dumps(&self, py: Python<'_>, values: &Bound<'_, PyDict>) -> PyResult<String> {
+        let root = values.as_any();
+        for (path, len) in &self.arrays {
+            match lookup(root, path)? {
+                Some(array) if array.is_instance_of::<PyList>() && array.len()? == *len => {}
+                _ => return Err(mismatch(path, &format!("expected a list of {len} tables"))),
+            }
+        }
+        for (path, len) in &self.tables {
+            match lookup(root, path)? {
+                Some(table) if table.is_instance_of::<PyDict>() && table.len()? == *len => {}
+                _ => return Err(mismatch(path, &format!("expected a table of {len} keys"))),
+            }
+        }
+
+        let mut out = String::with_capacity(self.segments.iter().map(String::len).sum());
+        for (segment, slot) in self.segments.iter().zip(&self.values) {
+            out.push_str(segment);
+            let value = lookup(root, &slot.path)?.ok_or_else(|| mismatch(&slot.path, "missing"))?;
+            let value_type = slot.value_type.bind(py);
+            if !value.get_type().is(value_type) {
+                return Err(mismatch(
+                    &slot.path,
+                    &format!(
+                        "expected {}, got {}",
+                        value_type.qualname()?,
+                        value.get_type().qualname()?
+                    ),
+                ));
+            }
+            self.write(slot.kind, &value, &mut out)?;
+        }
+        if let Some(last) = self.segments.last() {
+            out.push_str(last);
+        }
+        Ok(out)
+    }
+}
+
+impl DumpTemplate {
+    fn write(&self, kind: Kind, value: &Bound<'_, PyAny>, out: &mut String) -> PyResult<()> {
+        match kind {
+            Kind::Bool => out.push_str(if value.is_truthy()? { "true" } else { "false" }),
+            Kind::Int => out.push_str(value.str()?.to_str()?),
+            Kind::String => match value.cast::<PyString>()?.to_str() {
+                // Written as a basic string without escapes, as `dumps` does.
+                Ok(text)
+                    if !text
+                        .chars()
+                        .any(|ch| ch == '"' || ch == '\\' || ch.is_control()) =>
+                {
+                    out.push('"');
+                    out.push_str(text);
+                    out.push('"');
+                }
+                _ => out.push_str(&self.convert(value)?),
+            },
+            Kind::Other => out.push_str(&self.convert(value)?),
+        }
+        Ok(())
+    }
+
+    fn convert(&self, value: &Bound<'_, PyAny>) -> PyResult<String> {
+        let py = value.py();
+        Ok(match self.toml_version {
+            "1.0.0" => crate::v1::edit::to_value(py, value)?.to_string(),
+            _ => crate::v1_1::edit::to_value(py, value)?.to_string(),
+        })
+    }
+}
+
+fn lookup<'py>(root: &Bound<'py, PyAny>, path: &[Part]) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let mut value = root.clone();
+    for part in path {
+        let child = match part {
+            Part::Key(key) => match value.cast::<PyDict>() {
+                Ok(table) => table.get_item(key)?,
+                Err(_) => None,
+            },
+            Part::Index(index) => match value.cast::<PyList>() {
+                Ok(array) => array.get_item(*index).ok(),
+                Err(_) => None,
+            },
+        };
+        match child {
+            Some(child) => value = child,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(value))
+}
+
+fn mismatch(path: &[Part], message: &str) -> PyErr {
+    let mut place = String::new();
+    for part in path {
+        match part {
+            Part::Key(key) if place.is_empty() => place.push_str(key),
+            Part::Key(key) => {
+                place.push('.');
+                place.push_str(key);
+            }
+            Part::Index(index) => {
+                let _ = write!(place, "[{index}]");
+            }
+        }
+    }
+    if place.is_empty() {
+        place.push_str("the document");
+    }
+    PyValueError::new_err(format!("{place} does not match the template: {message}"))
+}
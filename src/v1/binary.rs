@@ -0,0 +1,9 @@
+use toml_edit_v1::{Array, ArrayOfTables, Datetime, DocumentMut, InlineTable, Item, Table, Value};
+use toml_v1::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+use crate::{binary::Node, impl_binary};
+
+impl_binary!();
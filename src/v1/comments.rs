@@ -0,0 +1,8 @@
+use pyo3::types::PyDictMethods;
+use toml_edit_v1::{
+    Decor, DocumentMut, InlineTable, Item, Key, RawString, Table, TableLike, Value,
+};
+
+use crate::impl_comments;
+
+impl_comments!(extract_comments, comment_at, add_comments);
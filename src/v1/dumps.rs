@@ -1,14 +1,15 @@
 use pyo3::types::{
-    PyAnyMethods, PyBoolMethods, PyDateAccess, PyDeltaAccess, PyListMethods, PyStringMethods,
-    PyTimeAccess, PyTupleMethods, PyTzInfoAccess,
+    PyAnyMethods, PyBoolMethods, PyDateAccess, PyDeltaAccess, PyDictMethods, PyFloatMethods,
+    PyListMethods, PyStringMethods, PyTimeAccess, PyTupleMethods,
 };
-use toml_edit_v1::{Array, InlineTable, Item, Offset, Table, Value};
+use toml_edit_v1::{Array, Datetime, InlineTable, Item, Key, Offset, Table, Value};
 
 use crate::{impl_dumps, to_toml_v1, toml_dt_v1};
 
 impl_dumps!(
     validate_inline_paths,
     python_to_toml,
+    dumps_plain,
     to_toml_v1,
     toml_dt_v1
 );
@@ -0,0 +1,5 @@
+use toml_edit_v1::{InlineTable, Key, Table, Value, visit_mut};
+
+use crate::impl_escapes;
+
+impl_escapes!();
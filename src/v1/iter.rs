@@ -0,0 +1,8 @@
+use toml_v1::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+use crate::{impl_table_iter, v1::loads::toml_to_python};
+
+impl_table_iter!(TableIter);
@@ -34,6 +34,15 @@ macro_rules! toml_dt_v1 {
         }
     };
 
+    (Time, $py_time:expr, $nanosecond:expr) => {
+        toml_v1::value::Time {
+            hour: $py_time.get_hour(),
+            minute: $py_time.get_minute(),
+            second: $py_time.get_second(),
+            nanosecond: $py_time.get_microsecond() * 1000 + $nanosecond,
+        }
+    };
+
     (Datetime, $date:expr, $time:expr, $offset:expr) => {
         toml_v1::value::Datetime {
             date: $date,
@@ -1,20 +1,37 @@
 // https://github.com/toml-rs/toml/blob/v0.25.12/crates/toml_edit/src/ser/pretty.rs
 use toml_edit_v1::{Array, DocumentMut, Item, Table, Value, visit_mut};
 
-pub struct Pretty {
+pub struct Pretty<'a> {
     in_value: bool,
     format_tables: bool,
+    // Written once per level of wrapped arrays around an element.
+    indent: &'a str,
+    depth: usize,
 }
 
-impl Pretty {
-    pub fn new(format_tables: bool) -> Self {
+impl<'a> Pretty<'a> {
+    pub fn new(format_tables: bool, indent: &'a str) -> Self {
         Self {
             in_value: false,
             format_tables,
+            indent,
+            depth: 0,
         }
     }
 }
 
+// One element per line, indented one level deeper than the array, with a
+// trailing comma.
+fn one_per_line(node: &mut Array, indent: &str, depth: usize) {
+    let prefix = format!("\n{}", indent.repeat(depth + 1));
+    for item in node.iter_mut() {
+        item.decor_mut().set_prefix(prefix.as_str());
+    }
+
+    node.set_trailing(format!("\n{}", indent.repeat(depth)));
+    node.set_trailing_comma(true);
+}
+
 fn make_item(node: &mut Item) {
     *node = std::mem::take(node)
         .into_table()
@@ -23,7 +40,7 @@ fn make_item(node: &mut Item) {
         .map_or_else(|i| i, Item::ArrayOfTables);
 }
 
-impl visit_mut::VisitMut for Pretty {
+impl visit_mut::VisitMut for Pretty<'_> {
     fn visit_document_mut(&mut self, node: &mut DocumentMut) {
         visit_mut::visit_document_mut(self, node);
     }
@@ -47,18 +64,17 @@ impl visit_mut::VisitMut for Pretty {
     }
 
     fn visit_array_mut(&mut self, node: &mut Array) {
-        visit_mut::visit_array_mut(self, node);
-
         if (0..=1).contains(&node.len()) {
+            visit_mut::visit_array_mut(self, node);
+
             node.set_trailing("");
             node.set_trailing_comma(false);
         } else {
-            for item in node.iter_mut() {
-                item.decor_mut().set_prefix("\n    ");
-            }
+            self.depth += 1;
+            visit_mut::visit_array_mut(self, node);
+            self.depth -= 1;
 
-            node.set_trailing("\n");
-            node.set_trailing_comma(true);
+            one_per_line(node, self.indent, self.depth);
         }
     }
 
@@ -73,3 +89,29 @@ impl visit_mut::VisitMut for Pretty {
         self.in_value = old_in_value;
     }
 }
+
+// `array_style="one_per_line"`: every array that is not empty is wrapped the
+// way `Pretty` wraps the longer ones, so appending an element adds a line
+// instead of rewriting one.
+pub struct OnePerLine<'a> {
+    indent: &'a str,
+    depth: usize,
+}
+
+impl<'a> OnePerLine<'a> {
+    pub const fn new(indent: &'a str) -> Self {
+        Self { indent, depth: 0 }
+    }
+}
+
+impl visit_mut::VisitMut for OnePerLine<'_> {
+    fn visit_array_mut(&mut self, node: &mut Array) {
+        self.depth += 1;
+        visit_mut::visit_array_mut(self, node);
+        self.depth -= 1;
+
+        if !node.is_empty() {
+            one_per_line(node, self.indent, self.depth);
+        }
+    }
+}
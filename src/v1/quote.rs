@@ -0,0 +1,5 @@
+use toml_edit_v1::{InlineTable, Key, Table, visit_mut};
+
+use crate::impl_quote_keys;
+
+impl_quote_keys!();
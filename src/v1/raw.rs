@@ -0,0 +1,5 @@
+use toml_edit_v1::{Document, Item, Key, Table, TableLike, Value};
+
+use crate::impl_raw;
+
+impl_raw!(raw_span, visit, layout);
@@ -0,0 +1,9 @@
+use toml_parser_v1::{
+    Source,
+    decoder::ScalarKind,
+    parser::{Event, EventKind, parse_document},
+};
+
+use crate::impl_tokenize;
+
+impl_tokenize!(tokenize);
@@ -1,5 +1,14 @@
+pub mod binary;
+pub mod comments;
 pub mod dumps;
+pub mod edit;
+pub mod escape;
+pub mod iter;
 pub mod loads;
 pub mod macros;
 pub mod metadata;
 pub mod pretty;
+pub mod pyproject;
+pub mod quote;
+pub mod raw;
+pub mod tokens;
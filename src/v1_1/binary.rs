@@ -0,0 +1,9 @@
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+use toml_edit::{Array, ArrayOfTables, Datetime, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::{binary::Node, impl_binary};
+
+impl_binary!();
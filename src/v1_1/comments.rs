@@ -0,0 +1,6 @@
+use pyo3::types::PyDictMethods;
+use toml_edit::{Decor, DocumentMut, InlineTable, Item, Key, RawString, Table, TableLike, Value};
+
+use crate::impl_comments;
+
+impl_comments!(extract_comments, comment_at, add_comments);
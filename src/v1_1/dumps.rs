@@ -1,9 +1,15 @@
 use pyo3::types::{
-    PyAnyMethods, PyBoolMethods, PyDateAccess, PyDeltaAccess, PyListMethods, PyStringMethods,
-    PyTimeAccess, PyTupleMethods, PyTzInfoAccess,
+    PyAnyMethods, PyBoolMethods, PyDateAccess, PyDeltaAccess, PyDictMethods, PyFloatMethods,
+    PyListMethods, PyStringMethods, PyTimeAccess, PyTupleMethods,
 };
-use toml_edit::{Array, InlineTable, Item, Offset, Table, Value};
+use toml_edit::{Array, Datetime, InlineTable, Item, Key, Offset, Table, Value};
 
 use crate::{impl_dumps, to_toml, toml_dt};
 
-impl_dumps!(validate_inline_paths, python_to_toml, to_toml, toml_dt);
+impl_dumps!(
+    validate_inline_paths,
+    python_to_toml,
+    dumps_plain,
+    to_toml,
+    toml_dt
+);
@@ -0,0 +1,16 @@
+use pyo3::types::{PyAnyMethods, PyDictMethods};
+use toml_edit::{Array, DocumentMut, Item, KeyMut, RawString, Table, TableLike, Value};
+
+use crate::{impl_edit, v1_1::dumps::python_to_toml};
+
+impl_edit!(
+    update_document,
+    array_insert,
+    array_remove,
+    decorate,
+    graft,
+    move_table,
+    trivia,
+    set_trivia,
+    python_to_toml
+);
@@ -0,0 +1,8 @@
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+use crate::{impl_table_iter, v1_1::loads::toml_to_python};
+
+impl_table_iter!(TableIter);
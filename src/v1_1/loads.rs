@@ -3,32 +3,422 @@ use pyo3::{
     IntoPyObjectExt,
     exceptions::PyValueError,
     prelude::*,
-    types::{PyDate, PyDelta, PyDict, PyList, PyTime, PyTzInfo},
+    types::{PyDate, PyDict, PyFloat, PyList, PyTime, PyTzInfo},
+};
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+    value::Offset,
 };
-use toml::{Spanned, de::DeValue, value::Offset};
 
-use crate::{create_py_datetime, error::TomlError, parse_int, toml_rs::TOMLDecodeError};
+use crate::{
+    core::{
+        formatted::wrap,
+        hook::{enter_all, enter_table, finish_table, leave, leave_table, push, push_table},
+        intern::{TimezoneCache, fixed_offset},
+        lossy,
+        metadata::raw_slice,
+        normalize::set_key,
+        options::{Datetimes, LeapSeconds, LoadOptions},
+    },
+    create_py_datetime,
+    error::{duplicate_key, invalid_value, resource_limit},
+    parse_int,
+    toml_rs::TOMLInvalidValueError,
+};
 
 pub fn toml_to_python<'py>(
     py: Python<'py>,
     de_value: &Spanned<DeValue<'_>>,
-    parse_float: &Bound<'py, PyAny>,
+    options: &LoadOptions<'_, 'py>,
+    doc: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    if let Some(max) = options.max_items {
+        count_items(de_value, max, doc)?;
+    }
+    let py_value = select(py, de_value, options, doc)?;
+    if let (Some(key_by), DeValue::Table(table)) = (options.key_by, de_value.as_ref()) {
+        for (path, field) in key_by {
+            key_array(table, &py_value, path, field, doc)?;
+        }
+    }
+    Ok(py_value)
+}
+
+// Counts the keys and array elements under `de_value`, in the order they
+// are written and before anything is converted, failing at the first one
+// past `max`.
+fn count_items(de_value: &Spanned<DeValue<'_>>, max: usize, doc: &str) -> PyResult<()> {
+    let mut count = 0;
+    let mut stack = vec![(None, de_value)];
+    while let Some((span, value)) = stack.pop() {
+        if let Some(span) = span {
+            count += 1;
+            if count > max {
+                return Err(resource_limit(
+                    &format!("document exceeds max_items ({max} keys and array elements)"),
+                    doc,
+                    span,
+                ));
+            }
+        }
+        match value.as_ref() {
+            DeValue::Array(array) => {
+                stack.extend(array.iter().rev().map(|item| (Some(item.span()), item)));
+            }
+            DeValue::Table(table) => {
+                stack.extend(
+                    table
+                        .iter()
+                        .rev()
+                        .map(|(key, value)| (Some(key.span()), value)),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Converts the whole document, or only its `only` paths.
+fn select<'py>(
+    py: Python<'py>,
+    de_value: &Spanned<DeValue<'_>>,
+    options: &LoadOptions<'_, 'py>,
     doc: &str,
 ) -> PyResult<Bound<'py, PyAny>> {
-    to_python(py, de_value, parse_float, doc)
+    let (Some(only), DeValue::Table(table)) = (options.only, de_value.as_ref()) else {
+        return to_python(py, de_value, options, doc, 0);
+    };
+
+    let py_dict = PyDict::new(py);
+    for path in only {
+        let (Some(value), Some((last, parents))) = (lookup(table, path), path.split_last()) else {
+            continue;
+        };
+
+        let mut target = py_dict.clone();
+        for key in parents {
+            target = if let Some(existing) = target.get_item(key)? {
+                existing.cast_into::<PyDict>()?
+            } else {
+                let child = PyDict::new(py);
+                target.set_item(key, &child)?;
+                child
+            };
+        }
+        let value = enter_all(options.value_hook, path, || {
+            enter_table(options.table_hook, path, || {
+                to_python(py, value, options, doc, path.len())
+            })
+        })?;
+        target.set_item(last, value)?;
+    }
+
+    Ok(py_dict.into_any())
 }
 
+// Replaces the array of tables at `path` with a dict of the same tables,
+// keyed by their `field`. Paths missing from the document, or left out by
+// `only`, are skipped.
+fn key_array(
+    table: &DeTable<'_>,
+    py_value: &Bound<'_, PyAny>,
+    path: &[String],
+    field: &str,
+    doc: &str,
+) -> PyResult<()> {
+    let (Some(value), Some((last, parents))) = (lookup(table, path), path.split_last()) else {
+        return Ok(());
+    };
+    let not_tables = |span| {
+        invalid_value(
+            format!("key_by: '{}' is not an array of tables", path.join(".")),
+            doc,
+            span,
+        )
+    };
+    let DeValue::Array(array) = value.as_ref() else {
+        return Err(not_tables(value.span()));
+    };
+
+    let mut parent = py_value.clone();
+    for key in parents {
+        let Some(child) = parent.cast::<PyDict>()?.get_item(key)? else {
+            return Ok(());
+        };
+        parent = child;
+    }
+    let parent = parent.cast_into::<PyDict>()?;
+    let Some(list) = parent.get_item(last)? else {
+        return Ok(());
+    };
+
+    let keyed = PyDict::new(py_value.py());
+    for (item, de_item) in list.try_iter()?.zip(array) {
+        let item = item?;
+        let DeValue::Table(de_table) = de_item.as_ref() else {
+            return Err(not_tables(de_item.span()));
+        };
+        let Some(key) = item.cast::<PyDict>()?.get_item(field)? else {
+            return Err(invalid_value(
+                format!("key_by: table in '{}' has no '{field}' key", path.join(".")),
+                doc,
+                de_item.span(),
+            ));
+        };
+        if keyed.contains(&key)? {
+            let span = de_table
+                .iter()
+                .find(|(name, _)| name.get_ref() == field)
+                .map_or_else(|| de_item.span(), |(_, value)| value.span());
+            return Err(duplicate_key(
+                format!(
+                    "key_by: duplicate '{field}' {} in '{}'",
+                    key.repr()?,
+                    path.join(".")
+                ),
+                doc,
+                span,
+            ));
+        }
+        keyed.set_item(key, item)?;
+    }
+    parent.set_item(last, keyed)
+}
+
+fn lookup<'a, 'i>(table: &'a DeTable<'i>, path: &[String]) -> Option<&'a Spanned<DeValue<'i>>> {
+    let (first, rest) = path.split_first()?;
+    let (_, value) = table
+        .iter()
+        .find(|(key, _)| key.get_ref() == first.as_str())?;
+
+    if rest.is_empty() {
+        Some(value)
+    } else if let DeValue::Table(table) = value.as_ref() {
+        lookup(table, rest)
+    } else {
+        None
+    }
+}
+
+// Converts `de_value`, which sits in `depth` arrays and tables. Arrays and
+// tables are filled from an explicit stack rather than by recursing, so only
+// `max_depth` bounds how deeply a document may nest.
 fn to_python<'py>(
     py: Python<'py>,
     de_value: &Spanned<DeValue<'_>>,
-    parse_float: &Bound<'py, PyAny>,
+    options: &LoadOptions<'_, 'py>,
+    doc: &str,
+    depth: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    let mut stack = Vec::new();
+    let mut step = open(py, de_value, options, doc, depth)?;
+    loop {
+        let mut frame = match step {
+            Step::Open(frame) => frame,
+            Step::Value(value) => {
+                let Some(mut frame) = stack.pop() else {
+                    return Ok(value);
+                };
+                frame.add(value, options, doc)?;
+                frame
+            }
+        };
+        step = match frame.next_child(options)? {
+            Some(child) => {
+                let step = open(py, child, options, doc, depth + stack.len() + 1)?;
+                stack.push(frame);
+                step
+            }
+            None => Step::Value(frame.finish(py, options)?),
+        };
+    }
+}
+
+enum Step<'a, 'i, 'py> {
+    // A finished value.
+    Value(Bound<'py, PyAny>),
+    // An array or table whose values are still to convert.
+    Open(Frame<'a, 'i, 'py>),
+}
+
+enum Frame<'a, 'i, 'py> {
+    Array {
+        items: std::iter::Enumerate<std::slice::Iter<'a, Spanned<DeValue<'i>>>>,
+        // Converting into a presized `Vec` first lets `PyList::new`
+        // allocate the list once instead of growing it per element.
+        list: Vec<Bound<'py, PyAny>>,
+    },
+    Table {
+        entries: <&'a DeTable<'i> as IntoIterator>::IntoIter,
+        dict: Bound<'py, PyDict>,
+        // Where the value being converted goes.
+        key: Option<(&'a str, std::ops::Range<usize>)>,
+    },
+}
+
+impl<'a, 'i, 'py> Frame<'a, 'i, 'py> {
+    // The next value to convert, with its key or index entered in the hooks'
+    // paths until it is added.
+    fn next_child(
+        &mut self,
+        options: &LoadOptions<'_, 'py>,
+    ) -> PyResult<Option<&'a Spanned<DeValue<'i>>>> {
+        match self {
+            Self::Array { items, .. } => {
+                let Some((index, item)) = items.next() else {
+                    return Ok(None);
+                };
+                push(options.value_hook, index)?;
+                Ok(Some(item))
+            }
+            Self::Table { entries, key, .. } => {
+                let Some((name, value)) = entries.next() else {
+                    return Ok(None);
+                };
+                let text: &'a str = name.get_ref();
+                push(options.value_hook, text)?;
+                push_table(options.table_hook, text);
+                *key = Some((text, name.span()));
+                Ok(Some(value))
+            }
+        }
+    }
+
+    fn add(
+        &mut self,
+        value: Bound<'py, PyAny>,
+        options: &LoadOptions<'_, 'py>,
+        doc: &str,
+    ) -> PyResult<()> {
+        leave(options.value_hook);
+        match self {
+            Self::Array { list, .. } => list.push(value),
+            Self::Table { dict, key, .. } => {
+                leave_table(options.table_hook);
+                if let Some((name, span)) = key.take() {
+                    set_key(dict, name, value, options.normalize_keys, doc, span)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(
+        self,
+        py: Python<'py>,
+        options: &LoadOptions<'_, 'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Array { list, .. } => Ok(PyList::new(py, list)?.into_any()),
+            Self::Table { dict, .. } => finish_table(options.table_hook, dict),
+        }
+    }
+}
+
+// Converts a scalar or empty container right away, or opens a frame for the
+// values of any other array or table.
+fn open<'a, 'i, 'py>(
+    py: Python<'py>,
+    de_value: &'a Spanned<DeValue<'i>>,
+    options: &LoadOptions<'_, 'py>,
+    doc: &str,
+    depth: usize,
+) -> PyResult<Step<'a, 'i, 'py>> {
+    if let Some(max) = options.max_depth
+        && depth > max
+        && matches!(de_value.as_ref(), DeValue::Array(_) | DeValue::Table(_))
+    {
+        return Err(resource_limit(
+            &format!("document nests deeper than max_depth ({max})"),
+            doc,
+            de_value.span(),
+        ));
+    }
+
+    match de_value.as_ref() {
+        DeValue::Array(array) => {
+            if array.is_empty() {
+                return Ok(Step::Value(PyList::empty(py).into_any()));
+            }
+
+            Ok(Step::Open(Frame::Array {
+                items: array.iter().enumerate(),
+                list: Vec::with_capacity(array.len()),
+            }))
+        }
+        DeValue::Table(table) => {
+            if table.is_empty() {
+                return finish_table(options.table_hook, PyDict::new(py)).map(Step::Value);
+            }
+
+            Ok(Step::Open(Frame::Table {
+                entries: table.iter(),
+                dict: PyDict::new(py),
+                key: None,
+            }))
+        }
+        _ => scalar_to_python(py, de_value, options, doc).map(Step::Value),
+    }
+}
+
+fn scalar_to_python<'py>(
+    py: Python<'py>,
+    de_value: &Spanned<DeValue<'_>>,
+    options: &LoadOptions<'_, 'py>,
     doc: &str,
 ) -> PyResult<Bound<'py, PyAny>> {
     let value = de_value.as_ref();
     let span = de_value.span();
 
+    if let Some(locations) = options.locations {
+        let plain = LoadOptions {
+            locations: None,
+            ..*options
+        };
+        return locations.wrap(scalar_to_python(py, de_value, &plain, doc)?, span.start);
+    }
+
+    if let Some(hook) = options.value_hook {
+        let plain = LoadOptions {
+            value_hook: None,
+            ..*options
+        };
+        return hook.call(scalar_to_python(py, de_value, &plain, doc)?);
+    }
+
+    if options.preserve_format {
+        let plain = LoadOptions {
+            preserve_format: false,
+            ..*options
+        };
+        let py_value = scalar_to_python(py, de_value, &plain, doc)?;
+        return wrap(py, &py_value, raw_slice(doc, &span));
+    }
+
     match value {
-        DeValue::String(str) => str.into_bound_py_any(py),
+        DeValue::String(str) => {
+            // The byte length bounds the character count, which is only
+            // counted for strings that might be over the limit.
+            if let Some(max) = options.max_string_length
+                && str.len() > max
+            {
+                let length = str.chars().count();
+                if length > max {
+                    return Err(resource_limit(
+                        &format!("string of {length} characters exceeds max_string_length ({max})"),
+                        doc,
+                        span,
+                    ));
+                }
+            }
+
+            match options.strings {
+                Some(strings) => Ok(strings.get(py, str).into_any()),
+                None => str.into_bound_py_any(py),
+            }
+        }
         DeValue::Integer(int) => {
             let bytes = int.as_str().as_bytes();
             let radix = int.radix();
@@ -36,33 +426,40 @@ fn to_python<'py>(
             let parse_options = lexical_core::ParseIntegerOptions::new();
 
             if let Ok(int_64) = parse_int!(i64, bytes, &parse_options, radix) {
-                return int_64.into_bound_py_any(py);
+                return options.make_int(int_64);
             }
 
             if let Some(big_int) = BigInt::parse_bytes(bytes, radix) {
-                return big_int.into_bound_py_any(py);
+                return options.make_int(big_int);
             }
 
-            let error_start = span.start;
-            let mut err = TomlError::custom(
+            Err(invalid_value(
                 format!(
                     "invalid integer '{}'",
                     &doc[span.start..span.end.min(doc.len())]
                 ),
-                Some(span),
-            );
-            err.set_input(Some(doc));
-
-            Err(TOMLDecodeError::new_err((
-                err.to_string(),
-                doc.to_string(),
-                error_start,
-            )))
+                doc,
+                span,
+            ))
         }
         DeValue::Float(float) => {
             let float_str = float.as_str();
 
-            let py_call = parse_float.call1((float_str,))?;
+            if !options.allow_nan && float_str.contains(['n', 'i']) {
+                return Err(invalid_value(
+                    format!(
+                        "non-finite float '{}' is not allowed",
+                        raw_slice(doc, &span)
+                    ),
+                    doc,
+                    span,
+                ));
+            }
+
+            // Like tomllib, `parse_float` sees the float exactly as written,
+            // underscores included, rather than the decoded digits.
+            let lexeme = doc.get(span.clone()).unwrap_or(float_str);
+            let py_call = options.parse_float.call1((lexeme,))?;
 
             // https://github.com/hukkin/tomli/blob/2.4.1/src/tomli/_parser.py#L789
             if py_call.is_instance_of::<PyDict>() || py_call.is_instance_of::<PyList>() {
@@ -72,72 +469,108 @@ fn to_python<'py>(
                 ));
             }
 
+            if py_call.is_exact_instance_of::<PyFloat>()
+                && !lossy::float_is_exact(float_str, || Ok(py_call.repr()?.to_string()))?
+            {
+                lossy::report(
+                    py,
+                    options.strict,
+                    format!("float '{float_str}' cannot be represented exactly"),
+                    |msg| TOMLInvalidValueError::new_err((msg, doc.to_string(), span.start)),
+                )?;
+            }
+
             Ok(py_call)
         }
         DeValue::Boolean(bool) => bool.into_bound_py_any(py),
-        DeValue::Datetime(datetime) => match (datetime.date, datetime.time, datetime.offset) {
-            (Some(date), Some(time), Some(offset)) => {
-                let py_tzinfo = create_timezone_from_offset(py, offset)?;
-                Ok(create_py_datetime!(py, date, time, Some(&py_tzinfo))?.into_any())
-            }
-            (Some(date), Some(time), None) => {
-                Ok(create_py_datetime!(py, date, time, None)?.into_any())
+        DeValue::Datetime(datetime) => {
+            if options.datetimes == Datetimes::IsoStrings {
+                // Seconds are written out even where TOML 1.1.0 lets the
+                // document leave them off, as RFC 3339 requires them.
+                let mut datetime = *datetime;
+                if let Some(time) = datetime.time.as_mut() {
+                    time.second.get_or_insert(0);
+                }
+                return datetime.to_string().into_bound_py_any(py);
             }
-            (Some(date), None, None) => {
-                let py_date = PyDate::new(py, i32::from(date.year), date.month, date.day)?;
-                Ok(py_date.into_any())
-            }
-            (None, Some(time), None) => {
-                let py_time = PyTime::new(
+
+            if let Some(time) = datetime.time
+                && time.nanosecond.unwrap_or(0) % 1000 != 0
+            {
+                lossy::report(
                     py,
-                    time.hour,
-                    time.minute,
-                    time.second.unwrap_or(0),
-                    time.nanosecond.unwrap_or(0) / 1000,
-                    None,
+                    options.strict,
+                    format!(
+                        "nanoseconds of '{}' truncated to microseconds",
+                        raw_slice(doc, &span)
+                    ),
+                    |msg| TOMLInvalidValueError::new_err((msg, doc.to_string(), span.start)),
                 )?;
-
-                Ok(py_time.into_any())
-            }
-            _ => unreachable!(),
-        },
-        DeValue::Array(array) => {
-            if array.is_empty() {
-                return Ok(PyList::empty(py).into_any());
             }
 
-            let py_list = PyList::empty(py);
-            for item in array {
-                py_list.append(to_python(py, item, parse_float, doc)?)?;
-            }
-            Ok(py_list.into_any())
-        }
-        DeValue::Table(table) => {
-            if table.is_empty() {
-                return Ok(PyDict::new(py).into_any());
+            let mut time = datetime.time;
+            if let Some(time) = time.as_mut()
+                && time.second == Some(60)
+            {
+                match options.leap_seconds {
+                    LeapSeconds::Error => {
+                        return Err(invalid_value(
+                            format!(
+                                "leap second in '{}' cannot be represented in Python",
+                                raw_slice(doc, &span)
+                            ),
+                            doc,
+                            span,
+                        ));
+                    }
+                    LeapSeconds::Clamp => time.second = Some(59),
+                }
             }
 
-            let py_dict = PyDict::new(py);
-            for (key, value) in table {
-                py_dict.set_item(key.as_ref(), to_python(py, value, parse_float, doc)?)?;
+            match (datetime.date, time, datetime.offset) {
+                (Some(date), Some(time), Some(offset)) => {
+                    let py_tzinfo = create_timezone_from_offset(py, offset, options.timezones)?;
+                    Ok(create_py_datetime!(py, date, time, Some(&py_tzinfo))?.into_any())
+                }
+                (Some(date), Some(time), None) => {
+                    Ok(create_py_datetime!(py, date, time, None)?.into_any())
+                }
+                (Some(date), None, None) => {
+                    let py_date = PyDate::new(py, i32::from(date.year), date.month, date.day)?;
+                    Ok(py_date.into_any())
+                }
+                (None, Some(time), None) => {
+                    let py_time = PyTime::new(
+                        py,
+                        time.hour,
+                        time.minute,
+                        time.second.unwrap_or(0),
+                        time.nanosecond.unwrap_or(0) / 1000,
+                        None,
+                    )?;
+
+                    Ok(py_time.into_any())
+                }
+                _ => unreachable!(),
             }
-            Ok(py_dict.into_any())
+        }
+        DeValue::Array(_) | DeValue::Table(_) => {
+            unreachable!("arrays and tables are converted by `to_python`")
         }
     }
 }
 
 #[inline]
-pub fn create_timezone_from_offset(py: Python, offset: Offset) -> PyResult<Bound<PyTzInfo>> {
-    const SECS_IN_DAY: i32 = 86_400;
-
+pub fn create_timezone_from_offset<'py>(
+    py: Python<'py>,
+    offset: Offset,
+    timezones: Option<&TimezoneCache>,
+) -> PyResult<Bound<'py, PyTzInfo>> {
     match offset {
         Offset::Z => PyTzInfo::utc(py).map(Borrowed::to_owned),
-        Offset::Custom { minutes } => {
-            let seconds = i32::from(minutes) * 60;
-            let days = seconds.div_euclid(SECS_IN_DAY);
-            let seconds = seconds.rem_euclid(SECS_IN_DAY);
-            let py_delta = PyDelta::new(py, days, seconds, 0, false)?;
-            PyTzInfo::fixed_offset(py, py_delta)
-        }
+        Offset::Custom { minutes } => match timezones {
+            Some(timezones) => timezones.get(py, minutes),
+            None => fixed_offset(py, minutes),
+        },
     }
 }
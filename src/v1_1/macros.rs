@@ -34,6 +34,15 @@ macro_rules! toml_dt {
         }
     };
 
+    (Time, $py_time:expr, $nanosecond:expr) => {
+        toml::value::Time {
+            hour: $py_time.get_hour(),
+            minute: $py_time.get_minute(),
+            second: Some($py_time.get_second()),
+            nanosecond: Some($py_time.get_microsecond() * 1000 + $nanosecond),
+        }
+    };
+
     (Datetime, $date:expr, $time:expr, $offset:expr) => {
         toml::value::Datetime {
             date: $date,
@@ -22,9 +22,9 @@ use crate::{
         empty_value_loc, raw_slice, set_key_fields, set_value_metadata_fields, table_needs_wrapper,
     },
     create_py_datetime,
-    error::TomlError,
+    error::invalid_value,
     parse_int,
-    toml_rs::TOMLDecodeError,
+    toml_rs::TOMLInvalidValueError,
     v1_1::loads::create_timezone_from_offset,
 };
 
@@ -78,25 +78,19 @@ fn scalar_to_py_obj<'py>(
                 return big_int.into_py_any(py);
             }
 
-            let error_start = raw_span.start;
-            let mut err = TomlError::custom(
+            Err(invalid_value(
                 format!(
                     "invalid integer '{}'",
                     &doc[raw_span.start..raw_span.end.min(doc.len())]
                 ),
-                Some(raw_span),
-            );
-            err.set_input(Some(doc));
-            Err(TOMLDecodeError::new_err((
-                err.to_string(),
-                doc.to_string(),
-                error_start,
-            )))
+                doc,
+                raw_span,
+            ))
         }
         DeValue::Float(float) => {
             let float_bytes = float.as_str().as_bytes();
             let parsed: f64 = lexical_core::parse(float_bytes).map_err(|err| {
-                TOMLDecodeError::new_err((
+                TOMLInvalidValueError::new_err((
                     format!("invalid float '{}': {err}", float.as_str()),
                     doc.to_string(),
                     raw_span.start,
@@ -107,7 +101,7 @@ fn scalar_to_py_obj<'py>(
         DeValue::Datetime(dt) => {
             let bound_any: Bound<'py, PyAny> = match (dt.date, dt.time, dt.offset) {
                 (Some(date), Some(time), Some(offset)) => {
-                    let py_tzinfo = create_timezone_from_offset(py, offset)?;
+                    let py_tzinfo = create_timezone_from_offset(py, offset, None)?;
                     let tzinfo = Some(&py_tzinfo);
                     create_py_datetime!(py, date, time, tzinfo)?.into_any()
                 }
@@ -272,26 +266,19 @@ pub fn to_python<'py>(
                 return big_int.into_bound_py_any(py);
             }
 
-            let error_start = span.start;
-            let mut err = TomlError::custom(
+            Err(invalid_value(
                 format!(
                     "invalid integer '{}'",
                     &doc[span.start..span.end.min(doc.len())]
                 ),
-                Some(span),
-            );
-            err.set_input(Some(doc));
-
-            Err(TOMLDecodeError::new_err((
-                err.to_string(),
-                doc.to_string(),
-                error_start,
-            )))
+                doc,
+                span,
+            ))
         }
         DeValue::Float(float) => {
             let float_bytes = float.as_str().as_bytes();
             let parsed: f64 = lexical_core::parse(float_bytes).map_err(|err| {
-                TOMLDecodeError::new_err((
+                TOMLInvalidValueError::new_err((
                     format!("invalid float '{}': {err}", float.as_str()),
                     doc.to_string(),
                     span.start,
@@ -301,7 +288,7 @@ pub fn to_python<'py>(
         }
         DeValue::Datetime(dt) => match (dt.date, dt.time, dt.offset) {
             (Some(date), Some(time), Some(offset)) => {
-                let py_tzinfo = create_timezone_from_offset(py, offset)?;
+                let py_tzinfo = create_timezone_from_offset(py, offset, None)?;
                 let tzinfo = Some(&py_tzinfo);
                 Ok(create_py_datetime!(py, date, time, tzinfo)?.into_any())
             }
@@ -327,11 +314,11 @@ pub fn to_python<'py>(
                 return Ok(PyList::empty(py).into_any());
             }
 
-            let py_list = PyList::empty(py);
+            let mut items = Vec::with_capacity(array.len());
             for item in array {
-                py_list.append(to_python(py, item.get_ref(), item.span(), doc)?)?;
+                items.push(to_python(py, item.get_ref(), item.span(), doc)?);
             }
-            Ok(py_list.into_any())
+            Ok(PyList::new(py, items)?.into_any())
         }
         DeValue::Table(table) => {
             if table.is_empty() {
@@ -0,0 +1,8 @@
+use toml_edit::{Array, DocumentMut, Item, TableLike, Value};
+
+use crate::{
+    impl_pyproject,
+    v1_1::edit::{insert_value, lookup_mut, parse_document, remove_value, replace_value, table_at},
+};
+
+impl_pyproject!(add_dependency, remove_dependency);
@@ -0,0 +1,192 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError, Weak,
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+use pyo3::{exceptions::PyOSError, prelude::*};
+
+use crate::core::options::LoadOptions;
+
+// Editors usually save by writing a new file and renaming it over the old
+// one, so the parent directory is watched and events are matched by name.
+#[pyclass]
+pub struct Watcher {
+    state: Arc<State>,
+}
+
+type State = Mutex<Option<(RecommendedWatcher, JoinHandle<()>)>>;
+
+// Every watcher started, so the ones still running are stopped at exit,
+// before the interpreter is finalized under their threads.
+static WATCHERS: Mutex<Vec<Weak<State>>> = Mutex::new(Vec::new());
+
+struct Reload {
+    path: PathBuf,
+    callback: Py<PyAny>,
+    parse_float: Py<PyAny>,
+    debounce: Duration,
+    toml_version: &'static str,
+}
+
+impl Watcher {
+    pub fn start(
+        path: &Path,
+        callback: Py<PyAny>,
+        parse_float: Py<PyAny>,
+        debounce: Duration,
+        toml_version: &'static str,
+    ) -> PyResult<Self> {
+        let path = std::path::absolute(path)?;
+        let dir = path.parent().unwrap_or(&path).to_path_buf();
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(|err| watch_error(&err))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|err| watch_error(&err))?;
+
+        let reload = Reload {
+            path,
+            callback,
+            parse_float,
+            debounce,
+            toml_version,
+        };
+        let handle = thread::spawn(move || reload.run(&rx));
+
+        let state = Arc::new(Mutex::new(Some((watcher, handle))));
+        let mut watchers = WATCHERS.lock().unwrap_or_else(PoisonError::into_inner);
+        watchers.retain(|state| state.strong_count() > 0);
+        watchers.push(Arc::downgrade(&state));
+        Ok(Self { state })
+    }
+}
+
+// Stops the watchers still running, from `atexit`.
+pub fn stop_all(py: Python<'_>) {
+    let states: Vec<_> = WATCHERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .drain(..)
+        .filter_map(|state| state.upgrade())
+        .collect();
+    for state in states {
+        halt(py, &state);
+    }
+}
+
+fn halt(py: Python<'_>, state: &State) {
+    let state = state.lock().unwrap_or_else(PoisonError::into_inner).take();
+    let Some((watcher, handle)) = state else {
+        return;
+    };
+
+    // Dropping the watcher closes the channel, which ends the thread.
+    drop(watcher);
+    // `stop` may be called from the callback itself, on that very thread.
+    if handle.thread().id() != thread::current().id() {
+        py.detach(|| {
+            let _ = handle.join();
+        });
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // Past the start of finalization the thread cannot attach either, so
+        // closing the channel is enough to end it.
+        Python::try_attach(|py| halt(py, &self.state));
+    }
+}
+
+#[pymethods]
+impl Watcher {
+    fn stop(&self, py: Python<'_>) {
+        halt(py, &self.state);
+    }
+
+    #[getter]
+    fn running(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) {
+        halt(py, &self.state);
+    }
+}
+
+impl Reload {
+    fn run(&self, events: &Receiver<notify::Result<Event>>) {
+        while let Ok(event) = events.recv() {
+            if !self.is_relevant(event) {
+                continue;
+            }
+
+            // Wait until the directory has been quiet for `debounce`, so a
+            // save that fires several events is read once and complete.
+            loop {
+                match events.recv_timeout(self.debounce) {
+                    Ok(_) => {}
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let text = std::fs::read_to_string(&self.path);
+            // Once the interpreter is finalizing there is no one to notify.
+            if Python::try_attach(|py| self.notify(py, text)).is_none() {
+                return;
+            }
+        }
+    }
+
+    fn is_relevant(&self, event: notify::Result<Event>) -> bool {
+        event.is_ok_and(|event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == self.path.file_name())
+        })
+    }
+
+    fn notify(&self, py: Python<'_>, text: std::io::Result<String>) {
+        let parse_float = self.parse_float.bind(py);
+        let options = LoadOptions::plain(parse_float);
+
+        let data = text.map_err(PyErr::from).and_then(|text| {
+            crate::toml_rs::loads_with_options(py, &text, self.toml_version, &options)
+        });
+        let result = match data {
+            Ok(data) => self.callback.call1(py, (data, py.None())),
+            Err(err) => self.callback.call1(py, (py.None(), err.into_value(py))),
+        };
+
+        if let Err(err) = result {
+            err.write_unraisable(py, Some(self.callback.bind(py)));
+        }
+    }
+}
+
+fn watch_error(err: &notify::Error) -> PyErr {
+    PyOSError::new_err(err.to_string())
+}
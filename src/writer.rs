@@ -0,0 +1,227 @@
+use pyo3::{exceptions::PyValueError, intern, prelude::*, types::PyDict};
+use rustc_hash::FxHashMap;
+use toml_edit::Key;
+
+use crate::document::parse_key_path;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Value,
+    // A table that only exists because a header below it was written.
+    Implicit,
+    Table,
+    ArrayOfTables,
+}
+
+// Writes a document one header and key at a time, so it never has to be
+// held in memory. Every key path written is remembered to refuse what would
+// make the document invalid, like a key or header written twice. The
+// sub-tables of an array of tables are forgotten when its next entry starts.
+#[pyclass]
+pub struct TomlWriter {
+    file: Py<PyAny>,
+    // Whether the writer opened `file` from a path, and so closes it.
+    owned: bool,
+    closed: bool,
+    toml_version: &'static str,
+    written: FxHashMap<Vec<String>, Kind>,
+    // Key path of the table the next keys go into.
+    current: Vec<String>,
+    empty: bool,
+}
+
+#[pymethods]
+impl TomlWriter {
+    #[new]
+    #[pyo3(signature = (file, /, *, toml_version = "1.0.0"))]
+    fn new(py: Python<'_>, file: &Bound<'_, PyAny>, toml_version: &str) -> PyResult<Self> {
+        let toml_version = match toml_version {
+            "1.0.0" => "1.0.0",
+            "1.1.0" => "1.1.0",
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported TOML version: {toml_version}",
+                )));
+            }
+        };
+
+        let owned = !file.hasattr(intern!(py, "write"))?;
+        let file = if owned {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item(intern!(py, "encoding"), intern!(py, "utf-8"))?;
+            py.import(intern!(py, "builtins"))?
+                .getattr(intern!(py, "open"))?
+                .call((file, intern!(py, "w")), Some(&kwargs))?
+        } else {
+            file.clone()
+        };
+
+        Ok(Self {
+            file: file.unbind(),
+            owned,
+            closed: false,
+            toml_version,
+            written: FxHashMap::default(),
+            current: Vec::new(),
+            empty: true,
+        })
+    }
+
+    // Writes `name = value` into the table of the last header.
+    fn key(&mut self, py: Python<'_>, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.check_open()?;
+        let mut keys = self.current.clone();
+        keys.push(name.to_owned());
+        if self.written.contains_key(&keys) {
+            return Err(PyValueError::new_err(format!(
+                "{} is already written",
+                display_path(&keys)
+            )));
+        }
+
+        let value = match self.toml_version {
+            "1.0.0" => crate::v1::edit::to_value(py, value)?.to_string(),
+            _ => crate::v1_1::edit::to_value(py, value)?.to_string(),
+        };
+        self.write(
+            py,
+            &format!("{} = {value}\n", Key::new(name).display_repr()),
+        )?;
+        self.written.insert(keys, Kind::Value);
+        Ok(())
+    }
+
+    // Writes a `[path]` header; the keys written next go into that table.
+    fn table(&mut self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.check_open()?;
+        let keys = self.header_path(path)?;
+        match self.written.get(&keys) {
+            None | Some(Kind::Implicit) => {}
+            Some(kind) => return Err(already_written(&keys, *kind)),
+        }
+
+        self.header(py, keys, Kind::Table)
+    }
+
+    // Writes a `[[path]]` header, starting the next table in that array.
+    fn array_of_tables(&mut self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.check_open()?;
+        let keys = self.header_path(path)?;
+        match self.written.get(&keys) {
+            None | Some(Kind::ArrayOfTables) => {}
+            Some(kind) => return Err(already_written(&keys, *kind)),
+        }
+
+        self.written
+            .retain(|written, _| written.len() <= keys.len() || !written.starts_with(&keys));
+        self.header(py, keys, Kind::ArrayOfTables)
+    }
+
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        if self.owned {
+            self.file.bind(py).call_method0(intern!(py, "close"))?;
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        self.close(py)
+    }
+}
+
+impl TomlWriter {
+    fn check_open(&self) -> PyResult<()> {
+        if self.closed {
+            return Err(PyValueError::new_err("TomlWriter is closed"));
+        }
+        Ok(())
+    }
+
+    // Parses a header path, refusing one that goes through a value.
+    fn header_path(&self, path: &str) -> PyResult<Vec<String>> {
+        let keys = parse_key_path(path)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid key path: {path:?}")))?;
+
+        for depth in 1..keys.len() {
+            let parent = &keys[..depth];
+            if self.written.get(parent) == Some(&Kind::Value) {
+                return Err(PyValueError::new_err(format!(
+                    "Cannot write {}: {} is a value",
+                    display_path(&keys),
+                    display_path(parent)
+                )));
+            }
+        }
+        Ok(keys)
+    }
+
+    // Writes the header of a table or array of tables and makes it the
+    // current table. Headers after the first are set off by a blank line.
+    fn header(&mut self, py: Python<'_>, keys: Vec<String>, kind: Kind) -> PyResult<()> {
+        let separator = if self.empty { "" } else { "\n" };
+        let (open, close) = if kind == Kind::ArrayOfTables {
+            ("[[", "]]")
+        } else {
+            ("[", "]")
+        };
+        self.write(
+            py,
+            &format!("{separator}{open}{}{close}\n", display_path(&keys)),
+        )?;
+
+        for depth in 1..keys.len() {
+            self.written
+                .entry(keys[..depth].to_vec())
+                .or_insert(Kind::Implicit);
+        }
+        self.written.insert(keys.clone(), kind);
+        self.current = keys;
+        Ok(())
+    }
+
+    fn write(&mut self, py: Python<'_>, text: &str) -> PyResult<()> {
+        self.file
+            .bind(py)
+            .call_method1(intern!(py, "write"), (text,))?;
+        self.empty = false;
+        Ok(())
+    }
+}
+
+fn display_path(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| Key::new(key.as_str()).display_repr().into_owned())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn already_written(keys: &[String], kind: Kind) -> PyErr {
+    let what = match kind {
+        Kind::Value => "a value",
+        Kind::Implicit | Kind::Table => "a table",
+        Kind::ArrayOfTables => "an array of tables",
+    };
+    PyValueError::new_err(format!(
+        "{} is already written as {what}",
+        display_path(keys)
+    ))
+}
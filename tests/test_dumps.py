@@ -1,8 +1,12 @@
+import math
+import random
 import re
+import sys
 from collections import OrderedDict
 from collections.abc import Mapping
-from datetime import date, datetime, time, timedelta, timezone
+from datetime import date, datetime, time, timedelta, timezone, tzinfo
 from decimal import Decimal
+from pathlib import Path
 from types import MappingProxyType
 from typing import Any
 
@@ -50,6 +54,13 @@ class MyMap(Mapping[str, Any]):
             r"Cannot serialize {1, 2, 3} \(<class 'set'>\)",
             {},
         ),
+        (
+            {"set": set(range(100))},
+            re.escape(
+                f"Cannot serialize {set(range(100))!r:.80}... (<class 'set'>)",
+            ),
+            {},
+        ),
         (
             {"valid": {"invalid": object()}},
             r"Cannot serialize <object object at 0x.*> \(<class 'object'>\)",
@@ -179,6 +190,30 @@ def test_dumps_pretty() -> None:
     )
 
 
+@pytest.mark.parametrize("pretty", [False, True])
+def test_dumps_scalars_before_tables(
+    toml_version: toml_rs._lib.TomlVersion,
+    *,
+    pretty: bool,
+) -> None:
+    # Key-values that follow a table in the dict still belong to the table
+    # they were in, so they are written before its first sub-table.
+    obj = {
+        "server": {"tls": {"cert": "a.pem"}, "port": 443},
+        "jobs": [{"name": "a"}],
+        "name": "demo",
+    }
+    text = toml_rs.dumps(obj, toml_version=toml_version, pretty=pretty)
+    assert text.index("name = ") < text.index("[server]") < text.index("port = ")
+    assert text.index("port = ") < text.index("[server.tls]")
+    if pretty:
+        assert text.index("[server.tls]") < text.index("[[jobs]]")
+    else:
+        # Without `pretty` a list of tables is an inline key-value.
+        assert text.index('jobs = [{ name = "a" }]') < text.index("[server]")
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+
 def test_dumps_pretty_with_inline_tables() -> None:
     obj = {
         "array": ["item 1", "item 2", "item 3"],
@@ -382,3 +417,872 @@ def test_dumps_direct(
         toml_version: toml_rs._lib.TomlVersion,
 ) -> None:
     assert toml_rs.dumps(value, toml_version=toml_version) == expected[toml_version]
+
+
+def test_dumps_numpy(toml_version: toml_rs._lib.TomlVersion) -> None:
+    np = pytest.importorskip("numpy")
+
+    obj = {
+        "day": np.datetime64("1979-05-27"),
+        "stamp": np.datetime64("1979-05-27T07:32:00.123456789"),
+        "ints": np.array([1, 2, 3]),
+        "floats": np.array([0.5, 1.5]),
+    }
+    with pytest.warns(UserWarning, match="truncated to datetime64"):
+        dumped = toml_rs.dumps(obj, toml_version=toml_version)
+    assert dumped == toml_rs.dumps(
+        {
+            "day": date(1979, 5, 27),
+            "stamp": datetime(1979, 5, 27, 7, 32, 0, 123456),
+            "ints": [1, 2, 3],
+            "floats": [0.5, 1.5],
+        },
+        toml_version=toml_version,
+    )
+
+    with pytest.raises(toml_rs.TOMLEncodeError, match="NaT"):
+        toml_rs.dumps({"x": np.datetime64("NaT")}, toml_version=toml_version)
+
+    with pytest.raises(toml_rs.TOMLEncodeError, match="Cannot serialize"):
+        toml_rs.dumps({"x": np.zeros((2, 2))}, toml_version=toml_version)
+
+
+def test_dumps_pandas(toml_version: toml_rs._lib.TomlVersion) -> None:
+    pd = pytest.importorskip("pandas")
+
+    obj = {
+        "naive": pd.Timestamp("1979-05-27T07:32:00.123456789"),
+        "aware": pd.Timestamp("1979-05-27T07:32:00", tz="Europe/Berlin"),
+    }
+    assert toml_rs.dumps(obj, toml_version=toml_version) == (
+        "naive = 1979-05-27T07:32:00.123456789\n"
+        "aware = 1979-05-27T07:32:00+01:00\n"
+    )
+
+    with pytest.warns(UserWarning, match="'naive' truncated to microseconds"):
+        truncated = toml_rs.dumps(
+            obj, nanoseconds="truncate", toml_version=toml_version
+        )
+    assert truncated.startswith("naive = 1979-05-27T07:32:00.123456\n")
+    with pytest.raises(toml_rs.TOMLEncodeError, match="truncated to microseconds"):
+        toml_rs.dumps(
+            obj, nanoseconds="truncate", strict=True, toml_version=toml_version
+        )
+    with pytest.raises(ValueError, match="Unsupported nanoseconds policy"):
+        toml_rs.dumps(
+            obj,
+            nanoseconds="round",  # type: ignore[arg-type]
+            toml_version=toml_version,
+        )
+
+    with pytest.raises(
+        toml_rs.TOMLEncodeError,
+        match=r"Cannot serialize pandas\.NaT at 'server\.started' to TOML",
+    ):
+        toml_rs.dumps({"server": {"started": pd.NaT}}, toml_version=toml_version)
+
+
+def test_dumps_duck_typed_offsets() -> None:
+    class Offset(timedelta):
+        pass
+
+    class Moment(datetime):
+        def utcoffset(self) -> timedelta:
+            return Offset(hours=5, minutes=30)
+
+    assert (
+        toml_rs.dumps({"x": Moment(1979, 5, 27, 7, 32)})
+        == "x = 1979-05-27T07:32:00+05:30\n"
+    )
+
+
+def test_dumps_offset_out_of_range() -> None:
+    class Moment(datetime):
+        def utcoffset(self) -> timedelta:
+            return timedelta(days=30)
+
+    obj = {"event": {"when": Moment(1979, 5, 27, 7, 32)}}
+    with pytest.raises(toml_rs.TOMLEncodeError, match="43200 minutes at 'event.when'"):
+        toml_rs.dumps(obj)
+
+
+def test_dumps_failing_utcoffset() -> None:
+    class Broken(tzinfo):
+        def utcoffset(self, dt: datetime | None) -> timedelta:
+            msg = "no offset"
+            raise RuntimeError(msg)
+
+    obj = {"event": {"when": datetime(1979, 5, 27, 7, 32, tzinfo=Broken())}}
+    with pytest.raises(toml_rs.TOMLEncodeError, match="at 'event.when'") as exc_info:
+        toml_rs.dumps(obj)
+    assert isinstance(exc_info.value.__cause__, RuntimeError)
+
+
+def test_dumps_datetime_proxy() -> None:
+    class Proxy:
+        def __init__(self, target: object) -> None:
+            self._target = target
+
+        @property  # type: ignore[misc]
+        def __class__(self) -> type:  # type: ignore[override]
+            return type(self._target)
+
+        def __getattr__(self, name: str) -> Any:
+            return getattr(self._target, name)
+
+    when = datetime(1979, 5, 27, 7, 32, tzinfo=timezone.utc)
+    obj = {"when": Proxy(when), "day": Proxy(when.date()), "at": Proxy(time(7, 32))}
+    assert isinstance(obj["when"], datetime)
+    assert toml_rs.dumps(obj) == (
+        "when = 1979-05-27T07:32:00+00:00\n"
+        "day = 1979-05-27\n"
+        "at = 07:32:00\n"
+    )
+
+
+def test_dumps_arrow() -> None:
+    arrow = pytest.importorskip("arrow")
+
+    value = arrow.get(datetime(1979, 5, 27, 7, 32, tzinfo=timezone.utc))
+    assert toml_rs.dumps({"x": value}) == "x = 1979-05-27T07:32:00+00:00\n"
+
+
+def test_dumps_attrs() -> None:
+    attrs = pytest.importorskip("attrs")
+
+    @attrs.define
+    class Server:
+        host: str
+        port: int = attrs.field(metadata={"toml": {"name": "listen-port"}})
+        secret: str = attrs.field(default="", metadata={"toml": {"skip": True}})
+
+    @attrs.define
+    class Config:
+        name: str
+        server: Server
+
+    obj = Config(name="app", server=Server(host="localhost", port=8080, secret="x"))
+    assert toml_rs.dumps(obj) == (
+        'name = "app"\n'
+        "\n"
+        "[server]\n"
+        'host = "localhost"\n'
+        "listen-port = 8080\n"
+    )
+
+
+def test_dumps_pydantic() -> None:
+    pydantic = pytest.importorskip("pydantic")
+
+    class Server(pydantic.BaseModel):
+        host: str
+        port: int = pydantic.Field(alias="listen-port")
+        secret: str = pydantic.Field(default="", exclude=True)
+
+    class Config(pydantic.BaseModel):
+        name: str
+        servers: list[Server]
+
+    obj = Config(
+        name="app",
+        servers=[Server(host="localhost", **{"listen-port": 8080}, secret="x")],
+    )
+    assert toml_rs.dumps(obj) == (
+        'name = "app"\n'
+        'servers = [{ host = "localhost", listen-port = 8080 }]\n'
+    )
+
+
+def test_dumps_fallback_vars() -> None:
+    from argparse import Namespace
+
+    obj = {"args": Namespace(verbose=True, level=3)}
+    with pytest.raises(toml_rs.TOMLEncodeError, match="Cannot serialize"):
+        toml_rs.dumps(obj)
+    assert toml_rs.dumps(obj, fallback="vars") == "[args]\nverbose = true\nlevel = 3\n"
+    with pytest.raises(ValueError, match="Unsupported fallback: json"):
+        toml_rs.dumps(obj, fallback="json")  # type: ignore[arg-type]
+
+
+def test_dumps_lossy_offset() -> None:
+    tz = timezone(timedelta(hours=1, seconds=30))
+    obj = {"x": datetime(1979, 5, 27, 7, 32, tzinfo=tz)}
+
+    with pytest.warns(UserWarning, match="truncated to whole minutes"):
+        assert toml_rs.dumps(obj) == "x = 1979-05-27T07:32:00+01:00\n"
+
+    with pytest.raises(toml_rs.TOMLEncodeError, match="truncated to whole minutes"):
+        toml_rs.dumps(obj, strict=True)
+
+
+def test_dumps_lossy_offset_path() -> None:
+    tz = timezone(timedelta(hours=1, seconds=30))
+    obj = {"event": {"when": datetime(1979, 5, 27, 7, 32, tzinfo=tz)}}
+
+    with pytest.raises(toml_rs.TOMLEncodeError, match="at 'event.when' truncated"):
+        toml_rs.dumps(obj, strict=True)
+    with pytest.raises(toml_rs.TOMLEncodeError, match="at 'event.when' truncated"):
+        toml_rs.dumps(obj, strict=True, pretty=True)
+
+
+@pytest.mark.parametrize("atomic", [True, False])
+def test_dump_path(atomic: bool, tmp_path: Path) -> None:  # noqa: FBT001
+    path = tmp_path / "config.toml"
+    path.write_text("old = true\n")
+    path.chmod(0o640)
+    mode = path.stat().st_mode
+
+    written = toml_rs.dump_path({"a": 1, "b": "ü"}, path, atomic=atomic)
+
+    assert path.read_text(encoding="utf-8") == 'a = 1\nb = "ü"\n'
+    assert written == len('a = 1\nb = "ü"\n'.encode())
+    assert path.stat().st_mode == mode
+    assert [p.name for p in tmp_path.iterdir()] == ["config.toml"]
+
+    with pytest.raises(toml_rs.TOMLEncodeError):
+        toml_rs.dump_path({"x": object()}, path, atomic=atomic)
+    assert path.read_text(encoding="utf-8") == 'a = 1\nb = "ü"\n'
+
+    new_path = tmp_path / "new.toml"
+    (tmp_path / "plain").write_text("")
+    toml_rs.dump_path({"a": 1}, new_path, atomic=atomic)
+    assert new_path.stat().st_mode == (tmp_path / "plain").stat().st_mode
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="symlinks need privileges")
+def test_dump_path_through_symlink(tmp_path: Path) -> None:
+    target = tmp_path / "real.toml"
+    target.write_text("old = true\n")
+    link = tmp_path / "config.toml"
+    link.symlink_to(target)
+
+    toml_rs.dump_path({"a": 1}, link, atomic=True)
+
+    assert link.is_symlink()
+    assert target.read_text(encoding="utf-8") == "a = 1\n"
+
+
+def test_update_file(tmp_path: Path, toml_version: toml_rs._lib.TomlVersion) -> None:
+    path = tmp_path / "pyproject.toml"
+    path.write_text(
+        "# settings\n"
+        "[project]\n"
+        'name = "demo"  # the name\n'
+        'version = "1.0"\n'
+        "\n"
+        "[tool.x]\n"
+        "debug = true\n",
+    )
+    changes = {
+        "project.version": "1.1",
+        "project.name": "demo",
+        "tool.x.debug": None,
+        "tool.x.level": 3,
+    }
+
+    assert toml_rs.update_file(path, changes, toml_version=toml_version)
+    assert path.read_text() == (
+        "# settings\n"
+        "[project]\n"
+        'name = "demo"  # the name\n'
+        'version = "1.1"\n'
+        "\n"
+        "[tool.x]\n"
+        "level = 3\n"
+    )
+
+    mtime = path.stat().st_mtime_ns
+    assert not toml_rs.update_file(path, changes, toml_version=toml_version)
+    assert path.stat().st_mtime_ns == mtime
+
+    with pytest.raises(ValueError, match="is not a table"):
+        toml_rs.update_file(path, {"project.name.first": 1}, toml_version=toml_version)
+
+
+def test_update_file_keeps_number_format(
+    tmp_path: Path, toml_version: toml_rs._lib.TomlVersion
+) -> None:
+    path = tmp_path / "limits.toml"
+    path.write_text(
+        "[limits]\nsize = 1_000_000\nmask = 0xDEAD_BEEF\nports = [0x50, 8_443]\n"
+    )
+
+    changes = {"limits.ports": [80, 8443, 9000], "limits.size": 1_000_000}
+    assert toml_rs.update_file(path, changes, toml_version=toml_version)
+    assert path.read_text() == (
+        "[limits]\nsize = 1_000_000\nmask = 0xDEAD_BEEF\nports = [0x50, 8_443, 9000]\n"
+    )
+
+    table = {"size": 2_000_000, "mask": 0xDEADBEEF, "ports": [0x50, 8443, 9000]}
+    assert toml_rs.update_file(path, {"limits": table}, toml_version=toml_version)
+    assert path.read_text() == (
+        "[limits]\nsize = 2000000\nmask = 0xDEAD_BEEF\nports = [0x50, 8_443, 9000]\n"
+    )
+
+
+@pytest.mark.parametrize("suffix", [".toml.gz", ".toml.zst"])
+def test_dump_path_compressed(suffix: str, tmp_path: Path) -> None:
+    path = tmp_path / f"data{suffix}"
+    data = {"a": 1, "table": {"b": ["x"] * 100}}
+
+    written = toml_rs.dump_path(data, path)
+
+    assert written == path.stat().st_size
+    assert written < len(toml_rs.dumps(data))
+    assert toml_rs.load_path(path) == data
+
+    plain = tmp_path / "data.bin"
+    plain.write_bytes(path.read_bytes())
+    compression = "gzip" if suffix == ".toml.gz" else "zstd"
+    assert toml_rs.load_path(plain, compression=compression) == data
+
+
+def test_dumps_plain_matches_document(toml_version: toml_rs._lib.TomlVersion) -> None:
+    data = {
+        "title": 'say "hi"\n',
+        "empty": {},
+        "nested": {
+            "a b": 1,
+            "deeper": {"when": datetime(2024, 1, 2, tzinfo=timezone.utc)},
+        },
+        "nums": [1, 2.5, -0.0, float("inf")],
+        "points": [{"x": 1, "y": {"z": [True]}}, ()],
+        "day": date(2024, 1, 2),
+        "at": time(1, 2, 3),
+    }
+
+    # A non-dict root always goes through `DocumentMut`.
+    expected = toml_rs.dumps(MappingProxyType(data), toml_version=toml_version)
+    assert toml_rs.dumps(data, toml_version=toml_version) == expected
+    assert toml_rs.loads(expected, toml_version=toml_version) == {
+        **data,
+        "points": [{"x": 1, "y": {"z": [True]}}, []],
+    }
+
+
+def test_dumps_verify(monkeypatch: pytest.MonkeyPatch) -> None:
+    obj = {
+        "name": "demo",
+        "ratio": float("nan"),
+        "price": Decimal("1.5"),
+        "ports": (80, 443),
+        "when": datetime(2024, 1, 2, 3, 4, 5, tzinfo=timezone.utc),
+        "day": date(2024, 1, 2),
+        "server": MappingProxyType({"host": "localhost"}),
+    }
+    assert toml_rs.dumps(obj, verify=True) == toml_rs.dumps(obj)
+
+    monkeypatch.setattr(toml_rs._lib, "_dumps", lambda *_, **__: 'name = "other"\n')
+    with pytest.raises(toml_rs.TOMLEncodeError, match="at name: 'demo' became 'other'"):
+        toml_rs.dumps({"name": "demo"}, verify=True)
+
+
+def test_negative_zero_round_trip(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {"x": -0.0, "values": [-0.0, 0.0], "d": Decimal("-0.0")}
+
+    text = toml_rs.dumps(obj, verify=True, toml_version=toml_version)
+    assert text == "x = -0.0\nvalues = [-0.0, 0.0]\nd = -0.0\n"
+
+    loaded = toml_rs.loads(text, toml_version=toml_version)
+    assert [math.copysign(1, value) for value in loaded["values"]] == [-1, 1]
+    assert math.copysign(1, loaded["x"]) == math.copysign(1, loaded["d"]) == -1
+    assert toml_rs.dumps(loaded, toml_version=toml_version) == text
+
+
+def test_dumps_verify_zero_sign(monkeypatch: pytest.MonkeyPatch) -> None:
+    monkeypatch.setattr(toml_rs._lib, "_dumps", lambda *_, **__: "x = 0.0\n")
+    with pytest.raises(toml_rs.TOMLEncodeError, match="at x: -0.0 became 0.0"):
+        toml_rs.dumps({"x": -0.0}, verify=True)
+
+
+def test_dumps_backslash_strings_are_literal() -> None:
+    # Strings with backslashes and no single quotes are already written as
+    # literal strings, so nothing needs to be escaped.
+    obj = {"path": "C:\\Users\\me", "regex": "\\d+\\.\\d+"}
+    assert toml_rs.dumps(obj) == "path = 'C:\\Users\\me'\nregex = '\\d+\\.\\d+'\n"
+    assert toml_rs.loads(toml_rs.dumps(obj)) == obj
+
+
+def test_dumps_quote_keys(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {"name": "demo", "a b": 1, "": 2, "server": {"host": "localhost"}}
+
+    text = toml_rs.dumps(obj, toml_version=toml_version)
+    assert text == 'name = "demo"\n"a b" = 1\n"" = 2\n\n[server]\nhost = "localhost"\n'
+
+    quoted = toml_rs.dumps(obj, quote_keys=True, toml_version=toml_version)
+    assert quoted == (
+        '"name" = "demo"\n"a b" = 1\n"" = 2\n\n["server"]\n"host" = "localhost"\n'
+    )
+    assert toml_rs.loads(quoted, toml_version=toml_version) == obj
+
+
+@pytest.mark.parametrize("quote_keys", [False, True])
+@pytest.mark.parametrize("seed", range(25))
+def test_dumps_key_round_trip(
+    seed: int,
+    quote_keys: bool,  # noqa: FBT001
+    toml_version: toml_rs._lib.TomlVersion,
+) -> None:
+    rng = random.Random(seed)
+    alphabet = "aZ09_- .\"'\\\t\n\x00\x1f\x7féключ😀"
+
+    def key() -> str:
+        return "".join(rng.choice(alphabet) for _ in range(rng.randint(0, 6)))
+
+    obj = {
+        key(): {key(): [{key(): rng.randint(0, 9)}], key(): {key(): "v"}}
+        for _ in range(5)
+    }
+    text = toml_rs.dumps(obj, quote_keys=quote_keys, toml_version=toml_version)
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+
+def test_dumps_lone_surrogates(toml_version: toml_rs._lib.TomlVersion) -> None:
+    name = b"report\xff.txt".decode(errors="surrogateescape")
+
+    with pytest.raises(
+        toml_rs.TOMLEncodeError, match=r"string at 'files\.name' contains .* U\+DCFF"
+    ):
+        toml_rs.dumps({"files": {"name": name}}, toml_version=toml_version)
+    with pytest.raises(toml_rs.TOMLEncodeError, match=r"key 'report\\udcff\.txt'"):
+        toml_rs.dumps({name: 1}, toml_version=toml_version)
+
+    replaced = toml_rs.dumps(
+        {name: name}, surrogates="replace", toml_version=toml_version
+    )
+    assert toml_rs.loads(replaced, toml_version=toml_version) == {
+        "report\ufffd.txt": "report\ufffd.txt"
+    }
+
+    escaped = toml_rs.dumps(
+        {"name": name}, surrogates="backslashreplace", toml_version=toml_version
+    )
+    assert toml_rs.loads(escaped, toml_version=toml_version) == {
+        "name": "report\\udcff.txt"
+    }
+
+
+def test_dumps_escape_form(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {"bell\x07": "a\x1bb\tc", "list": ["\x00"]}
+
+    assert toml_rs.dumps(obj, toml_version=toml_version) == (
+        '"bell\\u0007" = "a\\u001Bb\\tc"\nlist = ["\\u0000"]\n'
+    )
+
+    text = toml_rs.dumps(obj, escape_form="U", toml_version=toml_version)
+    assert text == '"bell\\U00000007" = "a\\U0000001Bb\\tc"\nlist = ["\\U00000000"]\n'
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+    if toml_version == "1.0.0":
+        with pytest.raises(ValueError, match="requires TOML 1.1.0"):
+            toml_rs.dumps(obj, escape_form="x", toml_version=toml_version)
+    else:
+        text = toml_rs.dumps(obj, escape_form="x", toml_version=toml_version)
+        assert text == '"bell\\x07" = "a\\x1Bb\\tc"\nlist = ["\\x00"]\n'
+        assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+
+def test_dumps_sort_arrays(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {
+        "select": ["W", "E", "B"],
+        "ports": (443, 80),
+        "mixed": [2, "a", 1],
+        "jobs": [{"name": "b"}, {"name": "a"}],
+    }
+
+    loaded = toml_rs.loads(
+        toml_rs.dumps(obj, sort_arrays=True, toml_version=toml_version),
+        toml_version=toml_version,
+    )
+    assert loaded == {
+        "select": ["B", "E", "W"],
+        "ports": [80, 443],
+        "mixed": [2, "a", 1],
+        "jobs": [{"name": "b"}, {"name": "a"}],
+    }
+
+    loaded = toml_rs.loads(
+        toml_rs.dumps(obj, sort_arrays="strings", toml_version=toml_version),
+        toml_version=toml_version,
+    )
+    assert (loaded["select"], loaded["ports"]) == (["B", "E", "W"], [443, 80])
+
+    loaded = toml_rs.loads(
+        toml_rs.dumps(obj, sort_arrays={"ports"}, toml_version=toml_version),
+        toml_version=toml_version,
+    )
+    assert (loaded["select"], loaded["ports"]) == (["W", "E", "B"], [80, 443])
+
+
+def test_dumps_key_order(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {
+        "project": {
+            "readme": "README.md",
+            "description": "Fast TOML",
+            "name": "toml-rs",
+            "version": "1.0",
+            "urls": {"home": "https://example.org"},
+        },
+        "zeta": 1,
+        "alpha": 2,
+    }
+    key_order = {"project": ["name", "version", "description"], "": ["alpha"]}
+
+    text = toml_rs.dumps(obj, key_order=key_order, toml_version=toml_version)
+    assert text == (
+        "alpha = 2\n"
+        "zeta = 1\n"
+        "\n"
+        "[project]\n"
+        'name = "toml-rs"\n'
+        'version = "1.0"\n'
+        'description = "Fast TOML"\n'
+        'readme = "README.md"\n'
+        "\n"
+        "[project.urls]\n"
+        'home = "https://example.org"\n'
+    )
+
+    text = toml_rs.dumps(
+        obj,
+        inline_tables={"project"},
+        key_order=key_order,
+        toml_version=toml_version,
+    )
+    assert list(toml_rs.loads(text, toml_version=toml_version)["project"]) == [
+        "name",
+        "version",
+        "description",
+        "readme",
+        "urls",
+    ]
+
+
+def test_dumps_encode_hook(toml_version: toml_rs._lib.TomlVersion) -> None:
+    seen = []
+
+    def hook(path: tuple[str | int, ...], value: Any) -> Any:
+        seen.append(path)
+        if isinstance(value, str):
+            return value.strip()
+        if path == ("timeout",):
+            return value * 1000
+        return value
+
+    obj = {"name": "  app ", "timeout": 3, "hosts": [" a", ("b ", 2)]}
+
+    text = toml_rs.dumps(obj, encode_hook=hook, toml_version=toml_version)
+    assert text == 'name = "app"\ntimeout = 3000\nhosts = ["a", ["b", 2]]\n'
+    assert seen == [
+        (),
+        ("name",),
+        ("timeout",),
+        ("hosts",),
+        ("hosts", 0),
+        ("hosts", 1),
+        ("hosts", 1, 0),
+        ("hosts", 1, 1),
+    ]
+
+    text = toml_rs.dumps(
+        {"when": "2024"},
+        encode_hook=lambda path, value: date(int(value), 1, 1) if path else value,
+        toml_version=toml_version,
+    )
+    assert text == "when = 2024-01-01\n"
+
+
+def test_dumps_max_depth(toml_version: toml_rs._lib.TomlVersion) -> None:
+    deep: list[Any] = []
+    for _ in range(999):
+        deep = [deep]
+    text = toml_rs.dumps({"a": deep}, toml_version=toml_version)
+    assert text == "a = " + "[" * 1000 + "]" * 1000 + "\n"
+
+    obj = {"a": {"b": {"c": [[1], {"d": 2}]}}}
+    text = toml_rs.dumps(obj, max_depth=4, toml_version=toml_version)
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+    with pytest.raises(
+        toml_rs.TOMLEncodeError,
+        match=re.escape("Value at 'a.b.c' nests deeper than max_depth (3)"),
+    ):
+        toml_rs.dumps(obj, max_depth=3, toml_version=toml_version)
+
+    cyclic: dict[str, Any] = {"name": "app"}
+    cyclic["items"] = [cyclic]
+    with pytest.raises(toml_rs.TOMLEncodeError, match="Value at 'items' contains itself"):
+        toml_rs.dumps(cyclic, toml_version=toml_version)
+
+
+def test_dumps_indent_char(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {"matrix": [[1, 2], [3, 4]], "tags": ["a", "b"]}
+
+    text = toml_rs.dumps(obj, pretty=True, indent_char="\t", toml_version=toml_version)
+    assert text == (
+        "matrix = [\n"
+        "\t[\n\t\t1,\n\t\t2,\n\t],\n"
+        "\t[\n\t\t3,\n\t\t4,\n\t],\n"
+        "]\n"
+        'tags = [\n\t"a",\n\t"b",\n]\n'
+    )
+
+    text = toml_rs.dumps({"tags": ["a", "b"]}, pretty=True, indent_char=2)
+    assert text == 'tags = [\n  "a",\n  "b",\n]\n'
+    assert toml_rs.dumps({"tags": ["a", "b"]}, pretty=True) == (
+        'tags = [\n    "a",\n    "b",\n]\n'
+    )
+
+    with pytest.raises(ValueError, match="Unsupported indent_char: ' '"):
+        toml_rs.dumps(obj, pretty=True, indent_char=" ")  # type: ignore[arg-type]
+
+
+def test_dumps_preserve_order(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {
+        "server": {"tls": {"cert": "a.pem"}, "port": 443},
+        "jobs": [{"name": "a"}, {"name": "b"}],
+        "name": "demo",
+        "owner": {"name": "x"},
+    }
+
+    text = toml_rs.dumps(obj, toml_version=toml_version)
+    assert text.index("name = ") < text.index("[server]") < text.index("[owner]")
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+    text = toml_rs.dumps(obj, preserve_order=True, toml_version=toml_version)
+    assert text == (
+        'server = { tls = { cert = "a.pem" }, port = 443 }\n'
+        'jobs = [{ name = "a" }, { name = "b" }]\n'
+        'name = "demo"\n'
+        "\n"
+        "[owner]\n"
+        'name = "x"\n'
+    )
+    assert list(toml_rs.loads(text, toml_version=toml_version)) == list(obj)
+
+
+@pytest.mark.parametrize("preserve_order", [False, True])
+@pytest.mark.parametrize("pretty", [False, True])
+@pytest.mark.parametrize("seed", range(25))
+def test_dumps_shape_round_trip(
+    seed: int,
+    *,
+    pretty: bool,
+    preserve_order: bool,
+    toml_version: toml_rs._lib.TomlVersion,
+) -> None:
+    rng = random.Random(seed)
+
+    def value(depth: int) -> Any:
+        kinds = ["scalar", "list", "tables", "dict"] if depth < 3 else ["scalar"]
+        kind = rng.choice(kinds)
+        if kind == "scalar":
+            return rng.choice([1, "s", 2.5, True])
+        if kind == "list":
+            return [value(depth + 1) for _ in range(rng.randint(0, 3))]
+        if kind == "tables":
+            return [table(depth + 1) for _ in range(rng.randint(1, 3))]
+        return table(depth + 1)
+
+    def table(depth: int) -> dict[str, Any]:
+        return {f"k{i}": value(depth) for i in range(rng.randint(0, 4))}
+
+    obj = table(0)
+    text = toml_rs.dumps(
+        obj, pretty=pretty, preserve_order=preserve_order, toml_version=toml_version
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+
+@pytest.mark.parametrize("pretty", [False, True])
+def test_dumps_comments(toml_version: toml_rs._lib.TomlVersion, *, pretty: bool) -> None:
+    obj = {"title": "t", "registry": [{"name": "a"}, {"name": "b"}]}
+    comments = {
+        "title": "Generated",
+        "registry[0]": "first",
+        "registry[name=b]": "second\nmirror",
+    }
+
+    text = toml_rs.dumps(
+        obj, pretty=pretty, comments=comments, toml_version=toml_version
+    )
+    assert text == (
+        "# Generated\n"
+        'title = "t"\n'
+        "\n"
+        "# first\n"
+        "[[registry]]\n"
+        'name = "a"\n'
+        "\n"
+        "# second\n"
+        "# mirror\n"
+        "[[registry]]\n"
+        'name = "b"\n'
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+    text = toml_rs.dumps(
+        {"a": {"x": 1}}, comments={"a": "top"}, toml_version=toml_version
+    )
+    assert text == "# top\n[a]\nx = 1\n"
+
+    with pytest.raises(KeyError, match="Key path not found"):
+        toml_rs.dumps(obj, comments={"registry[name=c]": "x"}, toml_version=toml_version)
+    with pytest.raises(ValueError, match="not an array of tables"):
+        toml_rs.dumps(obj, comments={"title[0]": "x"}, toml_version=toml_version)
+
+
+def test_dumps_float_formats(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {
+        "ratio": 0.1,
+        "physics": {
+            "tiny": 1e-7,
+            "constants": {"c": 299792458.0, "g": 9.80665, "n": 6.02214076e23},
+        },
+    }
+    float_formats: dict[str, toml_rs._lib.FloatFormat] = {
+        "physics.constants.*": {"precision": 3},
+        "physics.tiny": {"fixed": True},
+    }
+
+    text = toml_rs.dumps(obj, float_formats=float_formats, toml_version=toml_version)
+    assert text == (
+        "ratio = 0.1\n"
+        "\n"
+        "[physics]\n"
+        "tiny = 0.0000001\n"
+        "\n"
+        "[physics.constants]\n"
+        "c = 299792458.000\n"
+        "g = 9.807\n"
+        "n = 6.022e23\n"
+    )
+    assert toml_rs.loads(text, toml_version=toml_version)["physics"]["tiny"] == 1e-7
+
+    unknown: Any = {"ratio": {"digits": 2}}
+    with pytest.raises(ValueError, match="Unsupported float format option: digits"):
+        toml_rs.dumps(obj, float_formats=unknown)
+
+
+def test_dumps_float_formats_quoted_keys(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {"a.b": {"c": 0.5, "*": 0.333}, "a": {"b": {"c": 0.5, "d": 0.333}}}
+
+    text = toml_rs.dumps(
+        obj,
+        float_formats={'"a.b".c': {"precision": 3}, '"a.b"."*"': {"precision": 1}},
+        toml_version=toml_version,
+    )
+    assert '["a.b"]\nc = 0.500\n"*" = 0.3\n' in text
+    assert "[a.b]\nc = 0.5\nd = 0.333\n" in text
+
+    for pattern in ("a..b", '"a.b', "a.[b]", "a b"):
+        with pytest.raises(ValueError, match="Invalid key pattern"):
+            toml_rs.dumps(obj, float_formats={pattern: {"precision": 1}})
+
+
+def test_dumps_datetime_formats(toml_version: toml_rs._lib.TomlVersion) -> None:
+    built = datetime(2024, 5, 1, 12, 30, tzinfo=timezone(timedelta(hours=2)))
+    obj = {
+        "released": built,
+        "build": {
+            "at": built,
+            "day": built,
+            "wall": built,
+            "naive": datetime(2024, 5, 1),
+        },
+    }
+    datetime_formats: dict[str, toml_rs._lib.DatetimeFormat] = {
+        "build.at": "utc",
+        "build.day": "date",
+        "build.*": "local",
+    }
+
+    text = toml_rs.dumps(
+        obj, datetime_formats=datetime_formats, toml_version=toml_version
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == {
+        "released": built,
+        "build": {
+            "at": datetime(2024, 5, 1, 10, 30, tzinfo=timezone.utc),
+            "day": date(2024, 5, 1),
+            "wall": datetime(2024, 5, 1, 12, 30),
+            "naive": datetime(2024, 5, 1),
+        },
+    }
+
+    with pytest.raises(
+        toml_rs.TOMLEncodeError,
+        match=r"Cannot write naive datetime at 'build\.naive' as UTC",
+    ):
+        toml_rs.dumps(
+            obj,
+            datetime_formats={"build.naive": "utc", **datetime_formats},
+            toml_version=toml_version,
+        )
+
+    unknown: Any = {"released": "time"}
+    with pytest.raises(ValueError, match="Unsupported datetime format: time"):
+        toml_rs.dumps(obj, datetime_formats=unknown)
+
+
+def test_dumps_datetime_formats_quoted_keys(
+    toml_version: toml_rs._lib.TomlVersion,
+) -> None:
+    built = datetime(2024, 5, 1, 12, 30, tzinfo=timezone(timedelta(hours=2)))
+    obj = {"a.b": {"c": built}, "a": {"b": {"c": built}}}
+
+    text = toml_rs.dumps(
+        obj, datetime_formats={'"a.b".c': "date"}, toml_version=toml_version
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == {
+        "a.b": {"c": date(2024, 5, 1)},
+        "a": {"b": {"c": built}},
+    }
+
+    with pytest.raises(ValueError, match="Invalid key pattern"):
+        toml_rs.dumps(obj, datetime_formats={"'a.b": "date"})
+
+
+def test_dumps_parse_datetime_strings(toml_version: toml_rs._lib.TomlVersion) -> None:
+    obj = {
+        "at": "1979-05-27T07:32:00Z",
+        "day": "1979-05-27",
+        "note": "1979-05-27 at noon",
+    }
+
+    text = toml_rs.dumps(obj, toml_version=toml_version)
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+    text = toml_rs.dumps(obj, parse_datetime_strings=True, toml_version=toml_version)
+    assert text == (
+        "at = 1979-05-27T07:32:00Z\n"
+        "day = 1979-05-27\n"
+        'note = "1979-05-27 at noon"\n'
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == {
+        "at": datetime(1979, 5, 27, 7, 32, tzinfo=timezone.utc),
+        "day": date(1979, 5, 27),
+        "note": "1979-05-27 at noon",
+    }
+
+
+@pytest.mark.parametrize("pretty", [False, True])
+def test_dumps_array_style(
+    toml_version: toml_rs._lib.TomlVersion, *, pretty: bool
+) -> None:
+    obj = {"deps": ["a"], "empty": [], "matrix": [[1], [2, 3]]}
+
+    text = toml_rs.dumps(
+        obj, pretty=pretty, array_style="one_per_line", toml_version=toml_version
+    )
+    assert text == (
+        'deps = [\n    "a",\n]\n'
+        "empty = []\n"
+        "matrix = [\n"
+        "    [\n        1,\n    ],\n"
+        "    [\n        2,\n        3,\n    ],\n"
+        "]\n"
+    )
+    assert toml_rs.loads(text, toml_version=toml_version) == obj
+
+    with pytest.raises(ValueError, match="Unsupported array_style: wrapped"):
+        toml_rs.dumps(obj, array_style="wrapped")  # type: ignore[arg-type]
@@ -175,3 +175,113 @@ def test_incorrect_suffix(toml_version: toml_rs._lib.TomlVersion) -> None:
             match="`_` may only go between digits, expected nothing",
     ):
         tomllib.loads(t1, toml_version=toml_version)
+
+
+@pytest.mark.parametrize(
+    ("data", "exc_type"),
+    [
+        ("x = ", tomllib.TOMLSyntaxError),
+        ("x = 1\nx = 2", tomllib.TOMLDuplicateKeyError),
+        ("[a]\n[a]", tomllib.TOMLDuplicateKeyError),
+        ("x = 0x", tomllib.TOMLInvalidValueError),
+    ],
+)
+def test_decode_error_subclasses(
+        data: str,
+        exc_type: type[tomllib.TOMLDecodeError],
+        toml_version: toml_rs._lib.TomlVersion,
+) -> None:
+    with pytest.raises(exc_type) as exc_info:
+        tomllib.loads(data, toml_version=toml_version)
+    assert isinstance(exc_info.value, tomllib.TOMLDecodeError)
+
+    with pytest.raises(exc_type):
+        tomllib.load_with_metadata(data, toml_version=toml_version)
+
+
+@pytest.mark.parametrize(
+    ("data", "note"),
+    [
+        ("a = 1\nb = 2\na = 3", "note: first defined at line 1, column 1"),
+        ("[t]\nx = 1\n\n[t]\ny = 2", "note: first defined at line 1, column 2"),
+        ("[t]\nx = 1\n[u]\n[t.x]", "note: first defined at line 2, column 1"),
+        ("[t]\nx.y = 1\nx.y = 2", "note: first defined at line 2, column 3"),
+    ],
+)
+def test_duplicate_key_shows_origin(
+        data: str,
+        note: str,
+        toml_version: toml_rs._lib.TomlVersion,
+) -> None:
+    with pytest.raises(tomllib.TOMLDuplicateKeyError) as exc_info:
+        tomllib.loads(data, toml_version=toml_version)
+    assert note in exc_info.value.msg
+
+
+def test_error_formatter(toml_version: toml_rs._lib.TomlVersion) -> None:
+    seen: list[toml_rs.ErrorDetails] = []
+
+    def formatter(details: toml_rs.ErrorDetails) -> str:
+        seen.append(details)
+        return f"Fehler in {'.'.join(details.path)}: {details.message}"
+
+    toml_rs.set_error_formatter(formatter)
+    try:
+        with pytest.raises(toml_rs.TOMLSyntaxError) as syntax_error:
+            toml_rs.loads('[server]\nname = "ü"\nport = \n', toml_version=toml_version)
+        with pytest.raises(toml_rs.TOMLInvalidValueError) as value_error:
+            toml_rs.loads("x = nan", allow_nan=False, toml_version=toml_version)
+    finally:
+        toml_rs.set_error_formatter(None)
+
+    syntax, value = seen
+    assert str(syntax_error.value) == f"Fehler in server.port: {syntax.message}"
+    assert syntax.path == ["server", "port"]
+    assert syntax.expected
+    start, _ = syntax.span
+    assert start > len('[server]\nname = "ü"\n')
+    assert syntax_error.value.doc == '[server]\nname = "ü"\nport = \n'
+
+    assert str(value_error.value) == "Fehler in x: non-finite float 'nan' is not allowed"
+    assert value.span == (4, 7)
+    assert value.expected == []
+
+    with pytest.raises(toml_rs.TOMLSyntaxError, match="TOML parse error"):
+        toml_rs.loads("x = ", toml_version=toml_version)
+
+
+def test_position(toml_version: toml_rs._lib.TomlVersion) -> None:
+    doc = 'name = "ü"\nport = = 1\n'
+    with pytest.raises(toml_rs.TOMLSyntaxError) as exc_info:
+        toml_rs.loads(doc, toml_version=toml_version)
+
+    line, col = toml_rs.position(doc, exc_info.value.pos)
+    assert line == 2
+    assert f"line {line}, column {col}" in str(exc_info.value)
+
+    assert toml_rs.position("ü = 1", 3) == (1, 3)
+    assert toml_rs.position("", 0) == (1, 1)
+
+
+def test_error_formatter_uses_requested_grammar() -> None:
+    seen: list[toml_rs.ErrorDetails] = []
+
+    def formatter(details: toml_rs.ErrorDetails) -> str:
+        seen.append(details)
+        return details.message
+
+    # The trailing comma is the first error under 1.0.0 and allowed in 1.1.0,
+    # where the missing value of `y` comes first.
+    doc = "x = {a = 1,}\ny = \n"
+    toml_rs.set_error_formatter(formatter)
+    try:
+        with pytest.raises(toml_rs.TOMLSyntaxError, match="trailing comma"):
+            toml_rs.loads(doc, toml_version="1.0.0")
+        with pytest.raises(toml_rs.TOMLSyntaxError):
+            toml_rs.loads(doc, toml_version="1.1.0")
+    finally:
+        toml_rs.set_error_formatter(None)
+
+    v1_0, v1_1 = seen
+    assert v1_0.expected == []
+    assert v1_1.expected
@@ -1,4 +1,7 @@
+import copy
 import datetime
+import pickle
+from collections.abc import Mapping
 
 import pytest
 import toml_rs
@@ -889,3 +892,158 @@ def test_document_item_accessors(toml_version: toml_rs._lib.TomlVersion) -> None
     assert escaped_quoted.meta["nodes"]["aAb"]["key_raw"] == '"a\\u0041b"'
     assert escaped_quoted.meta["nodes"]['a"b']["key"] == 'a"b'
     assert escaped_quoted.meta["nodes"]['a"b']["key_raw"] == '"a\\"b"'
+
+
+def test_document_pickle(toml_version: toml_rs._lib.TomlVersion) -> None:
+    doc = toml_rs.load_with_metadata(
+        _dedent("""
+        title = "TOML Example"
+
+        # Who wrote it.
+        [owner]
+        dob = 1979-05-27T07:32:00-08:00
+        """),
+        toml_version=toml_version,
+    )
+    # Unchanged, the text it was parsed from is pickled as is.
+    payload = pickle.dumps(doc)
+    assert b"# Who wrote it." in payload
+    assert pickle.loads(payload).value == doc.value  # noqa: S301
+
+    doc["owner.name"] = "Tom"
+    payload = pickle.dumps(doc)
+    assert b"# Who wrote it." not in payload
+
+    restored = pickle.loads(payload)  # noqa: S301
+
+    assert restored.value == doc.value
+    assert restored.toml_version == toml_version
+    assert restored.meta["nodes"]["owner"]["name"]["value"] == "Tom"
+
+
+def test_document_copy(toml_version: toml_rs._lib.TomlVersion) -> None:
+    doc = toml_rs.load_with_metadata(
+        _dedent("""
+        name = "base"
+
+        [server]
+        port = 8080
+        """),
+        toml_version=toml_version,
+    )
+
+    shallow = copy.copy(doc)
+    shallow["name"] = "fork"
+    assert doc["name"] == "base"
+    assert shallow.value["server"] is doc.value["server"]
+
+    deep = copy.deepcopy(doc)
+    deep["server.port"] = 9090
+    assert doc["server.port"] == 8080
+    assert deep["server.port"] == 9090
+    assert deep.meta == doc.meta
+    assert deep.toml_version == toml_version
+
+
+def test_document_mapping(toml_version: toml_rs._lib.TomlVersion) -> None:
+    doc = toml_rs.load_with_metadata(
+        _dedent("""
+        name = "app"
+
+        [server]
+        port = 8080
+        """),
+        toml_version=toml_version,
+    )
+
+    assert isinstance(doc, Mapping)
+    assert len(doc) == 2
+    assert list(doc) == ["name", "server"]
+    assert "name" in doc
+    assert "server" in doc
+    assert "server.port" not in doc
+    assert list(doc.keys()) == ["name", "server"]
+    assert list(doc.values()) == ["app", {"port": 8080}]
+    assert dict(doc.items()) == doc.value
+    assert doc.get("server.port") is None
+    assert doc.get("missing", 1) == 1
+    assert doc.get_path("server.port") == 8080
+    assert doc.get_path("server.host", 1) == 1
+    assert doc["server.port"] == 8080
+    assert doc == {"name": "app", "server": {"port": 8080}}
+    assert doc != {"name": "app"}
+    assert doc == toml_rs.load_with_metadata(
+        'name = "app"\nserver = { port = 8080 }',
+        toml_version=toml_version,
+    )
+    assert repr(doc) == "TOMLDocument({'name': 'app', 'server': {'port': 8080}})"
+
+
+def test_document_dict_conversion(toml_version: toml_rs._lib.TomlVersion) -> None:
+    data = {"name": "app", "server": {"port": 8080, "hosts": ["a", "b"]}}
+
+    doc = toml_rs.TOMLDocument.from_dict(data, toml_version=toml_version)
+    assert doc.value == data
+    assert doc.toml_version == toml_version
+    assert doc.meta["nodes"]["server"]["port"]["value_raw"] == "8080"
+
+    plain = doc.unwrap()
+    assert plain == data
+    assert type(plain) is dict
+    plain["server"]["port"] = 1
+    assert doc["server.port"] == 8080
+
+
+def test_document_visit(toml_version: toml_rs._lib.TomlVersion) -> None:
+    text = (
+        'title = "x"\n[server]\nports = [80, 443]\nlimits.cpu = 2\n'
+        '[[jobs]]\nname = "a"\n'
+    )
+    doc = toml_rs.load_with_metadata(text, toml_version=toml_version)
+
+    seen: dict[tuple[str | int, ...], tuple[str, str]] = {}
+
+    def record(
+        path: tuple[str | int, ...], span: tuple[int, int] | None, kind: str
+    ) -> None:
+        assert span is not None
+        seen[path] = (text[span[0] : span[1]], kind)
+
+    doc.visit(record)
+    assert seen == {
+        ("title",): ('"x"', "string"),
+        ("server",): ("[server]\nports = [80, 443]\nlimits.cpu = 2", "table"),
+        ("server", "ports"): ("[80, 443]", "array"),
+        ("server", "ports", 0): ("80", "integer"),
+        ("server", "ports", 1): ("443", "integer"),
+        ("server", "limits"): ("limits.cpu = 2", "table"),
+        ("server", "limits", "cpu"): ("2", "integer"),
+        ("jobs",): ('[[jobs]]\nname = "a"', "array of tables"),
+        ("jobs", 0): ('[[jobs]]\nname = "a"', "table"),
+        ("jobs", 0, "name"): ('"a"', "string"),
+    }
+
+    paths: list[tuple[str | int, ...]] = []
+
+    def skip_tables(
+        path: tuple[str | int, ...], span: tuple[int, int] | None, kind: str
+    ) -> bool:
+        paths.append(path)
+        return kind != "table"
+
+    doc.visit(skip_tables)
+    assert paths == [("title",), ("server",), ("jobs",), ("jobs", 0)]
+
+    doc["server"]["ports"].append(8080)
+    del doc["jobs"]
+    changed: dict[tuple[str | int, ...], tuple[int, int] | None] = {}
+
+    def record_changed(
+        path: tuple[str | int, ...], span: tuple[int, int] | None, kind: str
+    ) -> None:
+        changed[path] = span
+
+    doc.visit(record_changed)
+    assert ("server", "ports", 2) in changed
+    assert ("jobs",) not in changed
+    assert set(changed.values()) == {None}
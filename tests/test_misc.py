@@ -1,11 +1,23 @@
 import copy
 import datetime
+import io
+import os
+import queue
+import re
+import subprocess
+import sys
+import warnings
+from collections.abc import Iterator
 from decimal import Decimal
 from pathlib import Path
 from tempfile import TemporaryDirectory
+from typing import Any
 
 import pytest
 import toml_rs as tomllib
+from toml_rs import items, tomlkit
+
+from .helpers import _dedent
 
 
 def test_load() -> None:
@@ -20,6 +32,59 @@ def test_load() -> None:
     assert actual == expected
 
 
+def test_load_encoding(tmp_path: Path) -> None:
+    path = tmp_path / "legacy.toml"
+    path.write_bytes('name = "café"\n'.encode("latin-1"))
+
+    with path.open("rb") as bin_f:
+        assert tomllib.load(bin_f, encoding="latin-1") == {"name": "café"}
+    assert tomllib.load_path(path, encoding="latin-1") == {"name": "café"}
+
+    path.write_bytes('a = "日本"\n'.encode("utf-16-le"))
+    assert tomllib.load_path(path, encoding="utf-16le") == {"a": "日本"}
+
+    path.write_bytes(b'a = "\x82\xa0"\nb = "\x82"\n')
+    with pytest.raises(UnicodeDecodeError) as exc_info:
+        tomllib.load_path(path, encoding="shift_jis")
+    assert (exc_info.value.start, exc_info.value.end) == (14, 15)
+
+    with pytest.raises(LookupError, match="unknown encoding"):
+        tomllib.load_path(path, encoding="no-such-codec")
+
+
+def test_load_bom(tmp_path: Path, toml_version: tomllib._lib.TomlVersion) -> None:
+    path = tmp_path / "bom.toml"
+    path.write_bytes(b"\xef\xbb\xbfa = 1\n")
+
+    assert tomllib.load_path(path, toml_version=toml_version) == {"a": 1}
+    with path.open("rb") as bin_f:
+        assert tomllib.load(bin_f, toml_version=toml_version) == {"a": 1}
+    assert tomllib.loads("\ufeffa = 1\n", toml_version=toml_version) == {"a": 1}
+
+    path.write_bytes("a = 1\n".encode("utf-16"))
+    with pytest.raises(UnicodeDecodeError, match="UTF-16LE byte order mark"):
+        tomllib.load_path(path, toml_version=toml_version)
+    assert tomllib.load_path(path, encoding="utf-16le") == {"a": 1}
+
+    path.write_bytes("\ufeffa = 1\n".encode("utf-32-be"))
+    with pytest.raises(UnicodeDecodeError, match="UTF-32BE byte order mark"):
+        tomllib.load_path(path, toml_version=toml_version)
+
+
+def test_load_utf8_bytes(tmp_path: Path, toml_version: tomllib._lib.TomlVersion) -> None:
+    path = tmp_path / "utf8.toml"
+    path.write_bytes('name = "café"\nport = 0x\n'.encode())
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        tomllib.load_path(path, toml_version=toml_version)
+    assert (exc_info.value.lineno, exc_info.value.colno) == (2, 8)
+    assert exc_info.value.doc == 'name = "café"\nport = 0x\n'
+
+    path.write_bytes(b'a = "ok"\nb = "\xff"\n')
+    with pytest.raises(UnicodeDecodeError) as decode_info:
+        tomllib.load_path(path, toml_version=toml_version)
+    assert (decode_info.value.start, decode_info.value.end) == (14, 15)
+
+
 def test_incorrect_load() -> None:
     content = "one=1"
     with TemporaryDirectory() as tmp_dir_path:
@@ -66,6 +131,19 @@ def test_parse_float() -> None:
             assert actual_val == expected_val
 
 
+def test_parse_float_lexeme(toml_version: tomllib._lib.TomlVersion) -> None:
+    seen: list[str] = []
+
+    def record(s: str) -> str:
+        seen.append(s)
+        return s
+
+    doc = "a = 1_000.000_1\nb = [+1.50E+3, -0.0e0]\nc = { d = 1e-07 }"
+    obj = tomllib.loads(doc, parse_float=record, toml_version=toml_version)
+    assert seen == ["1_000.000_1", "+1.50E+3", "-0.0e0", "1e-07"]
+    assert obj == {"a": "1_000.000_1", "b": ["+1.50E+3", "-0.0e0"], "c": {"d": "1e-07"}}
+
+
 def test_deepcopy() -> None:
     doc = """
           [bliibaa.diibaa]
@@ -94,3 +172,1372 @@ def test_deepcopy() -> None:
         },
     }
     assert obj_copy == expected_obj
+
+
+def test_digest() -> None:
+    doc = """
+          title = "TOML Example"
+          [owner]
+          dob = 1979-05-27T07:32:00-08:00
+          ports = [ 8000, 8001 ]
+          """
+    reformatted = """
+          [owner]
+          ports = [8000,8001]   # same values
+          dob = 1979-05-27T15:32:00Z
+
+          title = 'TOML Example'
+          """
+    assert tomllib.digest(doc) == tomllib.digest(reformatted)
+    assert tomllib.digest(doc) != tomllib.digest('title = "TOML Example"')
+    assert tomllib.digest("x = 1") != tomllib.digest("x = 1.0")
+    assert tomllib.digest("x = 0x10") == tomllib.digest("x = 16")
+    assert len(tomllib.digest("x = 1", "md5")) == 32
+
+
+def test_loads_with_comments(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = """\
+# Project metadata
+[project]
+name = "toml-rs"  # distribution name
+# Supported interpreters
+requires-python = ">= 3.10"
+
+[[tool.checks]]  # first check
+id = 1
+"""
+    data, comments = tomllib.loads(doc, with_comments=True, toml_version=toml_version)
+    assert data == tomllib.loads(doc, toml_version=toml_version)
+    assert comments == {
+        "project": {"leading": "Project metadata", "trailing": None},
+        "project.name": {"leading": None, "trailing": "distribution name"},
+        "project.requires-python": {
+            "leading": "Supported interpreters",
+            "trailing": None,
+        },
+        "tool.checks[0]": {"leading": None, "trailing": "first check"},
+    }
+
+
+def test_get_comment(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = """\
+# Formatter settings
+[tool.black]
+# Wrap width
+line-length = 88  # PEP 8 is 79
+target = { py = "3.10" }  # oldest supported
+"""
+    assert tomllib.get_comment(doc, "tool.black", toml_version=toml_version) == {
+        "leading": "Formatter settings",
+        "trailing": None,
+    }
+    assert tomllib.get_comment(doc, "tool.black.line-length", toml_version=toml_version) == {
+        "leading": "Wrap width",
+        "trailing": "PEP 8 is 79",
+    }
+    assert tomllib.get_comment(doc, 'tool.black."target"', toml_version=toml_version) == {
+        "leading": None,
+        "trailing": "oldest supported",
+    }
+    assert tomllib.get_comment(doc, "tool.black.target.py", toml_version=toml_version) == {
+        "leading": None,
+        "trailing": None,
+    }
+    assert tomllib.get_comment(doc, "tool.isort", toml_version=toml_version) is None
+
+    with pytest.raises(ValueError, match="Invalid key path"):
+        tomllib.get_comment(doc, "tool..black")
+
+
+def test_get_raw(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = """\
+title = 'Ünïcode'
+
+[servers.alpha]
+ip = "10.0.0.1"  # primary
+limits.cpu = 2
+
+[servers.alpha.backup]
+ip = "10.0.0.2"
+
+[[jobs]]
+name = "a"
+[[jobs]]
+name = "b"
+"""
+
+    def raw(path: str) -> str | None:
+        found = tomllib.get_raw(doc, path, toml_version=toml_version)
+        if found is None:
+            return None
+        assert doc[found.start : found.end] == found.text
+        return found.text
+
+    assert raw("title") == "'Ünïcode'"
+    assert raw("servers.alpha") == (
+        '[servers.alpha]\nip = "10.0.0.1"  # primary\nlimits.cpu = 2'
+    )
+    assert raw("servers.alpha.limits") == "limits.cpu = 2"
+    assert raw("servers.alpha.backup.ip") == '"10.0.0.2"'
+    assert raw("servers") == doc[doc.index("[servers") : doc.index('2"') + 2]
+    assert raw("jobs") == '[[jobs]]\nname = "a"\n[[jobs]]\nname = "b"'
+    assert raw("servers.beta") is None
+
+
+def test_loads_preserve_format() -> None:
+    doc = """\
+hex = 0xDEAD_BEEF
+big = 1_000_000
+ratio = 6.626e-34
+literal = 'C:\\Users'
+when = 1979-05-27 07:32:00Z
+flag = true
+"""
+    obj = tomllib.loads(doc, preserve_format=True)
+
+    assert obj == tomllib.loads(doc)
+    assert isinstance(obj["hex"], items.Integer)
+    assert obj["hex"].raw == "0xDEAD_BEEF"
+    assert isinstance(obj["literal"], items.String)
+    assert obj["literal"].raw == "'C:\\Users'"
+    assert isinstance(obj["when"], datetime.datetime)
+    assert obj["when"].raw == "1979-05-27 07:32:00Z"
+    assert obj["flag"] is True
+
+    assert tomllib.dumps(obj) == doc
+
+    obj["big"] += 1
+    assert tomllib.dumps(obj).splitlines()[1] == "big = 1000001"
+
+
+@pytest.mark.parametrize(
+    ("data", "match"),
+    [
+        ("x = 07:32:00.123456789", "nanoseconds of '07:32:00.123456789'"),
+        ("x = 0.12345678901234567890", "float '0.12345678901234567890'"),
+    ],
+)
+def test_loads_lossy(
+        data: str,
+        match: str,
+        toml_version: tomllib._lib.TomlVersion,
+) -> None:
+    with pytest.warns(UserWarning, match=match):
+        tomllib.loads(data, toml_version=toml_version)
+
+    with pytest.raises(tomllib.TOMLInvalidValueError, match=match):
+        tomllib.loads(data, toml_version=toml_version, strict=True)
+
+
+def test_loads_lossless(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = "a = 07:32:00.123456\nb = 0.1\nc = 1e300\nd = 1.0000000000000000"
+    with warnings.catch_warnings():
+        warnings.simplefilter("error")
+        tomllib.loads(data, toml_version=toml_version, strict=True)
+
+
+@pytest.mark.parametrize("value", ["nan", "+nan", "-nan", "inf", "+inf", "-inf"])
+def test_loads_allow_nan(value: str, toml_version: tomllib._lib.TomlVersion) -> None:
+    data = f"x = {value}"
+    assert tomllib.loads(data, toml_version=toml_version)["x"] != 0
+
+    with pytest.raises(
+            tomllib.TOMLInvalidValueError,
+            match=f"non-finite float '{re.escape(value)}' is not allowed",
+    ) as exc_info:
+        tomllib.loads(data, toml_version=toml_version, allow_nan=False)
+    assert exc_info.value.pos == 4
+
+    assert tomllib.loads("x = 1.5", allow_nan=False) == {"x": 1.5}
+
+
+def test_loads_dumps_threads(toml_version: tomllib._lib.TomlVersion) -> None:
+    from concurrent.futures import ThreadPoolExecutor
+
+    data = "\n".join(f"[table{i}]\nkey = {i}\nvalue = 'x'" for i in range(200))
+    expected = tomllib.loads(data, toml_version=toml_version)
+
+    with ThreadPoolExecutor(max_workers=8) as pool:
+        loaded = list(pool.map(
+            lambda _: tomllib.loads(data, toml_version=toml_version), range(32),
+        ))
+        dumped = list(pool.map(
+            lambda obj: tomllib.dumps(obj, toml_version=toml_version), loaded,
+        ))
+
+    assert all(obj == expected for obj in loaded)
+    assert all(tomllib.loads(text) == expected for text in dumped)
+
+
+def test_allocator() -> None:
+    assert tomllib.allocator() in {"mimalloc", "snmalloc", "system"}
+
+
+def test_features() -> None:
+    features = tomllib.features()
+    assert features["version"] == tomllib.__version__
+    assert features["toml_versions"] == ("1.0.0", "1.1.0")
+    assert features["allocator"] == tomllib.allocator()
+    assert isinstance(features["free_threading"], bool)
+
+
+def test_loads_only(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = """
+    [project]
+    name = "demo"
+
+    [tool.ruff]
+    line-length = 90
+
+    [tool.mypy]
+    strict = true
+
+    [huge]
+    values = [1, 2, 3]
+    """
+    assert tomllib.loads(
+        data, toml_version=toml_version, only=["project", "tool.ruff", "missing"],
+    ) == {"project": {"name": "demo"}, "tool": {"ruff": {"line-length": 90}}}
+    assert tomllib.loads(data, toml_version=toml_version, only=[]) == {}
+
+    with pytest.raises(tomllib.TOMLDecodeError):
+        tomllib.loads(data + "x = ", toml_version=toml_version, only=["project"])
+
+    with pytest.raises(ValueError, match="Invalid key path"):
+        tomllib.loads(data, toml_version=toml_version, only=["tool..ruff"])
+
+
+def test_loads_intern_strings(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = "\n".join(f'[[package]]\nname = "pkg{i}"\nstatus = "ok"' for i in range(3))
+
+    loaded = tomllib.loads(data, toml_version=toml_version, intern_strings=True)
+    assert loaded == tomllib.loads(data, toml_version=toml_version)
+
+    statuses = [package["status"] for package in loaded["package"]]
+    assert statuses == ["ok"] * 3
+    assert all(status is statuses[0] for status in statuses)
+
+
+def test_loads_normalize_keys(toml_version: tomllib._lib.TomlVersion) -> None:
+    composed, decomposed = "caf\u00e9", "cafe\u0301"
+
+    loaded = tomllib.loads(
+        f'"{decomposed}" = 1\n[t]\n"{decomposed}" = 2\n',
+        toml_version=toml_version,
+        normalize_keys="nfc",
+    )
+    assert loaded == {composed: 1, "t": {composed: 2}}
+
+    data = f'"{composed}" = 1\n"{decomposed}" = 2\n'
+    assert tomllib.loads(data, toml_version=toml_version) == {composed: 1, decomposed: 2}
+    with pytest.raises(
+        tomllib.TOMLDuplicateKeyError, match="NFC normalization"
+    ) as exc_info:
+        tomllib.loads(data, toml_version=toml_version, normalize_keys="nfc")
+    assert exc_info.value.lineno == 2
+
+    with pytest.raises(ValueError, match="Unsupported normalize_keys: nfd"):
+        tomllib.loads(data, normalize_keys="nfd")  # type: ignore[arg-type]
+
+
+def test_loads_leap_seconds(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = "a = 2016-12-31T23:59:60Z\nb = 23:59:60.5\n"
+
+    with pytest.raises(tomllib.TOMLInvalidValueError, match="leap second") as exc_info:
+        tomllib.loads(data, toml_version=toml_version)
+    assert (exc_info.value.lineno, exc_info.value.colno) == (1, 5)
+
+    loaded = tomllib.loads(data, toml_version=toml_version, leap_seconds="clamp")
+    assert loaded == {
+        "a": datetime.datetime(2016, 12, 31, 23, 59, 59, tzinfo=datetime.timezone.utc),
+        "b": datetime.time(23, 59, 59, 500000),
+    }
+
+
+def test_loads_datetimes_iso_strings(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = _dedent("""
+        a = 1979-05-27 07:32:00.5+05:30
+        b = 1979-05-27T07:32:00z
+        c = 1979-05-27
+        d = 07:32:00
+        e = 2016-12-31T23:59:60Z
+    """)
+
+    loaded = tomllib.loads(data, toml_version=toml_version, datetimes="iso_strings")
+    assert loaded == {
+        "a": "1979-05-27T07:32:00.5+05:30",
+        "b": "1979-05-27T07:32:00Z",
+        "c": "1979-05-27",
+        "d": "07:32:00",
+        "e": "2016-12-31T23:59:60Z",
+    }
+
+    with pytest.raises(ValueError, match="Unsupported datetimes: text"):
+        tomllib.loads(data, datetimes="text")  # type: ignore[arg-type]
+
+
+def test_loads_value_hook(toml_version: tomllib._lib.TomlVersion) -> None:
+    seen: list[tuple[str | int, ...]] = []
+
+    def hook(path: tuple[str | int, ...], value: Any) -> Any:
+        seen.append(path)
+        if isinstance(value, str) and value.endswith("s"):
+            return datetime.timedelta(seconds=int(value[:-1]))
+        return value
+
+    data = 'timeout = "10s"\n[server]\nports = [80, 443]\n[[jobs]]\nname = "a"\n'
+    loaded = tomllib.loads(data, toml_version=toml_version, value_hook=hook)
+    assert loaded == {
+        "timeout": datetime.timedelta(seconds=10),
+        "server": {"ports": [80, 443]},
+        "jobs": [{"name": "a"}],
+    }
+    assert seen == [
+        ("timeout",),
+        ("server", "ports", 0),
+        ("server", "ports", 1),
+        ("jobs", 0, "name"),
+    ]
+
+    seen.clear()
+    tomllib.loads(data, toml_version=toml_version, only=["server"], value_hook=hook)
+    assert seen == [("server", "ports", 0), ("server", "ports", 1)]
+
+
+def test_loads_table_hook(toml_version: tomllib._lib.TomlVersion) -> None:
+    class Job:
+        def __init__(self, table: dict[str, Any]) -> None:
+            self.name = table["name"]
+
+    seen: list[str] = []
+
+    def hook(path: str, table: dict[str, Any]) -> Any:
+        seen.append(path)
+        return Job(table) if path == "job" else table
+
+    data = _dedent(
+        """
+        title = "ci"
+        [server]
+        limits = { cpu = 2 }
+        [[job]]
+        name = "build"
+        [[job]]
+        name = "test"
+        env = {}
+        """,
+    )
+    loaded = tomllib.loads(data, toml_version=toml_version, table_hook=hook)
+    assert loaded["server"] == {"limits": {"cpu": 2}}
+    assert [job.name for job in loaded["job"]] == ["build", "test"]
+    assert seen == ["server.limits", "server", "job", "job.env", "job"]
+
+    seen.clear()
+    tomllib.loads(data, toml_version=toml_version, only=["server"], table_hook=hook)
+    assert seen == ["server.limits", "server"]
+
+
+def test_loads_max_string_length(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = 'name = "short"\n[server]\nbanner = """\nwelcome"""\n'
+    assert tomllib.loads(data, toml_version=toml_version, max_string_length=7) == {
+        "name": "short",
+        "server": {"banner": "welcome"},
+    }
+
+    with pytest.raises(
+        tomllib.TOMLResourceLimitError,
+        match=re.escape(
+            "string of 7 characters exceeds max_string_length (6) at `server.banner`",
+        ),
+    ) as exc_info:
+        tomllib.loads(data, toml_version=toml_version, max_string_length=6)
+    assert (exc_info.value.lineno, exc_info.value.colno) == (3, 10)
+
+    assert tomllib.loads('a = "日本"', max_string_length=2) == {"a": "日本"}
+
+
+def test_loads_max_items(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = "a = 1\n[t]\nb = [1, 2]\nc = { d = 3 }\n"
+    # a, t, b, its two elements, c and d
+    assert tomllib.loads(data, toml_version=toml_version, max_items=7) == {
+        "a": 1,
+        "t": {"b": [1, 2], "c": {"d": 3}},
+    }
+
+    with pytest.raises(
+        tomllib.TOMLResourceLimitError,
+        match=re.escape("document exceeds max_items (6 keys and array elements) at `t.c`"),
+    ) as exc_info:
+        tomllib.loads(data, toml_version=toml_version, max_items=6)
+    assert (exc_info.value.lineno, exc_info.value.colno) == (4, 7)
+
+    with pytest.raises(tomllib.TOMLResourceLimitError):
+        tomllib.loads("x = [[[]], [], []]", toml_version=toml_version, max_items=4)
+
+
+def test_loads_max_depth(toml_version: tomllib._lib.TomlVersion) -> None:
+    deep = "a = " + "[" * 1000 + "]" * 1000
+    value = tomllib.loads(deep, toml_version=toml_version)["a"]
+    depth = 1
+    while value:
+        value = value[0]
+        depth += 1
+    assert depth == 1000
+
+    data = "[a.b]\nc = [[1], { d = 2 }]\n"
+    assert tomllib.loads(data, toml_version=toml_version, max_depth=4) == {
+        "a": {"b": {"c": [[1], {"d": 2}]}},
+    }
+    with pytest.raises(
+        tomllib.TOMLResourceLimitError,
+        match=re.escape("document nests deeper than max_depth (3) at `a.b.c`"),
+    ) as exc_info:
+        tomllib.loads(data, toml_version=toml_version, max_depth=3)
+    assert (exc_info.value.lineno, exc_info.value.colno) == (2, 6)
+
+    with pytest.raises(tomllib.TOMLResourceLimitError):
+        tomllib.loads(deep, toml_version=toml_version, max_depth=100)
+
+
+def test_loads_int_factory(toml_version: tomllib._lib.TomlVersion) -> None:
+    class Port(int):
+        pass
+
+    data = "port = 0x1F90\nbig = 18446744073709551616\nratio = 0.5\n"
+    loaded = tomllib.loads(data, toml_version=toml_version, int_factory=Port)
+    assert loaded == {"port": 8080, "big": 2**64, "ratio": 0.5}
+    assert type(loaded["port"]) is Port
+    assert type(loaded["big"]) is Port
+    assert type(loaded["ratio"]) is float
+
+    entries = "".join(f"[[t]]\nn = {i}\n" for i in range(2000))
+    loaded = tomllib.loads(entries, toml_version=toml_version, int_factory=Port)
+    assert all(type(entry["n"]) is Port for entry in loaded["t"])
+
+
+def test_loads_key_by(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = """
+    [[servers]]
+    name = "alpha"
+    ip = "10.0.0.1"
+
+    [[servers]]
+    name = "beta"
+    ip = "10.0.0.2"
+
+    [deploy]
+    [[deploy.targets]]
+    id = 1
+    """
+    loaded = tomllib.loads(
+        data,
+        toml_version=toml_version,
+        key_by={"servers": "name", "deploy.targets": "id", "missing": "name"},
+    )
+    assert loaded == {
+        "servers": {
+            "alpha": {"name": "alpha", "ip": "10.0.0.1"},
+            "beta": {"name": "beta", "ip": "10.0.0.2"},
+        },
+        "deploy": {"targets": {1: {"id": 1}}},
+    }
+
+    with pytest.raises(tomllib.TOMLDuplicateKeyError, match="duplicate 'name' 'a'"):
+        tomllib.loads(
+            '[[s]]\nname = "a"\n[[s]]\nname = "a"\n',
+            toml_version=toml_version,
+            key_by={"s": "name"},
+        )
+    with pytest.raises(tomllib.TOMLInvalidValueError, match="has no 'name' key"):
+        tomllib.loads(
+            "[[s]]\nid = 1\n", toml_version=toml_version, key_by={"s": "name"}
+        )
+    with pytest.raises(tomllib.TOMLInvalidValueError, match="not an array of tables"):
+        tomllib.loads("s = 1\n", toml_version=toml_version, key_by={"s": "name"})
+
+
+def test_loads_locations(tmp_path: Path, toml_version: tomllib._lib.TomlVersion) -> None:
+    data = '[server]\nhost = "localhost"\nports = [80, 443]\n'
+
+    loaded = tomllib.loads(data, toml_version=toml_version, locations=True)
+    host = loaded["server"]["host"]
+    assert isinstance(host, tomllib.Located)
+    assert (host.value, host.line, host.col, host.file) == ("localhost", 2, 8, None)
+    assert [(port.value, port.col) for port in loaded["server"]["ports"]] == [
+        (80, 10),
+        (443, 14),
+    ]
+    assert repr(host) == "Located('localhost', file=None, line=2, col=8)"
+
+    path = tmp_path / "server.toml"
+    path.write_text(data, encoding="utf-8")
+    loaded = tomllib.load_path(path, toml_version=toml_version, locations=True)
+    assert loaded["server"]["ports"][1].file == str(path)
+    assert loaded["server"]["ports"][1].line == 3
+
+
+def test_loads_keeps_crlf(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = 'a = """\r\nline\r\n"""\r\nb = "x"\r\nc = 0x\r\n'
+
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        tomllib.loads(data, toml_version=toml_version)
+    assert exc_info.value.pos == data.index("0x")
+    assert (exc_info.value.lineno, exc_info.value.colno) == (5, 5)
+
+    valid = data[: data.index("c =")]
+    loaded = tomllib.loads(valid, toml_version=toml_version, locations=True)
+    assert loaded["a"].value == "line\r\n"
+    assert (loaded["b"].line, loaded["b"].col) == (4, 5)
+
+
+def test_loads_shares_timezones(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = "a = 2024-01-01T00:00:00+02:00\nb = 2024-06-01T12:00:00+02:00\n"
+
+    loaded = tomllib.loads(data, toml_version=toml_version)
+    assert loaded["a"].tzinfo is loaded["b"].tzinfo
+    assert loaded["a"].utcoffset() == datetime.timedelta(hours=2)
+
+
+def test_iter_tables(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = """
+    title = "demo"
+
+    [server]
+    host = "localhost"
+
+    [[jobs]]
+    name = "a"
+
+    [[jobs]]
+    name = "b"
+    """
+    tables = tomllib.iter_tables(data, toml_version=toml_version)
+    assert next(tables) == ("title", "demo")
+    assert next(tables) == ("server", {"host": "localhost"})
+    assert list(tables) == [("jobs", [{"name": "a"}, {"name": "b"}])]
+
+    with pytest.raises(tomllib.TOMLDecodeError):
+        tomllib.iter_tables("[a]\nx = ", toml_version=toml_version)
+
+    dec = tomllib.iter_tables("x = 0.1", parse_float=Decimal, toml_version=toml_version)
+    assert list(dec) == [("x", Decimal("0.1"))]
+
+
+def test_iter_array_of_tables(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = _dedent('''
+        title = "log"
+
+        [[entry]]
+        level = "info"
+        message = """
+        [[entry]]
+        not a header
+        """
+
+        [entry.extra]
+        code = 1
+
+        [[entry]]
+        level = "error"
+
+        [other]
+        x = 1
+
+        [[entry]]
+        level = "debug"
+    ''')
+    expected = [
+        {
+            "level": "info",
+            "message": "[[entry]]\nnot a header\n",
+            "extra": {"code": 1},
+        },
+        {"level": "error"},
+        {"level": "debug"},
+    ]
+
+    entries = tomllib.iter_array_of_tables(data, "entry", toml_version=toml_version)
+    assert list(entries) == expected
+
+    with TemporaryDirectory() as tmp_dir_path:
+        file_path = Path(tmp_dir_path) / "log.toml"
+        file_path.write_text(data)
+
+        assert list(tomllib.iter_array_of_tables(file_path, "entry")) == expected
+        with file_path.open() as file:
+            assert list(tomllib.iter_array_of_tables(file, "entry")) == expected
+
+    assert list(tomllib.iter_array_of_tables(data, "missing")) == []
+
+    tricky = _dedent("""
+        [[entry]]
+        quote = '\"""'
+        note = '''
+        [other]
+        '''
+        grid = [
+        [1], [2],
+        ]
+        [[entry]]
+        x = 2
+    """)
+    entries = tomllib.iter_array_of_tables(tricky, "entry", toml_version=toml_version)
+    assert list(entries) == [
+        {"quote": '"""', "note": "[other]\n", "grid": [[1], [2]]},
+        {"x": 2},
+    ]
+
+    broken = "[[entry]]\nx = 1\n\n[[entry]]\nx = \n"
+    entries = tomllib.iter_array_of_tables(broken, "entry", toml_version=toml_version)
+    assert next(entries) == {"x": 1}
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        next(entries)
+    err = exc_info.value
+    assert err.doc == broken
+    assert err.lineno == 5
+    assert err.pos == broken.index("x = \n") + err.colno - 1
+
+    # A stream is not kept, but its positions still line up.
+    entries = tomllib.iter_array_of_tables(
+        io.StringIO(broken), "entry", toml_version=toml_version
+    )
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        list(entries)
+    assert exc_info.value.doc.endswith("[[entry]]\nx = \n")
+    assert (exc_info.value.pos, exc_info.value.lineno) == (err.pos, err.lineno)
+    assert exc_info.value.colno == err.colno
+
+    with pytest.raises(ValueError, match="Invalid key path"):
+        next(tomllib.iter_array_of_tables(data, "a..b"))
+
+
+def test_loads_chunks(toml_version: tomllib._lib.TomlVersion) -> None:
+    data = '[server]\nhost = "localhost"\nport = 8080\nname = "ünïcode"\n'
+
+    def chunks(size: int) -> Iterator[str]:
+        for i in range(0, len(data), size):
+            yield data[i:i + size]
+
+    expected = tomllib.loads(data, toml_version=toml_version)
+    for size in (1, 3, 7, len(data)):
+        assert tomllib.loads_chunks(chunks(size), toml_version=toml_version) == expected
+    assert tomllib.loads_chunks([], toml_version=toml_version) == {}
+
+    with pytest.raises(TypeError, match="Expected str chunks, not 'bytes'"):
+        tomllib.loads_chunks([b"x = 1"], toml_version=toml_version)  # type: ignore[list-item]
+
+
+def test_loads_front_matter(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = '+++\ntitle = "Hello"\ntags = ["a", "b"]\n+++\n# Heading\n\nBody +++ text\n'
+
+    data, body = tomllib.loads_front_matter(text, toml_version=toml_version)
+    assert data == {"title": "Hello", "tags": ["a", "b"]}
+    assert body == "# Heading\n\nBody +++ text\n"
+
+    assert tomllib.loads_front_matter("# No front matter\n") == ({}, "# No front matter\n")
+    assert tomllib.loads_front_matter("---\nx = 1\n---", delimiter="---") == ({"x": 1}, "")
+
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        tomllib.loads_front_matter("+++\nx = 1\ny = \n+++\nbody\n", toml_version=toml_version)
+    assert exc_info.value.lineno == 3
+    assert "line 3" in str(exc_info.value)
+
+    with pytest.raises(tomllib.TOMLSyntaxError, match="not closed"):
+        tomllib.loads_front_matter("+++\nx = 1\n")
+
+
+def test_loads_documents(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = "a = 1\n+++\n[t]\nb = 'x +++'\n+++\r\n\n+++\nc = 2"
+
+    documents = tomllib.loads_documents(text, toml_version=toml_version)
+    assert documents == [{"a": 1}, {"t": {"b": "x +++"}}, {}, {"c": 2}]
+    assert tomllib.loads_documents("x = 1", toml_version=toml_version) == [{"x": 1}]
+    assert tomllib.loads_documents("a = 1\n---\na = 2", "---") == [{"a": 1}, {"a": 2}]
+
+    with pytest.raises(tomllib.TOMLDecodeError) as exc_info:
+        tomllib.loads_documents("a = 1\n+++\nb = 2\nc = 0x\n", toml_version=toml_version)
+    assert (exc_info.value.lineno, exc_info.value.colno, exc_info.value.pos) == (4, 5, 20)
+    assert "line 4" in str(exc_info.value)
+
+
+def test_pyproject_dependencies(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = _dedent("""
+        [project]
+        name = "demo"
+        dependencies = [
+            # HTTP
+            "requests>=2.0",
+            "click",
+        ]
+    """)
+
+    text = tomllib.add_dependency(text, "rich>=13", toml_version=toml_version)
+    text = tomllib.add_dependency(text, "Requests>=2.31", toml_version=toml_version)
+    text = tomllib.remove_dependency(text, "click", toml_version=toml_version)
+    assert text == _dedent("""
+        [project]
+        name = "demo"
+        dependencies = [
+            # HTTP
+            "Requests>=2.31",
+            "rich>=13",
+        ]
+    """) + "\n"
+
+    text = tomllib.add_dependency(text, "pytest", group="test", toml_version=toml_version)
+    assert tomllib.loads(text)["project"]["optional-dependencies"] == {"test": ["pytest"]}
+
+    inline = '[project]\ndependencies = ["a", "b"]\n'
+    inline = tomllib.remove_dependency(inline, "a", toml_version=toml_version)
+    inline = tomllib.add_dependency(inline, "c[extra]; python_version < '3.12'")
+    assert inline == '[project]\ndependencies = ["b", "c[extra]; python_version < \'3.12\'"]\n'
+
+
+def test_pyproject_dependencies_poetry(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = _dedent("""
+        [tool.poetry.dependencies]
+        python = "^3.9"
+        Requests = "^2.0"  # http
+        httpx = { version = "^0.27", extras = ["http2"] }
+    """)
+
+    text = tomllib.add_dependency(text, "requests>=2.31", toml_version=toml_version)
+    text = tomllib.add_dependency(text, "httpx==0.28", toml_version=toml_version)
+    text = tomllib.add_dependency(text, "rich", toml_version=toml_version)
+    assert text == _dedent("""
+        [tool.poetry.dependencies]
+        python = "^3.9"
+        Requests = ">=2.31"  # http
+        httpx = { version = "==0.28", extras = ["http2"] }
+        rich = "*"
+    """) + "\n"
+
+    text = tomllib.remove_dependency(text, "requests", toml_version=toml_version)
+    text = tomllib.add_dependency(text, "pytest", group="dev", toml_version=toml_version)
+    poetry = tomllib.loads(text)["tool"]["poetry"]
+    assert list(poetry["dependencies"]) == ["python", "httpx", "rich"]
+    assert poetry["group"] == {"dev": {"dependencies": {"pytest": "*"}}}
+
+    with pytest.raises(ValueError, match="extras and markers are not supported"):
+        tomllib.add_dependency(text, "rich[jupyter]", toml_version=toml_version)
+
+
+def test_array_edits(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = _dedent("""
+        [tool.ruff]
+        select = ["E", "F"]
+        ignore = [
+            "E501",  # line length
+        ]
+    """)
+
+    text = tomllib.array_append(text, "tool.ruff.select", "I", toml_version=toml_version)
+    text = tomllib.array_remove(text, "tool.ruff.select", "E", toml_version=toml_version)
+    text = tomllib.array_insert(text, "tool.ruff.ignore", 0, "W", toml_version=toml_version)
+    text = tomllib.array_append(text, "tool.ruff.fixable", "ALL", toml_version=toml_version)
+    assert text == _dedent("""
+        [tool.ruff]
+        select = ["F", "I"]
+        ignore = [
+            "W",
+            "E501",  # line length
+        ]
+        fixable = ["ALL"]
+    """) + "\n"
+
+    text = tomllib.array_insert("x = [1, 3]\n", "x", -1, 2, toml_version=toml_version)
+    assert text == "x = [1, 2, 3]\n"
+
+    with pytest.raises(ValueError, match="4 not in 'x'"):
+        tomllib.array_remove(text, "x", 4, toml_version=toml_version)
+    with pytest.raises(ValueError, match="is not an array"):
+        tomllib.array_append("x = 1\n", "x", 2, toml_version=toml_version)
+
+
+def test_array_append_reflow(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = tomllib.array_append(
+        'x = ["aaaa", "bbbb"]\n',
+        "x",
+        "cccc",
+        line_width=20,
+        toml_version=toml_version,
+    )
+    assert text == 'x = [\n    "aaaa",\n    "bbbb",\n    "cccc",\n]\n'
+
+
+def test_tokenize(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = '[server]\n"ключ" = "ü"  # note\nports = [80, 443]\nup = true\nat = 1979-05-27\n'
+    tokens = tomllib.tokenize(text, toml_version=toml_version)
+
+    assert "".join(token.text for token in tokens) == text
+    assert all(text[token.start:token.end] == token.text for token in tokens)
+    assert [
+        (token.kind, token.text)
+        for token in tokens
+        if token.kind not in {"whitespace", "newline"}
+    ] == [
+        ("punctuation", "["),
+        ("key", "server"),
+        ("punctuation", "]"),
+        ("key", '"ключ"'),
+        ("punctuation", "="),
+        ("string", '"ü"'),
+        ("comment", "# note"),
+        ("key", "ports"),
+        ("punctuation", "="),
+        ("punctuation", "["),
+        ("number", "80"),
+        ("punctuation", ","),
+        ("number", "443"),
+        ("punctuation", "]"),
+        ("key", "up"),
+        ("punctuation", "="),
+        ("boolean", "true"),
+        ("key", "at"),
+        ("punctuation", "="),
+        ("datetime", "1979-05-27"),
+    ]
+
+    tokens = tomllib.tokenize("a = = 1\n", toml_version=toml_version)
+    assert ("error", "=") in {(token.kind, token.text) for token in tokens}
+
+
+def test_load_fd(toml_version: tomllib._lib.TomlVersion) -> None:
+    read_fd, write_fd = os.pipe()
+    try:
+        os.write(write_fd, "name = 'ünïcode'\nport = 8080\n".encode())
+        os.close(write_fd)
+        write_fd = -1
+
+        assert tomllib.load_fd(read_fd, toml_version=toml_version) == {
+            "name": "ünïcode",
+            "port": 8080,
+        }
+        # The descriptor stays owned by the caller.
+        os.fstat(read_fd)
+    finally:
+        os.close(read_fd)
+        if write_fd != -1:
+            os.close(write_fd)
+
+    with pytest.raises(OSError, match="Bad file descriptor"):
+        tomllib.load_fd(-1, toml_version=toml_version)
+    with pytest.raises(OSError, match="Bad file descriptor"):
+        tomllib.load_fd(read_fd, toml_version=toml_version)
+
+
+def test_dump_template(toml_version: tomllib._lib.TomlVersion) -> None:
+    def snippet(name: str, port: Any, tags: list[str]) -> dict[str, Any]:
+        return {
+            "name": name,
+            "enabled": bool(port),
+            "server": {"port": port, "tags": tags},
+            "backends": [{"host": f"{name}-1"}, {"host": f"{name}-2"}],
+        }
+
+    template = tomllib.DumpTemplate.from_example(
+        snippet("api", 80, ["a"]), toml_version=toml_version
+    )
+    for values in (
+        snippet("web", 8080, ["x", "y"]),
+        snippet('quote "me"', -1, []),
+        snippet("tab\there", 2**70, ["\u00e9"]),
+    ):
+        assert template.dumps(values) == tomllib.dumps(values, toml_version=toml_version)
+
+    with pytest.raises(ValueError, match=r"server\.port does not match.*got str"):
+        template.dumps(snippet("web", "80", []))
+    values = snippet("web", 80, [])
+    values["backends"].pop()
+    with pytest.raises(ValueError, match="backends does not match.*list of 2 tables"):
+        template.dumps(values)
+    values = snippet("web", 80, [])
+    values["server"]["extra"] = 1
+    with pytest.raises(ValueError, match="server does not match.*table of 2 keys"):
+        template.dumps(values)
+
+
+def test_config_stack(toml_version: tomllib._lib.TomlVersion) -> None:
+    with TemporaryDirectory() as tmp:
+        path = Path(tmp) / "app.toml"
+        path.write_text('[db]\nhost = "db.local"\nport = 5432\n', encoding="utf-8")
+
+        stack = tomllib.ConfigStack(
+            {"debug": False, "db": {"host": "localhost", "user": "app"}},
+            toml_version=toml_version,
+        )
+        stack.add_file(path)
+        stack.add_file(Path(tmp) / "missing.toml", required=False)
+        stack.add_env(
+            "APP_",
+            {"APP_DB__PORT": "6543", "APP_DEBUG": "true", "APP_NAME": "a b", "HOME": "/"},
+        )
+
+    assert stack.data == {
+        "debug": True,
+        "db": {"host": "db.local", "user": "app", "port": 6543},
+        "name": "a b",
+    }
+    assert stack.source("db.user") == "defaults"
+    assert stack.source("db.host") == str(path)
+    assert stack.source("db.port") == "env:APP_DB__PORT"
+    assert stack.source("home") is None
+
+    with pytest.raises(FileNotFoundError):
+        stack.add_file(path)
+
+
+def test_config_stack_resolver(toml_version: tomllib._lib.TomlVersion) -> None:
+    files = {"base": '[db]\nhost = "localhost"\n', "prod": '[db]\nhost = "db.prod"\n'}
+    resolved = []
+
+    def resolver(name: str) -> str | None:
+        resolved.append(name)
+        return files.get(name)
+
+    stack = tomllib.ConfigStack(toml_version=toml_version, resolver=resolver)
+    stack.add_file("base")
+    stack.add_file("prod")
+    stack.add_file("local", required=False)
+
+    assert stack.data == {"db": {"host": "db.prod"}}
+    assert stack.source("db.host") == "prod"
+    assert resolved == ["base", "prod", "local"]
+    with pytest.raises(FileNotFoundError, match='no file named "local"'):
+        stack.add_file("local")
+
+
+def test_toml_writer(toml_version: tomllib._lib.TomlVersion) -> None:
+    with TemporaryDirectory() as tmp:
+        path = Path(tmp) / "out.toml"
+        with tomllib.TomlWriter(path, toml_version=toml_version) as writer:
+            writer.key("title", "export")
+            writer.table("db.primary")
+            writer.key("hosts", ["a", "b"])
+            writer.key("opts", {"tls": True})
+            for index in range(2):
+                writer.array_of_tables("rows")
+                writer.key("id", index)
+                writer.table("rows.extra")
+                writer.key("note", "x y")
+            writer.table("db")
+            writer.key("my key", 1)
+
+            with pytest.raises(ValueError, match="is already written"):
+                writer.key("my key", 2)
+            with pytest.raises(ValueError, match="already written as a table"):
+                writer.table("db.primary")
+            with pytest.raises(ValueError, match="already written as a table"):
+                writer.array_of_tables("db")
+            with pytest.raises(ValueError, match='"my key" is a value'):
+                writer.table('db."my key".sub')
+
+        assert writer.closed
+        with pytest.raises(ValueError, match="closed"):
+            writer.key("late", 1)
+
+        text = path.read_text(encoding="utf-8")
+
+    assert text == _dedent("""
+        title = "export"
+
+        [db.primary]
+        hosts = ["a", "b"]
+        opts = { tls = true }
+
+        [[rows]]
+        id = 0
+
+        [rows.extra]
+        note = "x y"
+
+        [[rows]]
+        id = 1
+
+        [rows.extra]
+        note = "x y"
+
+        [db]
+        "my key" = 1
+    """) + "\n"
+    assert tomllib.loads(text, toml_version=toml_version)["rows"][1] == {
+        "id": 1,
+        "extra": {"note": "x y"},
+    }
+
+
+def test_deep_merge() -> None:
+    base = {
+        "name": "app",
+        "db": {"host": "localhost", "port": 5432},
+        "plugins": ["a", "b"],
+    }
+    overlay = {"db": {"port": 6543}, "plugins": ["b", "c"], "debug": True}
+
+    assert tomllib.deep_merge(base, overlay) == {
+        "name": "app",
+        "db": {"host": "localhost", "port": 6543},
+        "plugins": ["b", "c"],
+        "debug": True,
+    }
+    assert tomllib.deep_merge(base, overlay, arrays="append")["plugins"] == [
+        "a",
+        "b",
+        "b",
+        "c",
+    ]
+    assert tomllib.deep_merge(base, overlay, arrays="unique")["plugins"] == [
+        "a",
+        "b",
+        "c",
+    ]
+    assert tomllib.deep_merge(base, {"db": "sqlite"}) == {**base, "db": "sqlite"}
+    assert base["db"] == {"host": "localhost", "port": 5432}
+    assert base["plugins"] == ["a", "b"]
+
+    with pytest.raises(ValueError, match="Unsupported arrays policy: merge"):
+        tomllib.deep_merge(base, overlay, arrays="merge")  # type: ignore[arg-type]
+
+
+def test_infer_schema(toml_version: tomllib._lib.TomlVersion) -> None:
+    first = _dedent("""
+    name = "api"
+    port = 8080
+    level = "info"
+    started = 2024-01-01T00:00:00Z
+
+    [[hosts]]
+    addr = "10.0.0.1"
+    """)
+    second = _dedent("""
+    name = "web"
+    port = 80.5
+    level = "info"
+    started = 2024-01-02T00:00:00Z
+    debug = true
+    """)
+
+    assert tomllib.infer_schema([first, second], toml_version=toml_version) == {
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "port": {"type": "number"},
+            "level": {"type": "string", "enum": ["info"]},
+            "started": {"type": "string", "format": "date-time"},
+            "hosts": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {"addr": {"type": "string"}},
+                    "required": ["addr"],
+                },
+            },
+            "debug": {"type": "boolean"},
+        },
+        "required": ["name", "port", "level", "started"],
+    }
+    schema = tomllib.infer_schema(first, toml_version=toml_version)
+    assert schema["properties"]["port"] == {"type": "integer"}
+
+
+def test_compiled_schema(toml_version: tomllib._lib.TomlVersion) -> None:
+    first = 'name = "api"\nport = 8080\nlevel = "info"\n[[hosts]]\naddr = "10.0.0.1"\n'
+    second = 'name = "web"\nport = 80.5\nlevel = "info"\nstarted = 2024-01-02\n'
+    schema = tomllib.Schema.compile(
+        tomllib.infer_schema([first, 'name = "api"\nport = 1\nlevel = "info"'])
+        | {"title": "Service"}
+    )
+
+    assert tomllib.loads(first, toml_version=toml_version, schema=schema)["port"] == 8080
+
+    invalid = {
+        'name = "api"\nport = "80"\nlevel = "info"': (
+            'Invalid value for "port": expected integer, got string'
+        ),
+        'port = 80\nlevel = "info"': "missing required key \"name\"",
+        'name = "api"\nport = 80\nlevel = "warn"': "'warn' is not one of the allowed",
+        'name = "api"\nport = 80\nlevel = "info"\n[[hosts]]': (
+            'Invalid value for "hosts\\[0\\]": missing required key "addr"'
+        ),
+    }
+    for text, message in invalid.items():
+        with pytest.raises(ValueError, match=message):
+            tomllib.loads(text, toml_version=toml_version, schema=schema)
+
+    dated = tomllib.Schema.compile(
+        {"properties": {"started": {"type": "string", "format": "date-time"}}}
+    )
+    with pytest.raises(ValueError, match="expected date-time, got date"):
+        tomllib.loads(second, toml_version=toml_version, schema=dated)
+
+    with pytest.raises(ValueError, match='Unsupported schema keyword "minimum"'):
+        tomllib.Schema.compile({"properties": {"port": {"minimum": 1}}})
+
+
+def test_schema_to_typeddict() -> None:
+    schema = {
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "level": {"type": "string", "enum": ["debug", "info"]},
+            "started": {"type": "string", "format": "date-time"},
+            "port": {"type": ["integer", "string"]},
+            "hosts": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {"addr": {"type": "string"}},
+                    "required": ["addr"],
+                },
+            },
+            "tool": {
+                "type": "object",
+                "properties": {"python-version": {"type": "string"}},
+            },
+        },
+        "required": ["name", "level", "started", "port"],
+    }
+
+    assert tomllib.schema_to_typeddict(schema) == _dedent("""
+    import datetime
+    from typing import Literal, NotRequired, TypedDict
+
+
+    class ConfigHostsItem(TypedDict):
+        addr: str
+
+
+    ConfigTool = TypedDict(
+        "ConfigTool",
+        {
+            "python-version": NotRequired[str],
+        },
+    )
+
+
+    class Config(TypedDict):
+        name: str
+        level: Literal["debug", "info"]
+        started: datetime.datetime
+        port: int | str
+        hosts: NotRequired[list[ConfigHostsItem]]
+        tool: NotRequired[ConfigTool]
+    """) + "\n"
+
+    source = tomllib.schema_to_typeddict(
+        tomllib.infer_schema('title = "x"\n'), name="Settings"
+    )
+    assert source.endswith("class Settings(TypedDict):\n    title: str\n")
+
+
+def test_watch(toml_version: tomllib._lib.TomlVersion) -> None:
+    results: queue.Queue[tuple[dict[str, Any] | None, Exception | None]] = queue.Queue()
+
+    with TemporaryDirectory() as tmp:
+        path = Path(tmp) / "app.toml"
+        path.write_text("port = 1\n", encoding="utf-8")
+
+        with tomllib.watch(
+            path,
+            lambda data, error: results.put((data, error)),
+            debounce=0.05,
+            toml_version=toml_version,
+        ) as watcher:
+            assert watcher.running
+            path.write_text("port = 2\n", encoding="utf-8")
+            assert results.get(timeout=5) == ({"port": 2}, None)
+
+            path.write_text("port = \n", encoding="utf-8")
+            data, error = results.get(timeout=5)
+            assert data is None
+            assert isinstance(error, tomllib.TOMLDecodeError)
+
+        assert not watcher.running
+
+
+def test_watch_without_stop(toml_version: tomllib._lib.TomlVersion) -> None:
+    results: queue.Queue[object] = queue.Queue()
+
+    with TemporaryDirectory() as tmp:
+        path = Path(tmp) / "app.toml"
+        path.write_text("port = 1\n", encoding="utf-8")
+
+        # A watcher that is dropped stops, and one still running at exit is
+        # stopped before the interpreter goes away.
+        watcher = tomllib.watch(path, lambda *args: results.put(args), debounce=0.05)
+        del watcher
+        path.write_text("port = 2\n", encoding="utf-8")
+        with pytest.raises(queue.Empty):
+            results.get(timeout=0.5)
+
+        script = (
+            "import sys, toml_rs\n"
+            "w = toml_rs.watch(sys.argv[1], print, debounce=0, "
+            "toml_version=sys.argv[2])\n"
+            "open(sys.argv[1], 'w').write('port = 3\\n')\n"
+        )
+        proc = subprocess.run(
+            [sys.executable, "-c", script, str(path), toml_version],
+            capture_output=True,
+            text=True,
+            timeout=30,
+            check=False,
+        )
+        assert proc.returncode == 0
+        assert proc.stderr == ""
+
+
+def test_tomlkit_compat(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = tomlkit.parse(
+        _dedent("""
+            # settings
+            title = "app"  # name
+
+            [db]
+            port = 5432
+        """),
+        toml_version=toml_version,
+    )
+    assert doc["title"] == "app"
+    doc["db"]["port"] = 5433
+    doc["title"] = "svc"
+    doc["db"].comment("primary")
+
+    text = tomlkit.dumps(doc)
+    assert text.startswith('# settings\ntitle = "svc"  # name\n')
+    assert "[db] # primary\n" in text
+    assert tomllib.loads(text) == {"title": "svc", "db": {"port": 5433}}
+
+    doc = tomlkit.document(toml_version=toml_version)
+    doc.add(tomlkit.comment("generated"))
+    doc.add("name", "demo")
+    with pytest.raises(KeyError):
+        doc.add("name", "other")
+
+    deps = tomlkit.table(toml_version=toml_version)
+    deps.add("pyo3", "0.29")
+    doc.add("deps", deps.comment("runtime"))
+    doc["ports"] = tomlkit.array("[80, 443]", toml_version=toml_version)
+    doc["ports"].append(8080)
+
+    text = tomlkit.dumps(doc)
+    assert text.startswith('# generated\nname = "demo"\n')
+    assert "[deps] # runtime\n" in text
+    assert tomllib.loads(text) == {
+        "name": "demo",
+        "ports": [80, 443, 8080],
+        "deps": {"pyo3": "0.29"},
+    }
+
+
+def test_tomlkit_move(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = tomlkit.parse(
+        _dedent("""
+            [tool.black]
+            line-length = 88
+
+            # import sorting
+            [tool.isort]
+            profile = "black"
+
+            [tool.ruff]
+            line-length = 90
+
+            [tool.ruff.lint]
+            select = ["E"]
+        """),
+        toml_version=toml_version,
+    )
+
+    doc.move("tool.ruff", before="tool.black")
+    assert tomlkit.dumps(doc) == _dedent("""
+        [tool.ruff]
+        line-length = 90
+
+        [tool.ruff.lint]
+        select = ["E"]
+
+        [tool.black]
+        line-length = 88
+
+        # import sorting
+        [tool.isort]
+        profile = "black"
+    """)
+
+    doc["tool"].reorder(["isort", "black"])
+    assert tomlkit.dumps(doc).endswith(
+        '# import sorting\n[tool.isort]\nprofile = "black"\n\n'
+        "[tool.black]\nline-length = 88\n"
+    )
+
+    with pytest.raises(KeyError, match="Table not found"):
+        doc.move("tool.mypy", after="tool.black")
+    with pytest.raises(ValueError, match="exactly one of before and after"):
+        doc.move("tool.ruff", before="tool.black", after="tool.isort")
+
+
+def test_tomlkit_trivia(toml_version: tomllib._lib.TomlVersion) -> None:
+    doc = tomlkit.parse(
+        _dedent("""
+            # the name
+            name   =  "demo"  # short
+
+            [tool]  # tools
+            x = 1
+        """),
+        toml_version=toml_version,
+    )
+
+    assert doc.trivia("name") == {
+        "before": "# the name\n",
+        "before_eq": "   ",
+        "after_eq": "  ",
+        "after": "  # short",
+    }
+    assert doc.trivia("tool") == {"before": "\n", "after": "  # tools"}
+
+    doc.set_trivia("name", before="", before_eq=" ", after_eq=" ", after="")
+    doc.set_trivia("tool", before="\n# tools\n", after="")
+    doc["tool"].set_trivia("x", before="    ")
+    assert tomlkit.dumps(doc) == 'name = "demo"\n\n# tools\n[tool]\n    x = 1\n'
+
+    with pytest.raises(ValueError, match="Invalid before"):
+        doc.set_trivia("name", before="y = 2\n")
+    with pytest.raises(ValueError, match="has no `=`"):
+        doc.set_trivia("tool", after_eq=" ")
+    with pytest.raises(KeyError, match="Key path not found"):
+        doc.trivia("missing")
+
+
+def test_binary_bridge(toml_version: tomllib._lib.TomlVersion) -> None:
+    text = _dedent("""
+        title = "demo"
+        when = 1979-05-27T07:32:00Z
+        ports = [80, 443]
+        ratio = 0.5
+
+        [db]
+        enabled = true
+
+        [[servers]]
+        name = "a"
+    """)
+    data = tomllib.loads(text, toml_version=toml_version)
+
+    packed = tomllib.to_msgpack(text, toml_version=toml_version)
+    assert packed[:1] == b"\x86"
+    unpacked = tomllib.from_msgpack(packed, toml_version=toml_version)
+    assert tomllib.loads(unpacked) == {**data, "when": "1979-05-27T07:32:00Z"}
+
+    encoded = tomllib.to_cbor(text, toml_version=toml_version)
+    decoded = tomllib.from_cbor(encoded, toml_version=toml_version)
+    assert tomllib.loads(decoded, toml_version=toml_version) == data
+
+    with pytest.raises(ValueError, match="no null value"):
+        tomllib.from_msgpack(b"\x81\xa1a\xc0", toml_version=toml_version)
+    with pytest.raises(ValueError, match="trailing bytes"):
+        tomllib.from_cbor(encoded + b"\x00", toml_version=toml_version)
+    with pytest.raises(ValueError, match="map at the top level"):
+        tomllib.from_cbor(b"\x01", toml_version=toml_version)